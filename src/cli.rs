@@ -1,12 +1,18 @@
-use crate::config::{Config, load_config};
-use crate::layout::compute_layout_with_metrics;
+use crate::config::{
+    ActivationZ, Config, CycleBreaking, EdgeCurve, LabelOverflow, LabelShape, LineCap, LineJoin,
+    RankAlign, TickFormat, load_config_with_theme,
+};
+use crate::ir::NodeShape;
+use crate::layout::{check_size_limits, compute_layout_with_metrics, take_orphan_edges};
 use crate::layout_dump::write_layout_dump;
 use crate::parser::parse_mermaid;
 #[cfg(feature = "png")]
 use crate::render::write_output_png;
-use crate::render::{render_svg, write_output_svg};
+use crate::render::{render_svg_with_source, write_output_svg};
+use crate::theme::Theme;
 use anyhow::Result;
 use clap::{Parser, ValueEnum};
+use std::collections::HashMap;
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
@@ -33,6 +39,10 @@ pub struct Args {
     #[arg(short = 'c', long = "configFile")]
     pub config: Option<PathBuf>,
 
+    /// Theme (default, modern or dark)
+    #[arg(short = 't', long = "theme", value_parser = parse_theme_value)]
+    pub theme: Option<Theme>,
+
     /// Width
     #[arg(short = 'w', long = "width", default_value_t = 1200.0)]
     pub width: f32,
@@ -72,6 +82,10 @@ pub enum OutputFormat {
     Png,
 }
 
+fn parse_theme_value(raw: &str) -> Result<Theme, String> {
+    Theme::from_name(raw.trim()).ok_or_else(|| format!("unknown theme `{}`", raw.trim()))
+}
+
 fn parse_aspect_ratio_value(raw: &str) -> Result<f32, String> {
     let value = raw.trim();
     if value.is_empty() {
@@ -132,7 +146,8 @@ fn parse_aspect_ratio_json(value: &serde_json::Value) -> Option<f32> {
 
 pub fn run() -> Result<()> {
     let args = Args::parse();
-    let mut base_config = load_config(args.config.as_deref())?;
+    let theme = args.theme.clone().unwrap_or_else(Theme::mermaid_default);
+    let mut base_config = load_config_with_theme(args.config.as_deref(), theme)?;
     base_config.render.width = args.width;
     base_config.render.height = args.height;
     if let Some(ratio) = args.preferred_aspect_ratio {
@@ -147,6 +162,11 @@ pub fn run() -> Result<()> {
     if args.fast_text_metrics {
         base_config.layout.fast_text_metrics = true;
     }
+    // resvg does not resolve `var()` in presentation attributes, so PNGs are
+    // drawn with the plain theme colors.
+    if matches!(args.output_format, OutputFormat::Png) {
+        base_config.render.css_variables = false;
+    }
 
     let (input, is_markdown) = read_input(args.input.as_deref())?;
     let diagrams = if is_markdown {
@@ -170,15 +190,19 @@ pub fn run() -> Result<()> {
 
     if diagrams.len() == 1 {
         let t_parse_start = std::time::Instant::now();
-        let parsed = parse_mermaid(&diagrams[0])?;
+        let mut parsed = parse_mermaid(&diagrams[0])?;
         let parse_us = t_parse_start.elapsed().as_micros();
+        print_parse_warnings(&parsed.warnings, None);
 
         let mut config = base_config.clone();
-        if let Some(init_cfg) = parsed.init_config {
+        if let Some(init_cfg) = parsed.init_config.take() {
             config = merge_init_config(config, init_cfg);
         }
+        dedupe_flowchart_edges(&mut parsed.graph, &config, None);
+        report_orphan_edges(&mut parsed.graph, None);
 
         let t_layout_start = std::time::Instant::now();
+        check_size_limits(&parsed.graph, &config.layout)?;
         let (layout, layout_stages) =
             compute_layout_with_metrics(&parsed.graph, &config.theme, &config.layout);
         let layout_us = t_layout_start.elapsed().as_micros();
@@ -190,7 +214,13 @@ pub fn run() -> Result<()> {
         }
 
         let t_render_start = std::time::Instant::now();
-        let svg = render_svg(&layout, &config.theme, &config.layout);
+        let svg = render_svg_with_source(
+            &layout,
+            &config.theme,
+            &config.layout,
+            &config.render,
+            Some(&diagrams[0]),
+        );
         let render_us = t_render_start.elapsed().as_micros();
 
         match args.output_format {
@@ -222,6 +252,12 @@ pub fn run() -> Result<()> {
                     "edge_routing_us": layout_stages.edge_routing_us,
                     "label_placement_us": layout_stages.label_placement_us,
                     "total_us": layout_stages.total_us(),
+                },
+                "layout_counts": {
+                    "order_passes_run": layout_stages.counts.order_passes_run,
+                    "overlap_iterations": layout_stages.counts.overlap_iterations,
+                    "edges_rerouted": layout_stages.counts.edges_rerouted,
+                    "dummy_nodes_created": layout_stages.counts.dummy_nodes_created,
                 }
             });
             eprintln!("{payload}");
@@ -233,11 +269,15 @@ pub fn run() -> Result<()> {
     let outputs =
         resolve_multi_outputs(args.output.as_deref(), args.output_format, diagrams.len())?;
     for (idx, diagram) in diagrams.iter().enumerate() {
-        let parsed = parse_mermaid(diagram)?;
+        let mut parsed = parse_mermaid(diagram)?;
+        print_parse_warnings(&parsed.warnings, Some(idx));
         let mut config = base_config.clone();
-        if let Some(init_cfg) = parsed.init_config.clone() {
+        if let Some(init_cfg) = parsed.init_config.take() {
             config = merge_init_config(config, init_cfg);
         }
+        dedupe_flowchart_edges(&mut parsed.graph, &config, Some(idx));
+        report_orphan_edges(&mut parsed.graph, Some(idx));
+        check_size_limits(&parsed.graph, &config.layout)?;
         let (layout, _layout_stages) =
             compute_layout_with_metrics(&parsed.graph, &config.theme, &config.layout);
         if let Some(outputs) = layout_outputs.as_ref()
@@ -245,7 +285,13 @@ pub fn run() -> Result<()> {
         {
             write_layout_dump(path, &layout, &parsed.graph)?;
         }
-        let svg = render_svg(&layout, &config.theme, &config.layout);
+        let svg = render_svg_with_source(
+            &layout,
+            &config.theme,
+            &config.layout,
+            &config.render,
+            Some(diagram),
+        );
         match args.output_format {
             OutputFormat::Svg => {
                 write_output_svg(&svg, Some(&outputs[idx]))?;
@@ -266,6 +312,40 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+fn print_parse_warnings(warnings: &[String], diagram: Option<usize>) {
+    let which = diagram
+        .map(|idx| format!(" in diagram {}", idx + 1))
+        .unwrap_or_default();
+    for warning in warnings {
+        eprintln!("warning: {warning}{which}");
+    }
+}
+
+fn dedupe_flowchart_edges(graph: &mut crate::ir::Graph, config: &Config, diagram: Option<usize>) {
+    if !config.layout.flowchart.dedupe_edges || graph.kind != crate::ir::DiagramKind::Flowchart {
+        return;
+    }
+    let removed = graph.dedupe_edges();
+    if removed > 0 {
+        let which = diagram
+            .map(|idx| format!(" in diagram {}", idx + 1))
+            .unwrap_or_default();
+        eprintln!("warning: merged {removed} duplicate edge(s){which}");
+    }
+}
+
+fn report_orphan_edges(graph: &mut crate::ir::Graph, diagram: Option<usize>) {
+    let which = diagram
+        .map(|idx| format!(" in diagram {}", idx + 1))
+        .unwrap_or_default();
+    for edge in take_orphan_edges(graph) {
+        eprintln!(
+            "warning: skipped edge {} -> {} with an undeclared endpoint{which}",
+            edge.from, edge.to
+        );
+    }
+}
+
 fn read_input(path: Option<&Path>) -> Result<(String, bool)> {
     if let Some(path) = path {
         if path == Path::new("-") {
@@ -458,7 +538,10 @@ mod tests {
         assert_eq!(merged.theme.cluster_background, "#333333");
         assert_eq!(merged.theme.cluster_border, "#444444");
         assert_eq!(merged.theme.background, "#101010");
-        assert_eq!(merged.render.background, "#101010");
+        assert_eq!(
+            merged.render.background_color(&merged.theme),
+            Some("#101010")
+        );
     }
 
     #[test]
@@ -468,6 +551,12 @@ mod tests {
         assert_eq!(parse_aspect_ratio_value("1.5").unwrap(), 1.5);
     }
 
+    #[test]
+    fn parse_theme_value_accepts_dark() {
+        assert_eq!(parse_theme_value("dark").unwrap(), Theme::dark());
+        assert!(parse_theme_value("forest").is_err());
+    }
+
     #[test]
     fn merge_init_config_updates_preferred_aspect_ratio() {
         let config = Config::default();
@@ -480,12 +569,12 @@ mod tests {
 }
 
 fn merge_init_config(mut config: Config, init: serde_json::Value) -> Config {
-    if let Some(theme_name) = init.get("theme").and_then(|v| v.as_str()) {
-        if theme_name == "modern" {
-            config.theme = crate::theme::Theme::modern();
-        } else if theme_name == "base" || theme_name == "default" || theme_name == "mermaid" {
-            config.theme = crate::theme::Theme::mermaid_default();
-        }
+    if let Some(theme) = init
+        .get("theme")
+        .and_then(|v| v.as_str())
+        .and_then(Theme::from_name)
+    {
+        config.theme = theme;
     }
     if let Some(theme_vars) = init.get("themeVariables") {
         let tag_label_border_explicit = theme_vars
@@ -693,6 +782,152 @@ fn merge_init_config(mut config: Config, init: serde_json::Value) -> Config {
         if let Some(val) = flowchart.get("portSideBias").and_then(|v| v.as_f64()) {
             config.layout.flowchart.port_side_bias = val as f32;
         }
+        if let Some(val) = flowchart.get("centerChildren").and_then(|v| v.as_bool()) {
+            config.layout.flowchart.center_children = val;
+        }
+        if let Some(val) = flowchart
+            .get("cycleBreaking")
+            .and_then(|v| serde_json::from_value::<CycleBreaking>(v.clone()).ok())
+        {
+            config.layout.flowchart.cycle_breaking = val;
+        }
+        if let Some(val) = flowchart
+            .get("rankAlignment")
+            .and_then(|v| serde_json::from_value::<RankAlign>(v.clone()).ok())
+        {
+            config.layout.flowchart.rank_alignment = val;
+        }
+        if let Some(val) = flowchart
+            .get("shapePadding")
+            .and_then(|v| serde_json::from_value::<HashMap<NodeShape, (f32, f32)>>(v.clone()).ok())
+        {
+            config.layout.flowchart.shape_padding.extend(val);
+        }
+        if let Some(val) = flowchart.get("rotateEdgeLabels").and_then(|v| v.as_bool()) {
+            config.layout.flowchart.rotate_edge_labels = val;
+        }
+        if let Some(val) = flowchart.get("maxSubgraphDepth").and_then(|v| v.as_u64()) {
+            config.layout.flowchart.max_subgraph_depth = Some(val as usize);
+        }
+        if let Some(val) = flowchart.get("edgeLabelOffset").and_then(|v| v.as_f64()) {
+            config.layout.flowchart.edge_label_offset = (val as f32).max(0.0);
+        }
+        if let Some(val) = flowchart.get("wideNodeClearance").and_then(|v| v.as_f64()) {
+            config.layout.flowchart.wide_node_clearance = (val as f32).max(0.0);
+        }
+        if let Some(val) = flowchart
+            .get("edgeLabelShape")
+            .and_then(|v| serde_json::from_value::<LabelShape>(v.clone()).ok())
+        {
+            config.layout.flowchart.edge_label_shape = val;
+        }
+        if let Some(val) = flowchart
+            .get("edgeLinejoin")
+            .and_then(|v| serde_json::from_value::<LineJoin>(v.clone()).ok())
+        {
+            config.layout.flowchart.edge_linejoin = val;
+        }
+        if let Some(val) = flowchart
+            .get("edgeLinecap")
+            .and_then(|v| serde_json::from_value::<LineCap>(v.clone()).ok())
+        {
+            config.layout.flowchart.edge_linecap = val;
+        }
+        if let Some(val) = flowchart
+            .get("curve")
+            .and_then(|v| serde_json::from_value::<EdgeCurve>(v.clone()).ok())
+        {
+            config.layout.flowchart.edge_curve = val;
+        }
+        if let Some(val) = flowchart.get("dedupeEdges").and_then(|v| v.as_bool()) {
+            config.layout.flowchart.dedupe_edges = val;
+        }
+    }
+    if let Some(sequence) = init.get("sequence") {
+        if let Some(val) = sequence.get("minParticipantGap").and_then(|v| v.as_f64()) {
+            config.layout.sequence.min_participant_gap = val as f32;
+        }
+        if let Some(val) = sequence.get("maxParticipantGap").and_then(|v| v.as_f64()) {
+            config.layout.sequence.max_participant_gap = val as f32;
+        }
+        if let Some(val) = sequence.get("minMessageGap").and_then(|v| v.as_f64()) {
+            config.layout.sequence.min_message_gap = (val as f32).max(0.0);
+        }
+        if let Some(val) = sequence
+            .get("activationZ")
+            .and_then(|v| serde_json::from_value::<ActivationZ>(v.clone()).ok())
+        {
+            config.layout.sequence.activation_z = val;
+        }
+        if let Some(val) = sequence
+            .get("showFootbox")
+            .or_else(|| sequence.get("mirrorActors"))
+            .and_then(|v| v.as_bool())
+        {
+            config.layout.sequence.show_footbox = val;
+        }
+    }
+    if let Some(gantt) = init.get("gantt") {
+        if let Some(val) = gantt.get("alternateBands").and_then(|v| v.as_bool()) {
+            config.layout.gantt.alternate_bands = val;
+        }
+        if let Some(val) = gantt.get("barHeight").and_then(|v| v.as_f64()) {
+            config.layout.gantt.bar_height = Some(val as f32);
+        }
+        if let Some(val) = gantt.get("barCornerRadius").and_then(|v| v.as_f64()) {
+            config.layout.gantt.bar_corner_radius = val as f32;
+        }
+        if let Some(val) = gantt.get("showDependencies").and_then(|v| v.as_bool()) {
+            config.layout.gantt.show_dependencies = val;
+        }
+        if let Some(val) = gantt
+            .get("hiddenSections")
+            .and_then(|v| serde_json::from_value::<Vec<String>>(v.clone()).ok())
+        {
+            config.layout.gantt.hidden_sections = val.into_iter().collect();
+        }
+    }
+    if let Some(val) = init
+        .get("journey")
+        .and_then(|journey| journey.get("showLegend"))
+        .and_then(|v| v.as_bool())
+    {
+        config.layout.journey.show_legend = val;
+    }
+    if let Some(val) = init
+        .get("xyChart")
+        .and_then(|xychart| xychart.get("yTickFormat"))
+        .and_then(|v| serde_json::from_value::<TickFormat>(v.clone()).ok())
+    {
+        config.layout.xychart.y_tick_format = val;
+    }
+    if let Some(labels) = init.get("labels") {
+        if let Some(val) = labels.get("legendMaxWidth").and_then(|v| v.as_f64()) {
+            config.layout.labels.legend_max_width = Some(val as f32);
+        }
+        if let Some(val) = labels
+            .get("legendOverflow")
+            .and_then(|v| serde_json::from_value::<LabelOverflow>(v.clone()).ok())
+        {
+            config.layout.labels.legend_overflow = val;
+        }
+        if let Some(val) = labels.get("endpointLabelOffset").and_then(|v| v.as_f64()) {
+            config.layout.labels.endpoint_label_offset = Some(val.max(0.0) as f32);
+        }
+        if let Some(val) = labels.get("nodeClearance").and_then(|v| v.as_f64()) {
+            config.layout.labels.node_clearance = val.max(0.0) as f32;
+        }
+        for (key, slot) in [
+            ("titleScale", &mut config.layout.labels.title_scale),
+            ("axisLabelScale", &mut config.layout.labels.axis_label_scale),
+            ("nodeLabelScale", &mut config.layout.labels.node_label_scale),
+        ] {
+            if let Some(val) = labels.get(key).and_then(|v| v.as_f64())
+                && val > 0.0
+            {
+                *slot = val as f32;
+            }
+        }
     }
     if let Some(gitgraph) = init.get("gitGraph") {
         let mut commit_step_set = false;
@@ -1489,6 +1724,5 @@ fn merge_init_config(mut config: Config, init: serde_json::Value) -> Config {
             config.layout.c4.external_component_queue_border_color = val;
         }
     }
-    config.render.background = config.theme.background.clone();
     config
 }
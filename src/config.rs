@@ -1,5 +1,7 @@
+use crate::ir::{EdgeStyle, NodeShape};
 use crate::theme::Theme;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 const MINDMAP_SECTION_COLORS: [&str; 12] = [
@@ -59,6 +61,9 @@ pub struct RequirementConfig {
     pub edge_label_padding_x: f32,
     pub edge_label_padding_y: f32,
     pub edge_label_brackets: bool,
+    /// Give each requirement field (id, text, risk, ...) its own divided
+    /// compartment instead of listing them in one body section.
+    pub field_compartments: bool,
     pub render_padding_x: f32,
     pub render_padding_y: f32,
 }
@@ -86,6 +91,7 @@ impl Default for RequirementConfig {
             edge_label_padding_x: 3.0,
             edge_label_padding_y: 2.0,
             edge_label_brackets: true,
+            field_compartments: true,
             render_padding_x: 4.0,
             render_padding_y: 0.0,
         }
@@ -620,6 +626,16 @@ pub enum PieRenderMode {
     Chart,
 }
 
+/// Where a chart legend is placed relative to the chart.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LegendPosition {
+    #[default]
+    Right,
+    Bottom,
+    None,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PieConfig {
     pub render_mode: PieRenderMode,
@@ -630,6 +646,10 @@ pub struct PieConfig {
     pub legend_rect_size: f32,
     pub legend_spacing: f32,
     pub legend_horizontal_multiplier: f32,
+    pub legend_position: LegendPosition,
+    /// Show at most this many legend entries, followed by a "+N more" line
+    /// for the rest.
+    pub legend_max_items: Option<usize>,
     pub min_percent: f32,
     pub error_message: String,
     pub error_version: String,
@@ -659,6 +679,8 @@ impl Default for PieConfig {
             legend_rect_size: 14.0,
             legend_spacing: 3.0,
             legend_horizontal_multiplier: 10.0,
+            legend_position: LegendPosition::Right,
+            legend_max_items: None,
             min_percent: 1.0,
             error_message: "Syntax error in text".to_string(),
             error_version: "11.12.2".to_string(),
@@ -736,6 +758,182 @@ impl Default for TreemapConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GanttConfig {
+    /// Alternate section background bands between a light and a dark tone.
+    pub alternate_bands: bool,
+    /// Task bar height in pixels; rows grow or shrink to fit it. `None`
+    /// derives it from the theme font size.
+    pub bar_height: Option<f32>,
+    pub bar_corner_radius: f32,
+    /// Draw arrows from each `after` dependency's end to the dependent
+    /// task's start.
+    pub show_dependencies: bool,
+    /// Sections left out of the chart, rows and all, so one source can
+    /// produce focused views. Their tasks still resolve `after` references.
+    pub hidden_sections: HashSet<String>,
+}
+
+impl Default for GanttConfig {
+    fn default() -> Self {
+        Self {
+            alternate_bands: false,
+            bar_height: None,
+            bar_corner_radius: 3.0,
+            show_dependencies: false,
+            hidden_sections: HashSet::new(),
+        }
+    }
+}
+
+/// How xychart y-axis tick values are written.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TickFormat {
+    /// Whole numbers: `1500`.
+    #[default]
+    Plain,
+    /// Values are already percentages: `50%`.
+    Percent,
+    /// SI-abbreviated amount after a currency symbol: `$1.2k`.
+    Currency(String),
+    /// SI suffixes: `1.5k`, `2M`, `3G`.
+    #[serde(rename = "si")]
+    SI,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XYChartConfig {
+    pub y_tick_format: TickFormat,
+    /// Y-axis ticks, counting both ends of the value range.
+    pub y_tick_count: usize,
+    /// Most x-axis category labels to show, skipping evenly between them;
+    /// `None` labels every category.
+    pub x_tick_count: Option<usize>,
+    /// Draw light horizontal gridlines at each y tick, behind the data.
+    pub show_gridlines: bool,
+}
+
+impl Default for XYChartConfig {
+    fn default() -> Self {
+        Self {
+            y_tick_format: TickFormat::default(),
+            y_tick_count: 6,
+            x_tick_count: None,
+            show_gridlines: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneyConfig {
+    /// Draw the actor name/color legend above the journey.
+    pub show_legend: bool,
+}
+
+impl Default for JourneyConfig {
+    fn default() -> Self {
+        Self { show_legend: true }
+    }
+}
+
+/// What to do with a secondary label wider than `legend_max_width`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelOverflow {
+    #[default]
+    Wrap,
+    /// Cut the label short and end it with an ellipsis.
+    Truncate,
+}
+
+/// Horizontal justification of multi-line labels inside their node.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Fitting policy for secondary labels: pie legend entries, xychart axis
+/// titles and quadrant axis/quadrant labels. The `*_scale` fields multiply the
+/// theme font size for each tier of text, both when measuring and drawing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelsConfig {
+    /// Widest such a label may be, in pixels; `None` leaves them unbounded.
+    pub legend_max_width: Option<f32>,
+    pub legend_overflow: LabelOverflow,
+    /// Diagram titles (pie, quadrant, gantt, xychart, timeline, journey).
+    pub title_scale: f32,
+    /// Quadrant and xychart axis titles.
+    pub axis_label_scale: f32,
+    /// Flowchart and state node labels.
+    pub node_label_scale: f32,
+    /// Distance from an edge end to its endpoint label, such as a class
+    /// multiplicity. `None` uses each diagram's default.
+    pub endpoint_label_offset: Option<f32>,
+    /// Extra gap edge labels keep from node boxes during placement.
+    pub node_clearance: f32,
+}
+
+impl Default for LabelsConfig {
+    fn default() -> Self {
+        Self {
+            legend_max_width: None,
+            legend_overflow: LabelOverflow::default(),
+            title_scale: 1.0,
+            axis_label_scale: 1.0,
+            node_label_scale: 1.0,
+            endpoint_label_offset: None,
+            node_clearance: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugConfig {
+    /// Annotate each node with its source id in a corner.
+    pub show_node_ids: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceConfig {
+    /// Smallest horizontal gap between neighbouring participant boxes.
+    pub min_participant_gap: f32,
+    /// Largest gap participants may be pushed apart to fit message labels.
+    pub max_participant_gap: f32,
+    /// Whether activation bars stack below or above notes. Both always sit
+    /// above lifelines and below messages.
+    pub activation_z: ActivationZ,
+    /// Repeat each participant box below its lifeline (Mermaid's
+    /// `mirrorActors`).
+    pub show_footbox: bool,
+    /// Smallest vertical distance between consecutive message lines, on top
+    /// of the spacing label heights already demand.
+    pub min_message_gap: f32,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
+        Self {
+            min_participant_gap: 0.0,
+            max_participant_gap: 600.0,
+            activation_z: ActivationZ::BelowNotes,
+            show_footbox: true,
+            min_message_gap: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivationZ {
+    #[default]
+    BelowNotes,
+    AboveNotes,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutConfig {
     pub node_spacing: f32,
@@ -746,12 +944,35 @@ pub struct LayoutConfig {
     pub max_label_width_chars: usize,
     pub preferred_aspect_ratio: Option<f32>,
     pub fast_text_metrics: bool,
+    /// Decode HTML entities (`&amp;`, `&#39;`, ...) in labels before they are
+    /// measured and drawn.
+    pub decode_html_entities: bool,
+    /// Reject diagrams whose [`complexity_score`](crate::layout::complexity_score)
+    /// exceeds this, instead of laying them out. Enforced together with
+    /// `max_nodes` and `max_edges` by
+    /// [`check_size_limits`](crate::layout::check_size_limits).
+    pub complexity_budget: Option<usize>,
+    /// Reject diagrams with more nodes than this, whose overlap and crossing
+    /// passes grow quadratically. The render functions return the error;
+    /// [`compute_layout`](crate::layout::compute_layout) draws an error layout.
+    pub max_nodes: usize,
+    /// Reject diagrams with more edges than this, like `max_nodes`.
+    pub max_edges: usize,
+    /// Justification of node label lines; `None` centers node labels and
+    /// left-aligns class-diagram member lists. Node sizes are unaffected.
+    pub label_align: Option<LabelAlign>,
     pub requirement: RequirementConfig,
     pub mindmap: MindmapConfig,
     pub gitgraph: GitGraphConfig,
     pub c4: C4Config,
     pub pie: PieConfig,
     pub treemap: TreemapConfig,
+    pub gantt: GanttConfig,
+    pub xychart: XYChartConfig,
+    pub journey: JourneyConfig,
+    pub sequence: SequenceConfig,
+    pub labels: LabelsConfig,
+    pub debug: DebugConfig,
     pub flowchart: FlowchartLayoutConfig,
 }
 
@@ -766,12 +987,23 @@ impl Default for LayoutConfig {
             max_label_width_chars: 22,
             preferred_aspect_ratio: None,
             fast_text_metrics: false,
+            decode_html_entities: true,
+            complexity_budget: None,
+            max_nodes: 2000,
+            max_edges: 4000,
+            label_align: None,
             requirement: RequirementConfig::default(),
             mindmap: MindmapConfig::default(),
             gitgraph: GitGraphConfig::default(),
             c4: C4Config::default(),
             pie: PieConfig::default(),
             treemap: TreemapConfig::default(),
+            gantt: GanttConfig::default(),
+            xychart: XYChartConfig::default(),
+            journey: JourneyConfig::default(),
+            sequence: SequenceConfig::default(),
+            labels: LabelsConfig::default(),
+            debug: DebugConfig::default(),
             flowchart: FlowchartLayoutConfig::default(),
         }
     }
@@ -783,6 +1015,104 @@ impl LayoutConfig {
     }
 }
 
+/// Strategy for choosing which edges of a cycle are treated as back-edges when
+/// ranking a flowchart.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CycleBreaking {
+    /// Topological sweep that breaks cycles at the earliest-declared node.
+    #[default]
+    Greedy,
+    /// Depth-first search from declaration order; edges to a node on the
+    /// current DFS path become back-edges.
+    Dfs,
+}
+
+/// Backdrop for edge labels: none, the default faint box, or a filled pill
+/// badge in the theme's accent color.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelShape {
+    Text,
+    Pill,
+    #[default]
+    Rect,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineJoin {
+    Miter,
+    #[default]
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineCap {
+    Butt,
+    #[default]
+    Round,
+    Square,
+}
+
+impl LineCap {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// How routed edge waypoints are drawn.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeCurve {
+    /// Straight segments between waypoints, rounded by `routing.curve_tension`.
+    #[default]
+    Orthogonal,
+    /// Smooth Catmull-Rom curve through every waypoint.
+    Basis,
+    /// Straight segments between waypoints, ignoring `routing.curve_tension`.
+    Linear,
+}
+
+/// How nodes of differing main-axis size line up within their rank.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RankAlign {
+    /// Align the leading edges (tops in a TD layout).
+    Start,
+    #[default]
+    Center,
+    /// Align the trailing edges (bottoms in a TD layout).
+    End,
+}
+
+/// Fallback direction for a subgraph without its own `direction` line.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DirectionInheritance {
+    /// Use the nearest enclosing subgraph that declares a direction.
+    FromParent,
+    /// Use the diagram's direction, ignoring enclosing subgraphs.
+    #[default]
+    FromGraph,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowchartLayoutConfig {
     pub order_passes: usize,
@@ -790,6 +1120,44 @@ pub struct FlowchartLayoutConfig {
     pub port_pad_min: f32,
     pub port_pad_max: f32,
     pub port_side_bias: f32,
+    /// Center single-parent children symmetrically under their parent instead
+    /// of using the median heuristic.
+    pub center_children: bool,
+    pub cycle_breaking: CycleBreaking,
+    pub rank_alignment: RankAlign,
+    /// Per-shape `(x, y)` multipliers on node padding; unlisted shapes use 1.0.
+    pub shape_padding: HashMap<NodeShape, (f32, f32)>,
+    /// Rotate center edge labels to follow the segment they sit on.
+    pub rotate_edge_labels: bool,
+    /// Subgraphs nested deeper than this are flattened into their ancestor
+    /// at the limit. `None` keeps every level.
+    pub max_subgraph_depth: Option<usize>,
+    /// Labels wrapping past this many lines keep only the first lines, the
+    /// last one ending in `…`; the full text becomes a tooltip.
+    pub max_label_lines: Option<usize>,
+    /// Direction a subgraph falls back to when it declares none.
+    pub subgraph_direction_inheritance: DirectionInheritance,
+    /// Gap between an edge and its center label, pushing the label to one
+    /// side of the line. 0 leaves labels centered on the path.
+    pub edge_label_offset: f32,
+    /// Backdrop drawn behind center edge labels.
+    pub edge_label_shape: LabelShape,
+    /// `stroke-linejoin` on edge paths.
+    pub edge_linejoin: LineJoin,
+    /// `stroke-linecap` on edge paths.
+    pub edge_linecap: LineCap,
+    /// Curve interpolation between routed edge waypoints.
+    pub edge_curve: EdgeCurve,
+    /// Merge edges repeating an earlier edge's endpoints, label and style.
+    pub dedupe_edges: bool,
+    /// Sample line and description per edge style, drawn as a legend below
+    /// the diagram's bottom-right corner. Set through the API only.
+    #[serde(skip)]
+    pub edge_legend: Option<Vec<(EdgeStyle, String)>>,
+    /// Extra gap between nodes of a rank, as a fraction of how much wider
+    /// the widest node in an adjacent rank is than the rank's own widest
+    /// node, so edges fanning out of a wide node are not crammed. 0 disables.
+    pub wide_node_clearance: f32,
     pub auto_spacing: FlowchartAutoSpacingConfig,
     pub routing: FlowchartRoutingConfig,
     pub objective: FlowchartObjectiveConfig,
@@ -803,6 +1171,27 @@ impl Default for FlowchartLayoutConfig {
             port_pad_min: 4.0,
             port_pad_max: 30.0,
             port_side_bias: 0.0,
+            center_children: false,
+            cycle_breaking: CycleBreaking::Greedy,
+            rank_alignment: RankAlign::Center,
+            shape_padding: HashMap::from([
+                (NodeShape::Stadium, (0.43, 0.5)),
+                (NodeShape::Subroutine, (0.54, 0.5)),
+                (NodeShape::Parallelogram, (0.894, 0.5)),
+                (NodeShape::ParallelogramAlt, (0.904, 0.5)),
+            ]),
+            rotate_edge_labels: false,
+            max_subgraph_depth: None,
+            max_label_lines: None,
+            subgraph_direction_inheritance: DirectionInheritance::FromGraph,
+            edge_label_offset: 0.0,
+            edge_label_shape: LabelShape::Rect,
+            edge_linejoin: LineJoin::Round,
+            edge_linecap: LineCap::Round,
+            edge_curve: EdgeCurve::Orthogonal,
+            dedupe_edges: false,
+            edge_legend: None,
+            wide_node_clearance: 0.0,
             auto_spacing: FlowchartAutoSpacingConfig::default(),
             routing: FlowchartRoutingConfig::default(),
             objective: FlowchartObjectiveConfig::default(),
@@ -901,6 +1290,13 @@ pub struct FlowchartRoutingConfig {
     pub occupancy_weight: f32,
     pub max_steps: usize,
     pub snap_ports_to_grid: bool,
+    /// How tightly edge curves hug their waypoints, from 0 (round
+    /// Catmull-Rom) to 1 (straight segments between waypoints).
+    pub curve_tension: f32,
+    /// Snap the cross-rank segments of orthogonal edges onto one shared lane
+    /// per gap between ranks, giving a bus-style look.
+    #[serde(default)]
+    pub channel_routing: bool,
 }
 
 impl Default for FlowchartRoutingConfig {
@@ -912,6 +1308,8 @@ impl Default for FlowchartRoutingConfig {
             occupancy_weight: 1.2,
             max_steps: 160_000,
             snap_ports_to_grid: true,
+            curve_tension: 1.0,
+            channel_routing: false,
         }
     }
 }
@@ -920,7 +1318,129 @@ impl Default for FlowchartRoutingConfig {
 pub struct RenderConfig {
     pub width: f32,
     pub height: f32,
-    pub background: String,
+    /// Wrap nodes and edges in groups carrying `class`/`data-*` hooks for
+    /// hover styling and scripting.
+    #[serde(default)]
+    pub interactive: bool,
+    /// Fit the viewBox exactly to the drawn content with no margin. Applies
+    /// to flowchart, class, state, ER and sequence diagrams; other kinds keep
+    /// their own viewBox.
+    #[serde(default)]
+    pub tight: bool,
+    /// Vertical shift applied to node labels, for fonts whose glyphs sit
+    /// visibly above or below the estimated center.
+    #[serde(default)]
+    pub text_baseline_offset: f32,
+    /// Embed the Mermaid source and crate version as an XML comment.
+    #[serde(default)]
+    pub embed_source: bool,
+    /// Smallest output canvas; smaller diagrams are padded around the center.
+    #[serde(default)]
+    pub min_size: Option<(f32, f32)>,
+    /// Largest output canvas; bigger diagrams are scaled down to fit.
+    #[serde(default)]
+    pub max_size: Option<(f32, f32)>,
+    /// Clip all content to the viewBox so nothing draws outside the canvas.
+    #[serde(default)]
+    pub clip_to_viewbox: bool,
+    /// Mirror the whole canvas, text included, e.g. for printing on
+    /// transparency film.
+    #[serde(default)]
+    pub mirror: Option<Axis>,
+    /// `text-rendering` hint set on the root `<svg>` and inherited by all
+    /// text; resvg honours it when rasterizing.
+    #[serde(default)]
+    pub text_rendering: Option<TextRendering>,
+    /// Draw node fills and lines through `--mmd-node-fill` and
+    /// `--mmd-line-color` so host pages can restyle them with CSS. The CLI
+    /// ignores it for PNG output, since resvg does not resolve `var()`.
+    #[serde(default)]
+    pub css_variables: bool,
+    /// Round the output width and height up to whole pixels, growing the
+    /// viewBox to match.
+    #[serde(default)]
+    pub integer_dimensions: bool,
+    /// What fills the canvas behind the diagram; `None` uses the theme.
+    #[serde(default)]
+    pub background: Option<BackgroundMode>,
+    /// Embed the serialized layout as JSON in a `<metadata>` element so
+    /// tools can recover geometry from the SVG alone. Needs the `serde`
+    /// feature; ignored without it.
+    #[serde(default)]
+    pub embed_layout_json: bool,
+    /// Border around the whole diagram, with an optional title bar, for a
+    /// card look. The diagram is inset inside it.
+    pub frame: Option<FrameSpec>,
+    /// Prefix for the ids the SVG defines for itself; `None` derives one
+    /// from the diagram so several SVGs can be inlined into one page.
+    #[serde(default)]
+    pub id_prefix: Option<String>,
+}
+
+/// Border drawn around a whole diagram.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FrameSpec {
+    /// Text shown in a bar across the top of the frame; `None` draws no bar.
+    pub title: Option<String>,
+    /// Gap between the border and the diagram.
+    pub padding: f32,
+    pub corner_radius: f32,
+    /// Border and title bar color.
+    pub color: String,
+    pub stroke_width: f32,
+}
+
+impl Default for FrameSpec {
+    fn default() -> Self {
+        Self {
+            title: None,
+            padding: 16.0,
+            corner_radius: 8.0,
+            color: "#D0D7DE".to_string(),
+            stroke_width: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TextRendering {
+    OptimizeLegibility,
+    GeometricPrecision,
+    CrispEdges,
+}
+
+impl TextRendering {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TextRendering::OptimizeLegibility => "optimizeLegibility",
+            TextRendering::GeometricPrecision => "geometricPrecision",
+            TextRendering::CrispEdges => "crispEdges",
+        }
+    }
+}
+
+/// Fill drawn behind the diagram.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BackgroundMode {
+    /// The theme's `background` color.
+    Themed,
+    /// No background at all, so the host page shows through.
+    Transparent,
+    /// A fixed color in place of the theme's.
+    Solid(String),
+}
+
+/// Direction of a whole-canvas mirror.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    /// Swap left and right.
+    Horizontal,
+    /// Swap top and bottom.
+    Vertical,
 }
 
 impl Default for RenderConfig {
@@ -928,7 +1448,33 @@ impl Default for RenderConfig {
         Self {
             width: 1200.0,
             height: 800.0,
-            background: "#FFFFFF".to_string(),
+            interactive: false,
+            tight: false,
+            text_baseline_offset: 0.0,
+            embed_source: false,
+            min_size: None,
+            max_size: None,
+            clip_to_viewbox: false,
+            mirror: None,
+            text_rendering: None,
+            css_variables: false,
+            integer_dimensions: false,
+            background: None,
+            embed_layout_json: false,
+            frame: None,
+            id_prefix: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// Color drawn behind the diagram under `theme`, or `None` when the
+    /// background is transparent.
+    pub fn background_color<'a>(&'a self, theme: &'a Theme) -> Option<&'a str> {
+        match &self.background {
+            Some(BackgroundMode::Transparent) => None,
+            Some(BackgroundMode::Solid(color)) => Some(color),
+            Some(BackgroundMode::Themed) | None => Some(&theme.background),
         }
     }
 }
@@ -942,15 +1488,48 @@ pub struct Config {
 
 impl Default for Config {
     fn default() -> Self {
-        let theme = Theme::mermaid_default();
-        let render = RenderConfig {
-            background: theme.background.clone(),
-            ..Default::default()
-        };
         Self {
-            theme,
+            theme: Theme::mermaid_default(),
             layout: LayoutConfig::default(),
-            render,
+            render: RenderConfig::default(),
+        }
+    }
+}
+
+/// Settings a diagram carries in its own frontmatter `config:` or
+/// `%%{init}%%` block. Keys other than these are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverride {
+    /// Theme name, resolved with [`Theme::from_name`].
+    pub theme: Option<String>,
+    /// `themeVariables` colors and font, laid over the theme.
+    pub primary_color: Option<String>,
+    pub line_color: Option<String>,
+    pub font_family: Option<String>,
+    pub node_spacing: Option<f32>,
+}
+
+impl ConfigOverride {
+    pub fn from_init(init: &serde_json::Value) -> Self {
+        let theme_variable = |key: &str| {
+            init.get("themeVariables")
+                .and_then(|vars| vars.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            theme: init
+                .get("theme")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            primary_color: theme_variable("primaryColor"),
+            line_color: theme_variable("lineColor"),
+            font_family: theme_variable("fontFamily"),
+            node_spacing: init
+                .get("flowchart")
+                .and_then(|flow| flow.get("nodeSpacing"))
+                .and_then(|v| v.as_f64())
+                .map(|v| v as f32),
         }
     }
 }
@@ -1065,6 +1644,21 @@ struct FlowchartConfig {
     port_pad_min: Option<f32>,
     port_pad_max: Option<f32>,
     port_side_bias: Option<f32>,
+    center_children: Option<bool>,
+    cycle_breaking: Option<CycleBreaking>,
+    rank_alignment: Option<RankAlign>,
+    shape_padding: Option<HashMap<NodeShape, (f32, f32)>>,
+    rotate_edge_labels: Option<bool>,
+    max_subgraph_depth: Option<usize>,
+    max_label_lines: Option<usize>,
+    subgraph_direction_inheritance: Option<DirectionInheritance>,
+    edge_label_offset: Option<f32>,
+    wide_node_clearance: Option<f32>,
+    edge_label_shape: Option<LabelShape>,
+    edge_linejoin: Option<LineJoin>,
+    edge_linecap: Option<LineCap>,
+    edge_curve: Option<EdgeCurve>,
+    dedupe_edges: Option<bool>,
     auto_spacing: Option<FlowchartAutoSpacingConfigFile>,
     routing: Option<FlowchartRoutingConfigFile>,
     objective: Option<FlowchartObjectiveConfigFile>,
@@ -1089,6 +1683,8 @@ struct FlowchartRoutingConfigFile {
     occupancy_weight: Option<f32>,
     max_steps: Option<usize>,
     snap_ports_to_grid: Option<bool>,
+    curve_tension: Option<f32>,
+    channel_routing: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1106,6 +1702,60 @@ struct FlowchartObjectiveConfigFile {
     backedge_cross_weight: Option<f32>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DebugConfigFile {
+    show_node_ids: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SequenceConfigFile {
+    min_participant_gap: Option<f32>,
+    max_participant_gap: Option<f32>,
+    activation_z: Option<ActivationZ>,
+    #[serde(alias = "mirrorActors")]
+    show_footbox: Option<bool>,
+    min_message_gap: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GanttConfigFile {
+    alternate_bands: Option<bool>,
+    bar_height: Option<f32>,
+    bar_corner_radius: Option<f32>,
+    show_dependencies: Option<bool>,
+    hidden_sections: Option<HashSet<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JourneyConfigFile {
+    show_legend: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct XYChartConfigFile {
+    y_tick_format: Option<TickFormat>,
+    y_tick_count: Option<usize>,
+    x_tick_count: Option<usize>,
+    show_gridlines: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct LabelsConfigFile {
+    legend_max_width: Option<f32>,
+    legend_overflow: Option<LabelOverflow>,
+    title_scale: Option<f32>,
+    axis_label_scale: Option<f32>,
+    node_label_scale: Option<f32>,
+    endpoint_label_offset: Option<f32>,
+    node_clearance: Option<f32>,
+}
+
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 struct PieConfigFile {
@@ -1117,6 +1767,8 @@ struct PieConfigFile {
     legend_rect_size: Option<f32>,
     legend_spacing: Option<f32>,
     legend_horizontal_multiplier: Option<f32>,
+    legend_position: Option<LegendPosition>,
+    legend_max_items: Option<usize>,
     min_percent: Option<f32>,
     error_message: Option<String>,
     error_version: Option<String>,
@@ -1158,6 +1810,7 @@ struct RequirementConfigFile {
     edge_label_padding_x: Option<f32>,
     edge_label_padding_y: Option<f32>,
     edge_label_brackets: Option<bool>,
+    field_compartments: Option<bool>,
     render_padding_x: Option<f32>,
     render_padding_y: Option<f32>,
 }
@@ -1455,10 +2108,43 @@ struct ConfigFile {
     gitgraph: Option<GitGraphConfigFile>,
     c4: Option<C4ConfigFile>,
     treemap: Option<TreemapConfigFile>,
+    gantt: Option<GanttConfigFile>,
+    #[serde(rename = "xyChart")]
+    xychart: Option<XYChartConfigFile>,
+    journey: Option<JourneyConfigFile>,
+    sequence: Option<SequenceConfigFile>,
+    labels: Option<LabelsConfigFile>,
+    debug: Option<DebugConfigFile>,
+    decode_html_entities: Option<bool>,
+    complexity_budget: Option<usize>,
+    max_nodes: Option<usize>,
+    max_edges: Option<usize>,
+    label_align: Option<LabelAlign>,
+    interactive: Option<bool>,
+    text_baseline_offset: Option<f32>,
+    embed_source: Option<bool>,
+    min_size: Option<(f32, f32)>,
+    max_size: Option<(f32, f32)>,
+    clip_to_viewbox: Option<bool>,
+    mirror: Option<Axis>,
+    text_rendering: Option<TextRendering>,
+    css_variables: Option<bool>,
+    integer_dimensions: Option<bool>,
+    background: Option<BackgroundMode>,
+    embed_layout_json: Option<bool>,
+    frame: Option<FrameSpec>,
+    id_prefix: Option<String>,
 }
 
 pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
+    load_config_with_theme(path, Theme::mermaid_default())
+}
+
+/// Like [`load_config`], but starting from `theme` instead of the Mermaid
+/// default. A theme named in the config file still takes precedence.
+pub fn load_config_with_theme(path: Option<&Path>, theme: Theme) -> anyhow::Result<Config> {
     let mut config = Config::default();
+    config.theme = theme;
     let Some(path) = path else {
         return Ok(config);
     };
@@ -1466,12 +2152,8 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
     let contents = std::fs::read_to_string(path)?;
     let parsed: ConfigFile = serde_json::from_str(&contents)?;
 
-    if let Some(theme_name) = parsed.theme.as_deref() {
-        if theme_name == "modern" {
-            config.theme = Theme::modern();
-        } else if theme_name == "base" || theme_name == "default" || theme_name == "mermaid" {
-            config.theme = Theme::mermaid_default();
-        }
+    if let Some(theme) = parsed.theme.as_deref().and_then(Theme::from_name) {
+        config.theme = theme;
     }
 
     if let Some(vars) = parsed.theme_variables {
@@ -1741,6 +2423,51 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
         if let Some(v) = flow.port_side_bias {
             config.layout.flowchart.port_side_bias = v;
         }
+        if let Some(v) = flow.center_children {
+            config.layout.flowchart.center_children = v;
+        }
+        if let Some(v) = flow.cycle_breaking {
+            config.layout.flowchart.cycle_breaking = v;
+        }
+        if let Some(v) = flow.rank_alignment {
+            config.layout.flowchart.rank_alignment = v;
+        }
+        if let Some(v) = flow.shape_padding {
+            config.layout.flowchart.shape_padding.extend(v);
+        }
+        if let Some(v) = flow.rotate_edge_labels {
+            config.layout.flowchart.rotate_edge_labels = v;
+        }
+        if let Some(v) = flow.max_subgraph_depth {
+            config.layout.flowchart.max_subgraph_depth = Some(v);
+        }
+        if let Some(v) = flow.max_label_lines {
+            config.layout.flowchart.max_label_lines = Some(v.max(1));
+        }
+        if let Some(v) = flow.subgraph_direction_inheritance {
+            config.layout.flowchart.subgraph_direction_inheritance = v;
+        }
+        if let Some(v) = flow.edge_label_offset {
+            config.layout.flowchart.edge_label_offset = v.max(0.0);
+        }
+        if let Some(v) = flow.wide_node_clearance {
+            config.layout.flowchart.wide_node_clearance = v.max(0.0);
+        }
+        if let Some(v) = flow.edge_label_shape {
+            config.layout.flowchart.edge_label_shape = v;
+        }
+        if let Some(v) = flow.edge_linejoin {
+            config.layout.flowchart.edge_linejoin = v;
+        }
+        if let Some(v) = flow.edge_linecap {
+            config.layout.flowchart.edge_linecap = v;
+        }
+        if let Some(v) = flow.edge_curve {
+            config.layout.flowchart.edge_curve = v;
+        }
+        if let Some(v) = flow.dedupe_edges {
+            config.layout.flowchart.dedupe_edges = v;
+        }
         if let Some(auto) = flow.auto_spacing {
             if let Some(v) = auto.enabled {
                 config.layout.flowchart.auto_spacing.enabled = v;
@@ -1777,6 +2504,12 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
             if let Some(v) = routing.snap_ports_to_grid {
                 config.layout.flowchart.routing.snap_ports_to_grid = v;
             }
+            if let Some(v) = routing.curve_tension {
+                config.layout.flowchart.routing.curve_tension = v.clamp(0.0, 1.0);
+            }
+            if let Some(v) = routing.channel_routing {
+                config.layout.flowchart.routing.channel_routing = v;
+            }
         }
         if let Some(objective) = flow.objective {
             if let Some(v) = objective.enabled {
@@ -1837,6 +2570,12 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
         if let Some(v) = pie.legend_horizontal_multiplier {
             config.layout.pie.legend_horizontal_multiplier = v;
         }
+        if let Some(v) = pie.legend_position {
+            config.layout.pie.legend_position = v;
+        }
+        if let Some(v) = pie.legend_max_items {
+            config.layout.pie.legend_max_items = Some(v);
+        }
         if let Some(v) = pie.min_percent {
             config.layout.pie.min_percent = v;
         }
@@ -1948,6 +2687,9 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
         if let Some(v) = req.edge_label_brackets {
             config.layout.requirement.edge_label_brackets = v;
         }
+        if let Some(v) = req.field_compartments {
+            config.layout.requirement.field_compartments = v;
+        }
         if let Some(v) = req.render_padding_x {
             config.layout.requirement.render_padding_x = v;
         }
@@ -2759,7 +3501,168 @@ pub fn load_config(path: Option<&Path>) -> anyhow::Result<Config> {
         }
     }
 
-    config.render.background = config.theme.background.clone();
+    if let Some(gantt) = parsed.gantt {
+        if let Some(v) = gantt.alternate_bands {
+            config.layout.gantt.alternate_bands = v;
+        }
+        if let Some(v) = gantt.bar_height {
+            config.layout.gantt.bar_height = Some(v);
+        }
+        if let Some(v) = gantt.bar_corner_radius {
+            config.layout.gantt.bar_corner_radius = v;
+        }
+        if let Some(v) = gantt.show_dependencies {
+            config.layout.gantt.show_dependencies = v;
+        }
+        if let Some(v) = gantt.hidden_sections {
+            config.layout.gantt.hidden_sections = v;
+        }
+    }
+
+    if let Some(xychart) = parsed.xychart {
+        if let Some(v) = xychart.y_tick_format {
+            config.layout.xychart.y_tick_format = v;
+        }
+        if let Some(v) = xychart.y_tick_count {
+            config.layout.xychart.y_tick_count = v.max(2);
+        }
+        if let Some(v) = xychart.x_tick_count {
+            config.layout.xychart.x_tick_count = Some(v.max(1));
+        }
+        if let Some(v) = xychart.show_gridlines {
+            config.layout.xychart.show_gridlines = v;
+        }
+    }
+
+    if let Some(journey) = parsed.journey
+        && let Some(v) = journey.show_legend
+    {
+        config.layout.journey.show_legend = v;
+    }
+
+    if let Some(labels) = parsed.labels {
+        if let Some(v) = labels.legend_max_width {
+            config.layout.labels.legend_max_width = Some(v);
+        }
+        if let Some(v) = labels.legend_overflow {
+            config.layout.labels.legend_overflow = v;
+        }
+        if let Some(v) = labels.title_scale.filter(|v| *v > 0.0) {
+            config.layout.labels.title_scale = v;
+        }
+        if let Some(v) = labels.axis_label_scale.filter(|v| *v > 0.0) {
+            config.layout.labels.axis_label_scale = v;
+        }
+        if let Some(v) = labels.node_label_scale.filter(|v| *v > 0.0) {
+            config.layout.labels.node_label_scale = v;
+        }
+        if let Some(v) = labels.endpoint_label_offset {
+            config.layout.labels.endpoint_label_offset = Some(v.max(0.0));
+        }
+        if let Some(v) = labels.node_clearance {
+            config.layout.labels.node_clearance = v.max(0.0);
+        }
+    }
+
+    if let Some(sequence) = parsed.sequence {
+        if let Some(v) = sequence.min_participant_gap {
+            config.layout.sequence.min_participant_gap = v;
+        }
+        if let Some(v) = sequence.max_participant_gap {
+            config.layout.sequence.max_participant_gap = v;
+        }
+        if let Some(v) = sequence.activation_z {
+            config.layout.sequence.activation_z = v;
+        }
+        if let Some(v) = sequence.show_footbox {
+            config.layout.sequence.show_footbox = v;
+        }
+        if let Some(v) = sequence.min_message_gap {
+            config.layout.sequence.min_message_gap = v.max(0.0);
+        }
+    }
+
+    if let Some(debug) = parsed.debug
+        && let Some(v) = debug.show_node_ids
+    {
+        config.layout.debug.show_node_ids = v;
+    }
+
+    if let Some(decode) = parsed.decode_html_entities {
+        config.layout.decode_html_entities = decode;
+    }
+
+    if let Some(budget) = parsed.complexity_budget {
+        config.layout.complexity_budget = Some(budget);
+    }
+
+    if let Some(max_nodes) = parsed.max_nodes {
+        config.layout.max_nodes = max_nodes;
+    }
+
+    if let Some(max_edges) = parsed.max_edges {
+        config.layout.max_edges = max_edges;
+    }
+
+    if let Some(align) = parsed.label_align {
+        config.layout.label_align = Some(align);
+    }
+
+    if let Some(interactive) = parsed.interactive {
+        config.render.interactive = interactive;
+    }
+
+    if let Some(offset) = parsed.text_baseline_offset {
+        config.render.text_baseline_offset = offset;
+    }
+
+    if let Some(embed) = parsed.embed_source {
+        config.render.embed_source = embed;
+    }
+
+    if let Some(size) = parsed.min_size {
+        config.render.min_size = Some(size);
+    }
+
+    if let Some(size) = parsed.max_size {
+        config.render.max_size = Some(size);
+    }
+
+    if let Some(clip) = parsed.clip_to_viewbox {
+        config.render.clip_to_viewbox = clip;
+    }
+
+    if let Some(axis) = parsed.mirror {
+        config.render.mirror = Some(axis);
+    }
+
+    if let Some(hint) = parsed.text_rendering {
+        config.render.text_rendering = Some(hint);
+    }
+
+    if let Some(css_variables) = parsed.css_variables {
+        config.render.css_variables = css_variables;
+    }
+
+    if let Some(integer_dimensions) = parsed.integer_dimensions {
+        config.render.integer_dimensions = integer_dimensions;
+    }
+
+    if let Some(mode) = parsed.background {
+        config.render.background = Some(mode);
+    }
+
+    if let Some(embed) = parsed.embed_layout_json {
+        config.render.embed_layout_json = embed;
+    }
+
+    if let Some(frame) = parsed.frame {
+        config.render.frame = Some(frame);
+    }
+
+    if let Some(prefix) = parsed.id_prefix {
+        config.render.id_prefix = Some(prefix);
+    }
 
     Ok(config)
 }
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+/// Errors raised by the layout stage.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MmdrError {
+    /// The diagram's complexity score exceeds `LayoutConfig::complexity_budget`.
+    #[error("diagram too complex: score {score} exceeds budget {budget}")]
+    TooComplex { score: usize, budget: usize },
+    /// The diagram has more nodes than `LayoutConfig::max_nodes`.
+    #[error("diagram too large: {nodes} nodes exceeds the limit of {limit}")]
+    TooManyNodes { nodes: usize, limit: usize },
+    /// The diagram has more edges than `LayoutConfig::max_edges`.
+    #[error("diagram too large: {edges} edges exceeds the limit of {limit}")]
+    TooManyEdges { edges: usize, limit: usize },
+}
+
+/// A syntax error found by the parser, with its 1-based line and column in
+/// the original input.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}, column {column}: {message}\n  {snippet}")]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    /// The offending source line.
+    pub snippet: String,
+}
@@ -1,6 +1,8 @@
-use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Direction {
     TopDown,
     LeftRight,
@@ -9,6 +11,7 @@ pub enum Direction {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DiagramKind {
     Flowchart,
     Class,
@@ -35,6 +38,142 @@ pub enum DiagramKind {
     XYChart,
 }
 
+impl DiagramKind {
+    /// Human-readable name, used as the fallback accessible title.
+    pub fn display_name(self) -> &'static str {
+        match self {
+            DiagramKind::Flowchart => "Flowchart",
+            DiagramKind::Class => "Class diagram",
+            DiagramKind::State => "State diagram",
+            DiagramKind::Sequence => "Sequence diagram",
+            DiagramKind::Er => "Entity relationship diagram",
+            DiagramKind::Pie => "Pie chart",
+            DiagramKind::Mindmap => "Mindmap",
+            DiagramKind::Journey => "User journey",
+            DiagramKind::Timeline => "Timeline",
+            DiagramKind::Gantt => "Gantt chart",
+            DiagramKind::Requirement => "Requirement diagram",
+            DiagramKind::GitGraph => "Git graph",
+            DiagramKind::C4 => "C4 diagram",
+            DiagramKind::Sankey => "Sankey diagram",
+            DiagramKind::Quadrant => "Quadrant chart",
+            DiagramKind::ZenUML => "ZenUML diagram",
+            DiagramKind::Block => "Block diagram",
+            DiagramKind::Packet => "Packet diagram",
+            DiagramKind::Kanban => "Kanban board",
+            DiagramKind::Architecture => "Architecture diagram",
+            DiagramKind::Radar => "Radar chart",
+            DiagramKind::Treemap => "Treemap",
+            DiagramKind::XYChart => "XY chart",
+        }
+    }
+
+    /// Every diagram kind, in declaration order.
+    pub const ALL: [DiagramKind; 23] = [
+        DiagramKind::Flowchart,
+        DiagramKind::Class,
+        DiagramKind::State,
+        DiagramKind::Sequence,
+        DiagramKind::Er,
+        DiagramKind::Pie,
+        DiagramKind::Mindmap,
+        DiagramKind::Journey,
+        DiagramKind::Timeline,
+        DiagramKind::Gantt,
+        DiagramKind::Requirement,
+        DiagramKind::GitGraph,
+        DiagramKind::C4,
+        DiagramKind::Sankey,
+        DiagramKind::Quadrant,
+        DiagramKind::ZenUML,
+        DiagramKind::Block,
+        DiagramKind::Packet,
+        DiagramKind::Kanban,
+        DiagramKind::Architecture,
+        DiagramKind::Radar,
+        DiagramKind::Treemap,
+        DiagramKind::XYChart,
+    ];
+
+    /// How completely this renderer covers the kind's Mermaid syntax and
+    /// styling.
+    pub fn support_level(self) -> SupportLevel {
+        match self {
+            DiagramKind::Flowchart
+            | DiagramKind::Class
+            | DiagramKind::State
+            | DiagramKind::Sequence
+            | DiagramKind::Er
+            | DiagramKind::Pie
+            | DiagramKind::Mindmap
+            | DiagramKind::Journey
+            | DiagramKind::Timeline
+            | DiagramKind::Gantt
+            | DiagramKind::GitGraph => SupportLevel::Full,
+            DiagramKind::Requirement
+            | DiagramKind::C4
+            | DiagramKind::Sankey
+            | DiagramKind::Quadrant
+            | DiagramKind::Block
+            | DiagramKind::Kanban
+            | DiagramKind::Architecture
+            | DiagramKind::Treemap
+            | DiagramKind::XYChart => SupportLevel::Partial,
+            DiagramKind::ZenUML | DiagramKind::Packet | DiagramKind::Radar => {
+                SupportLevel::Experimental
+            }
+        }
+    }
+}
+
+/// How well a diagram kind renders compared with Mermaid itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupportLevel {
+    /// Renders the documented syntax with output comparable to Mermaid.
+    Full,
+    /// Renders common diagrams; some syntax or styling is missing.
+    Partial,
+    /// Renders basic input only; expect gaps and layout changes.
+    Experimental,
+}
+
+/// One entry of [`crate::supported_kinds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagramKindInfo {
+    pub kind: DiagramKind,
+    pub name: &'static str,
+    pub support: SupportLevel,
+}
+
+/// Dashboard status set with `id@{ status: ... }`; colored from
+/// [`crate::Theme::status_colors`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NodeStatus {
+    Error,
+    Warn,
+    Ok,
+}
+
+impl NodeStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "ok" => Some(Self::Ok),
+            _ => None,
+        }
+    }
+}
+
+/// Accessible name and description from `accTitle` / `accDescr`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Accessibility {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SequenceFrameKind {
     Alt,
@@ -118,7 +257,11 @@ pub struct GanttTask {
     pub label: String,
     pub start: Option<String>,
     pub duration: Option<String>,
+    /// First id listed in the task's `after` clause.
     pub after: Option<String>,
+    /// Every id listed in the `after` clause; the task starts once the
+    /// last of them ends.
+    pub after_all: Vec<String>,
     pub section: Option<String>,
     pub status: Option<GanttStatus>,
 }
@@ -329,16 +472,20 @@ pub struct Node {
     pub shape: NodeShape,
     pub value: Option<f32>,
     pub icon: Option<String>,
+    /// Requirement fields (`ID: 1`, `Risk: High`, ...), drawn below the
+    /// header, one compartment each.
+    pub fields: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeLink {
     pub url: String,
     pub title: Option<String>,
     pub target: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Edge {
     pub from: String,
     pub to: String,
@@ -356,6 +503,7 @@ pub struct Edge {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EdgeStyle {
     Solid,
     Dotted,
@@ -363,6 +511,7 @@ pub enum EdgeStyle {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EdgeDecoration {
     Circle,
     Cross,
@@ -376,9 +525,12 @@ pub enum EdgeDecoration {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EdgeArrowhead {
     OpenTriangle,
     ClassDependency,
+    /// Unfilled angle marking an asynchronous sequence message (`-)`).
+    SequenceAsync,
 }
 
 #[derive(Debug, Clone)]
@@ -394,6 +546,9 @@ pub struct Subgraph {
 pub struct Graph {
     pub kind: DiagramKind,
     pub direction: Direction,
+    /// `title` from the diagram's YAML frontmatter.
+    pub title: Option<String>,
+    pub accessibility: Accessibility,
     pub nodes: BTreeMap<String, Node>,
     pub node_order: HashMap<String, usize>,
     pub edges: Vec<Edge>,
@@ -403,6 +558,8 @@ pub struct Graph {
     pub sequence_notes: Vec<SequenceNote>,
     pub sequence_activations: Vec<SequenceActivation>,
     pub sequence_autonumber: Option<usize>,
+    /// Increment between `autonumber` values.
+    pub sequence_autonumber_step: usize,
     pub sequence_boxes: Vec<SequenceBox>,
     pub state_notes: Vec<StateNote>,
     pub pie_slices: Vec<PieSlice>,
@@ -420,6 +577,7 @@ pub struct Graph {
     pub subgraph_styles: HashMap<String, NodeStyle>,
     pub subgraph_classes: HashMap<String, Vec<String>>,
     pub node_links: HashMap<String, NodeLink>,
+    pub node_statuses: HashMap<String, NodeStatus>,
     pub edge_styles: HashMap<usize, EdgeStyleOverride>,
     pub edge_style_default: Option<EdgeStyleOverride>,
     pub c4: C4Data,
@@ -429,7 +587,8 @@ pub struct Graph {
     pub block: Option<BlockDiagram>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum NodeShape {
     Rectangle,
     ForkJoin,
@@ -449,6 +608,42 @@ pub enum NodeShape {
     Asymmetric,
     MindmapDefault,
     Text,
+    Document,
+    LinedDocument,
+    Brace,
+    Braces,
+    Bolt,
+}
+
+impl NodeShape {
+    /// Resolves a Mermaid v11 `@{ shape: ... }` name or alias.
+    pub fn from_v11_name(value: &str) -> Option<Self> {
+        let shape = match value.trim().to_ascii_lowercase().as_str() {
+            "rect" | "proc" | "process" | "rectangle" => Self::Rectangle,
+            "rounded" | "event" => Self::RoundRect,
+            "stadium" | "pill" | "terminal" => Self::Stadium,
+            "fr-rect" | "subproc" | "subprocess" | "subroutine" => Self::Subroutine,
+            "cyl" | "cylinder" | "db" | "database" => Self::Cylinder,
+            "circle" | "circ" => Self::Circle,
+            "dbl-circ" | "double-circle" => Self::DoubleCircle,
+            "diam" | "diamond" | "decision" | "question" => Self::Diamond,
+            "hex" | "hexagon" | "prepare" => Self::Hexagon,
+            "lean-r" | "lean-right" | "in-out" => Self::Parallelogram,
+            "lean-l" | "lean-left" | "out-in" => Self::ParallelogramAlt,
+            "trap-b" | "trapezoid" | "priority" => Self::Trapezoid,
+            "trap-t" | "inv-trapezoid" | "manual" => Self::TrapezoidAlt,
+            "odd" => Self::Asymmetric,
+            "text" => Self::Text,
+            "fork" | "join" => Self::ForkJoin,
+            "doc" | "document" => Self::Document,
+            "lin-doc" | "lined-document" => Self::LinedDocument,
+            "brace" | "brace-l" | "comment" => Self::Brace,
+            "braces" => Self::Braces,
+            "bolt" | "com-link" | "lightning-bolt" => Self::Bolt,
+            _ => return None,
+        };
+        Some(shape)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -536,6 +731,8 @@ impl Graph {
         Self {
             kind: DiagramKind::Flowchart,
             direction: Direction::TopDown,
+            title: None,
+            accessibility: Accessibility::default(),
             nodes: BTreeMap::new(),
             node_order: HashMap::new(),
             edges: Vec::new(),
@@ -545,6 +742,7 @@ impl Graph {
             sequence_notes: Vec::new(),
             sequence_activations: Vec::new(),
             sequence_autonumber: None,
+            sequence_autonumber_step: 1,
             sequence_boxes: Vec::new(),
             state_notes: Vec::new(),
             pie_slices: Vec::new(),
@@ -562,6 +760,7 @@ impl Graph {
             subgraph_styles: HashMap::new(),
             subgraph_classes: HashMap::new(),
             node_links: HashMap::new(),
+            node_statuses: HashMap::new(),
             edge_styles: HashMap::new(),
             edge_style_default: None,
             c4: C4Data::default(),
@@ -580,6 +779,7 @@ impl Graph {
             shape: NodeShape::Rectangle,
             value: None,
             icon: None,
+            fields: Vec::new(),
         });
         if is_new {
             let order = self.node_order.len();
@@ -592,9 +792,122 @@ impl Graph {
             entry.shape = shape;
         }
     }
+
+    /// Drops edges identical to an earlier one, remapping `linkStyle` indices.
+    /// Returns how many edges were removed.
+    pub fn dedupe_edges(&mut self) -> usize {
+        let mut kept: Vec<Edge> = Vec::with_capacity(self.edges.len());
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        for (idx, edge) in self.edges.drain(..).enumerate() {
+            if let Some(existing) = kept.iter().position(|other| *other == edge) {
+                remap.insert(idx, existing);
+            } else {
+                remap.insert(idx, kept.len());
+                kept.push(edge);
+            }
+        }
+        let removed = remap.len() - kept.len();
+        self.edges = kept;
+        if removed > 0 {
+            self.remap_edge_styles(&remap);
+        }
+        removed
+    }
+
+    /// Section names declared by a gantt, journey or timeline diagram, in
+    /// declaration order; empty for other kinds.
+    pub fn section_names(&self) -> Vec<String> {
+        match self.kind {
+            DiagramKind::Gantt => self.gantt_sections.clone(),
+            DiagramKind::Journey => self.subgraphs.iter().map(|sub| sub.label.clone()).collect(),
+            DiagramKind::Timeline => self.timeline.sections.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Drops every task, step or event outside section `name`, leaving a
+    /// standalone diagram of that section. Gantt tasks that start `after` a
+    /// task in another section fall back to the running schedule.
+    pub fn retain_section(&mut self, name: &str) {
+        match self.kind {
+            DiagramKind::Gantt => {
+                self.gantt_tasks
+                    .retain(|task| task.section.as_deref() == Some(name));
+                self.gantt_sections.retain(|section| section == name);
+            }
+            DiagramKind::Journey => {
+                self.subgraphs.retain(|sub| sub.label == name);
+                let kept: HashSet<String> = self
+                    .subgraphs
+                    .iter()
+                    .flat_map(|sub| sub.nodes.iter().cloned())
+                    .collect();
+                self.retain_nodes(|id| kept.contains(id));
+            }
+            DiagramKind::Timeline => {
+                self.timeline
+                    .events
+                    .retain(|event| event.section.as_deref() == Some(name));
+                self.timeline.sections.retain(|section| section == name);
+            }
+            _ => {}
+        }
+    }
+
+    /// Keeps only nodes whose id passes `keep`, dropping the rest from
+    /// subgraphs and participants along with every edge that touches them.
+    pub fn retain_nodes(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.nodes.retain(|id, _| keep(id));
+        let nodes = &self.nodes;
+        self.node_order.retain(|id, _| nodes.contains_key(id));
+        for sub in &mut self.subgraphs {
+            sub.nodes.retain(|id| nodes.contains_key(id));
+        }
+        self.sequence_participants
+            .retain(|id| nodes.contains_key(id));
+        self.remove_orphan_edges();
+    }
+
+    /// Removes edges whose endpoints are not declared nodes and returns them.
+    pub fn remove_orphan_edges(&mut self) -> Vec<Edge> {
+        if self
+            .edges
+            .iter()
+            .all(|edge| self.nodes.contains_key(&edge.from) && self.nodes.contains_key(&edge.to))
+        {
+            return Vec::new();
+        }
+        let mut kept = Vec::with_capacity(self.edges.len());
+        let mut orphans = Vec::new();
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        for (idx, edge) in self.edges.drain(..).enumerate() {
+            if self.nodes.contains_key(&edge.from) && self.nodes.contains_key(&edge.to) {
+                remap.insert(idx, kept.len());
+                kept.push(edge);
+            } else {
+                orphans.push(edge);
+            }
+        }
+        self.edges = kept;
+        self.remap_edge_styles(&remap);
+        orphans
+    }
+
+    /// Moves `linkStyle` overrides to new edge indices, dropping unmapped ones.
+    /// The lowest old index wins when several map to the same edge.
+    fn remap_edge_styles(&mut self, remap: &HashMap<usize, usize>) {
+        let mut styles: Vec<_> = std::mem::take(&mut self.edge_styles).into_iter().collect();
+        styles.sort_by_key(|(idx, _)| *idx);
+        for (idx, style) in styles {
+            if let Some(&new_idx) = remap.get(&idx) {
+                self.edge_styles.entry(new_idx).or_insert(style);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeStyle {
     pub fill: Option<String>,
     pub stroke: Option<String>,
@@ -602,14 +915,19 @@ pub struct NodeStyle {
     pub stroke_width: Option<f32>,
     pub stroke_dasharray: Option<String>,
     pub line_color: Option<String>,
+    pub opacity: Option<f32>,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EdgeStyleOverride {
     pub stroke: Option<String>,
     pub stroke_width: Option<f32>,
     pub dasharray: Option<String>,
     pub label_color: Option<String>,
+    pub opacity: Option<f32>,
+    /// Symbol drawn at the edge midpoint: `square`, `circle`, `diamond` or `cross`.
+    pub midpoint_marker: Option<String>,
 }
 
 impl Default for Graph {
@@ -92,6 +92,7 @@ pub(super) fn compute_architecture_layout(
             height: group_height,
             style,
             icon: sub.icon.clone(),
+            direction: sub.direction.unwrap_or(graph.direction),
         });
 
         current_y += group_height + GROUP_GAP_Y;
@@ -212,6 +213,7 @@ pub(super) fn compute_architecture_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
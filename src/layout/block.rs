@@ -11,11 +11,18 @@ pub(super) fn compute_block_layout(graph: &Graph, theme: &Theme, config: &Layout
     let mut edges: Vec<EdgeLayout> = Vec::new();
 
     let Some(block) = graph.block.as_ref() else {
-        let mut subgraphs = build_subgraph_layouts(graph, &nodes, theme, config);
+        let mut subgraphs = build_subgraph_layouts(
+            graph,
+            &nodes,
+            theme,
+            config,
+            &resolve_subgraph_directions(graph, config),
+        );
         normalize_layout(&mut nodes, edges.as_mut_slice(), &mut subgraphs);
         let (max_x, max_y) = bounds_without_padding(&nodes, &subgraphs);
         return Layout {
             kind: graph.kind,
+            accessibility: graph.accessibility.clone(),
             nodes,
             edges,
             subgraphs,
@@ -171,7 +178,13 @@ pub(super) fn compute_block_layout(graph: &Graph, theme: &Theme, config: &Layout
         });
     }
 
-    let mut subgraphs = build_subgraph_layouts(graph, &nodes, theme, config);
+    let mut subgraphs = build_subgraph_layouts(
+        graph,
+        &nodes,
+        theme,
+        config,
+        &resolve_subgraph_directions(graph, config),
+    );
     normalize_layout(&mut nodes, edges.as_mut_slice(), &mut subgraphs);
 
     let (max_x, max_y) = bounds_with_edges(&nodes, &subgraphs, &edges);
@@ -180,6 +193,7 @@ pub(super) fn compute_block_layout(graph: &Graph, theme: &Theme, config: &Layout
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
@@ -131,6 +131,7 @@ pub(super) fn compute_c4_layout(graph: &Graph, config: &LayoutConfig) -> Layout
                     lines: shape.label.lines.clone(),
                     width: shape.label.width,
                     height: shape.label.height,
+                    spans: Vec::new(),
                 },
                 shape: crate::ir::NodeShape::Rectangle,
                 style: crate::ir::NodeStyle::default(),
@@ -138,6 +139,7 @@ pub(super) fn compute_c4_layout(graph: &Graph, config: &LayoutConfig) -> Layout
                 anchor_subgraph: None,
                 hidden: false,
                 icon: None,
+                tooltip: None,
             },
         );
     }
@@ -174,6 +176,7 @@ pub(super) fn compute_c4_layout(graph: &Graph, config: &LayoutConfig) -> Layout
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs: Vec::new(),
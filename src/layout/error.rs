@@ -6,6 +6,20 @@ use crate::ir::Graph;
 use super::{DiagramData, ErrorLayout, Layout};
 
 pub(super) fn compute_error_layout(graph: &Graph, config: &LayoutConfig) -> Layout {
+    error_layout_with_message(graph, config, config.treemap.error_message.clone())
+}
+
+/// Error layout naming the limit [`check_size_limits`](super::check_size_limits)
+/// rejected the graph for, if any.
+pub(super) fn compute_size_limit_error_layout(
+    graph: &Graph,
+    config: &LayoutConfig,
+) -> Option<Layout> {
+    let err = super::check_size_limits(graph, config).err()?;
+    Some(error_layout_with_message(graph, config, err.to_string()))
+}
+
+fn error_layout_with_message(graph: &Graph, config: &LayoutConfig, message: String) -> Layout {
     let viewbox_width = config.treemap.error_viewbox_width.max(1.0);
     let viewbox_height = config.treemap.error_viewbox_height.max(1.0);
     let render_width = config.treemap.error_render_width.max(1.0);
@@ -17,6 +31,7 @@ pub(super) fn compute_error_layout(graph: &Graph, config: &LayoutConfig) -> Layo
     .max(1.0);
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -27,7 +42,7 @@ pub(super) fn compute_error_layout(graph: &Graph, config: &LayoutConfig) -> Layo
             viewbox_height,
             render_width,
             render_height,
-            message: config.treemap.error_message.clone(),
+            message,
             version: config.treemap.error_version.clone(),
             text_x: config.treemap.error_text_x,
             text_y: config.treemap.error_text_y,
@@ -54,6 +69,7 @@ pub(super) fn compute_pie_error_layout(graph: &Graph, config: &LayoutConfig) ->
     .max(1.0);
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -142,19 +142,30 @@ fn format_gantt_date(days: i32) -> String {
 
 pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &LayoutConfig) -> Layout {
     let padding = theme.font_size * 1.25;
-    let row_height = (theme.font_size * 1.5).max(theme.font_size + 8.0);
+    // A configured bar height sets the row: the bar plus a little air, but
+    // never shorter than a line of task label text.
+    let row_height = match config.gantt.bar_height {
+        Some(bar_height) => (bar_height + 4.0).max(theme.font_size * 0.85 * 1.2),
+        None => (theme.font_size * 1.5).max(theme.font_size + 8.0),
+    };
     let label_gap = theme.font_size * 1.05;
     let default_duration = 3.0_f32;
 
     let title = graph
         .gantt_title
         .as_ref()
-        .map(|t| measure_label(t, theme, config));
+        .map(|t| measure_scaled_label(t, theme, config, config.labels.title_scale));
     let title_height = title.as_ref().map(|t| t.height + padding).unwrap_or(0.0);
 
+    let hidden = |task: &crate::ir::GanttTask| {
+        task.section
+            .as_ref()
+            .is_some_and(|section| config.gantt.hidden_sections.contains(section))
+    };
+
     let mut task_label_width = 0.0_f32;
     let mut section_label_width = 0.0_f32;
-    for task in &graph.gantt_tasks {
+    for task in graph.gantt_tasks.iter().filter(|task| !hidden(task)) {
         let label = measure_label(&task.label, theme, config);
         task_label_width = task_label_width.max(label.width);
         if let Some(section) = task.section.as_ref() {
@@ -194,13 +205,14 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
     let mut time_end = f32::MIN;
 
     let mut computed: Vec<(
+        usize,
         String,
         f32,
         f32,
         Option<crate::ir::GanttStatus>,
         Option<String>,
     )> = Vec::with_capacity(graph.gantt_tasks.len());
-    for task in &graph.gantt_tasks {
+    for (task_idx, task) in graph.gantt_tasks.iter().enumerate() {
         let duration = task
             .duration
             .as_deref()
@@ -208,20 +220,26 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
             .unwrap_or(default_duration)
             .max(0.1);
         let mut start = parsed_starts.get(&task.id).copied();
-        if start.is_none()
-            && let Some(after_id) = task.after.as_deref()
-            && let Some((_, end)) = timing.get(after_id)
-        {
-            start = Some(*end);
+        if start.is_none() {
+            // With several dependencies the task waits for the last to finish.
+            start = task
+                .after_all
+                .iter()
+                .filter_map(|after_id| timing.get(after_id).map(|(_, end)| *end))
+                .reduce(f32::max);
         }
         let fallback_base = origin.unwrap_or(0.0);
         let start = start.unwrap_or(fallback_base + cursor);
         let end = start + duration;
         timing.insert(task.id.clone(), (start, end));
         cursor = cursor.max(end + 0.5);
+        if hidden(task) {
+            continue;
+        }
         time_start = time_start.min(start);
         time_end = time_end.max(end);
         computed.push((
+            task_idx,
             task.label.clone(),
             start,
             duration,
@@ -260,7 +278,8 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
     let mut tasks: Vec<GanttTaskLayout> = Vec::new();
     let mut y = chart_y;
 
-    for (idx, (label, start, duration, status, section)) in computed.iter().enumerate() {
+    for (idx, label, start, duration, status, section) in computed.iter() {
+        let idx = *idx;
         if section != &current_section {
             if let Some(sec) = section.as_ref() {
                 if let Some(prev_idx) = current_section_idx {
@@ -271,7 +290,15 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
                     .get(sec)
                     .cloned()
                     .unwrap_or_else(|| palette[idx % palette.len()].clone());
-                let band_color = shift_color(&base_color, 20.0, 92.0, 0.7);
+                let band_color = if config.gantt.alternate_bands {
+                    if sections.len().is_multiple_of(2) {
+                        theme.background.clone()
+                    } else {
+                        shift_color(&theme.primary_border_color, 20.0, 70.0, 0.7)
+                    }
+                } else {
+                    shift_color(&base_color, 20.0, 92.0, 0.7)
+                };
                 sections.push(GanttSectionLayout {
                     label: measure_label(sec, theme, config),
                     y,
@@ -337,6 +364,29 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
                 / 2.0
         })
         .fold(0.0_f32, f32::max);
+    let dependencies = if config.gantt.show_dependencies {
+        // Indices into the drawn rows, which skip hidden sections.
+        let mut task_index: HashMap<&str, usize> = HashMap::new();
+        for (row, (task_idx, ..)) in computed.iter().enumerate() {
+            task_index
+                .entry(graph.gantt_tasks[*task_idx].id.as_str())
+                .or_insert(row);
+        }
+        computed
+            .iter()
+            .enumerate()
+            .map(|(row, (task_idx, ..))| (row, &graph.gantt_tasks[*task_idx]))
+            .flat_map(|(to, task)| {
+                task.after_all
+                    .iter()
+                    .filter_map(|id| task_index.get(id.as_str()).map(|&from| (from, to)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
     let axis_pad = row_height * 0.9 + theme.font_size;
     let height = y + padding + axis_pad;
     let width = (chart_x + chart_width + padding)
@@ -344,6 +394,7 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -366,6 +417,7 @@ pub(super) fn compute_gantt_layout(graph: &Graph, theme: &Theme, config: &Layout
             task_label_width,
             title_y: chart_y - row_height * 0.6,
             ticks,
+            dependencies,
         }),
         width,
         height,
@@ -481,6 +481,7 @@ pub(super) fn compute_gitgraph_layout(
                 lines: vec![String::new()],
                 width: 0.0,
                 height: 0.0,
+                spans: Vec::new(),
             },
             shape: crate::ir::NodeShape::Rectangle,
             style: crate::ir::NodeStyle::default(),
@@ -488,11 +489,13 @@ pub(super) fn compute_gitgraph_layout(
             anchor_subgraph: None,
             hidden: false,
             icon: None,
+            tooltip: None,
         },
     );
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -136,7 +136,7 @@ pub(super) fn compute_journey_layout(
     let title_block = graph
         .journey_title
         .as_ref()
-        .map(|title| measure_label(title, theme, config));
+        .map(|title| measure_scaled_label(title, theme, config, config.labels.title_scale));
     let mut cursor_y = margin_y;
     let title_y = if let Some(ref title) = title_block {
         let y = cursor_y + title.height / 2.0;
@@ -164,7 +164,9 @@ pub(super) fn compute_journey_layout(
             });
             x += actor_radius * 2.0 + actor_gap + label.width + theme.font_size * 0.8;
         }
-        cursor_y += actor_radius * 2.0 + theme.font_size * 0.8;
+        if config.journey.show_legend {
+            cursor_y += actor_radius * 2.0 + theme.font_size * 0.8;
+        }
     }
 
     let content_y = cursor_y;
@@ -283,6 +285,7 @@ pub(super) fn compute_journey_layout(
                 lines: vec![String::new()],
                 width: 0.0,
                 height: 0.0,
+                spans: Vec::new(),
             },
             shape: crate::ir::NodeShape::Rectangle,
             style: crate::ir::NodeStyle::default(),
@@ -290,11 +293,13 @@ pub(super) fn compute_journey_layout(
             anchor_subgraph: None,
             hidden: false,
             icon: None,
+            tooltip: None,
         },
     );
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -91,7 +91,13 @@ pub(super) fn compute_kanban_layout(
     }
 
     let mut edges: Vec<EdgeLayout> = Vec::new();
-    let mut subgraphs = build_subgraph_layouts(graph, &nodes, theme, config);
+    let mut subgraphs = build_subgraph_layouts(
+        graph,
+        &nodes,
+        theme,
+        config,
+        &resolve_subgraph_directions(graph, config),
+    );
     normalize_layout(&mut nodes, edges.as_mut_slice(), &mut subgraphs);
 
     let (max_x, max_y) = bounds_without_padding(&nodes, &subgraphs);
@@ -100,6 +106,7 @@ pub(super) fn compute_kanban_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
@@ -163,6 +163,19 @@ pub fn resolve_all_label_positions(
         config,
     );
 
+    // Step 1b: Push center labels off their edge when an offset is configured.
+    if layout.kind == DiagramKind::Flowchart {
+        offset_center_labels(
+            &mut layout.edges,
+            &layout.nodes,
+            &layout.subgraphs,
+            layout.kind,
+            theme,
+            config.flowchart.edge_label_offset,
+            config.labels.node_clearance,
+        );
+    }
+
     // Step 2: Resolve endpoint labels (start_label_anchor, end_label_anchor).
     resolve_endpoint_labels(
         &mut layout.edges,
@@ -175,6 +188,80 @@ pub fn resolve_all_label_positions(
     );
 }
 
+/// Move each center label perpendicular to its nearest edge segment so that
+/// `offset` separates the line from the label box. The side that overlaps
+/// fewer nodes and other labels wins; ties keep the label's current side.
+fn offset_center_labels(
+    edges: &mut [EdgeLayout],
+    nodes: &BTreeMap<String, NodeLayout>,
+    subgraphs: &[SubgraphLayout],
+    kind: DiagramKind,
+    theme: &Theme,
+    offset: f32,
+    node_clearance: f32,
+) {
+    if offset <= 0.0 {
+        return;
+    }
+    let label_rect = |center: (f32, f32), w: f32, h: f32| -> Rect {
+        (center.0 - w / 2.0, center.1 - h / 2.0, w, h)
+    };
+    let fixed = build_label_obstacles(nodes, subgraphs, kind, theme, node_clearance, 0.0);
+    let mut label_rects: Vec<Option<Rect>> = edges
+        .iter()
+        .map(|edge| match (edge.label.as_ref(), edge.label_anchor) {
+            (Some(label), Some(center)) => Some(label_rect(center, label.width, label.height)),
+            _ => None,
+        })
+        .collect();
+    for idx in 0..edges.len() {
+        let edge = &edges[idx];
+        let (Some(label), Some(center)) = (edge.label.as_ref(), edge.label_anchor) else {
+            continue;
+        };
+        let Some((proj, (tx, ty))) = edge_nearest_segment_projection(&edge.points, center) else {
+            continue;
+        };
+        let normal = (-ty, tx);
+        let dist =
+            offset + normal.0.abs() * label.width / 2.0 + normal.1.abs() * label.height / 2.0;
+        let side = if (center.0 - proj.0) * normal.0 + (center.1 - proj.1) * normal.1 < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let mut obstacles = fixed.clone();
+        obstacles.extend(
+            label_rects
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .filter_map(|(_, rect)| *rect),
+        );
+        let mut best: Option<((f32, f32), (u32, f32))> = None;
+        for sign in [side, -side] {
+            let candidate = (
+                proj.0 + normal.0 * dist * sign,
+                proj.1 + normal.1 * dist * sign,
+            );
+            let stats = overlap_stats(
+                label_rect(candidate, label.width, label.height),
+                &obstacles,
+                LABEL_OVERLAP_WIDE_THRESHOLD,
+            );
+            if best.is_none_or(|(_, best_stats)| {
+                stats.0 < best_stats.0 || (stats.0 == best_stats.0 && stats.1 < best_stats.1)
+            }) {
+                best = Some((candidate, stats));
+            }
+        }
+        if let Some((center, _)) = best {
+            label_rects[idx] = Some(label_rect(center, label.width, label.height));
+            edges[idx].label_anchor = Some(center);
+        }
+    }
+}
+
 /// Resolve center label positions for all edges, writing into `edge.label_anchor`.
 fn resolve_center_labels(
     edges: &mut [EdgeLayout],
@@ -186,7 +273,8 @@ fn resolve_center_labels(
     config: &LayoutConfig,
 ) {
     let (label_pad_x, label_pad_y) = edge_label_padding(kind, config);
-    let node_obstacle_pad = center_label_node_obstacle_pad(kind, theme, label_pad_x, label_pad_y);
+    let node_obstacle_pad = center_label_node_obstacle_pad(kind, theme, label_pad_x, label_pad_y)
+        + config.labels.node_clearance;
     let edge_obstacle_pad = (theme.font_size * 0.35).max(label_pad_y);
     let step_normal_pad = (theme.font_size * 0.25).max(label_pad_y);
     let step_tangent_pad = (theme.font_size * 0.35).max(label_pad_x);
@@ -616,6 +704,7 @@ fn resolve_center_labels(
             theme,
             label_pad_x,
             label_pad_y,
+            node_obstacle_pad,
             &fixed_center_indices,
         );
     }
@@ -629,6 +718,7 @@ fn resolve_center_labels(
         theme,
         label_pad_x,
         label_pad_y,
+        node_obstacle_pad,
         &fixed_center_indices,
     );
 }
@@ -641,6 +731,7 @@ fn deoverlap_flowchart_center_labels(
     theme: &Theme,
     label_pad_x: f32,
     label_pad_y: f32,
+    node_obstacle_pad: f32,
     locked_indices: &HashSet<usize>,
 ) {
     let step_normal_pad = (theme.font_size * 0.25).max(label_pad_y);
@@ -700,8 +791,6 @@ fn deoverlap_flowchart_center_labels(
         return;
     }
 
-    let node_obstacle_pad =
-        (theme.font_size * 0.55).max(label_pad_x.max(label_pad_y + FLOWCHART_LABEL_CLEARANCE_PAD));
     let subgraph_label_pad = (theme.font_size * 0.35).max(3.0);
     let mut fixed_obstacles = build_label_obstacles(
         nodes,
@@ -1210,6 +1299,7 @@ fn tighten_center_label_gaps(
     theme: &Theme,
     label_pad_x: f32,
     label_pad_y: f32,
+    node_obstacle_pad: f32,
     locked_indices: &HashSet<usize>,
 ) {
     if edges
@@ -1225,7 +1315,6 @@ fn tighten_center_label_gaps(
         DiagramKind::State | DiagramKind::Class => 6,
         _ => 4,
     };
-    let node_obstacle_pad = center_label_node_obstacle_pad(kind, theme, label_pad_x, label_pad_y);
     let subgraph_label_pad = (theme.font_size * 0.35).max(3.0);
     let mut static_obstacles = build_label_obstacles(
         nodes,
@@ -1958,11 +2047,19 @@ fn flowchart_center_distance_limits(label_h: f32, label_pad_y: f32) -> (f32, f32
 }
 
 fn edge_nearest_segment_tangent(points: &[(f32, f32)], center: (f32, f32)) -> Option<(f32, f32)> {
+    edge_nearest_segment_projection(points, center).map(|(_, tangent)| tangent)
+}
+
+/// Closest point to `center` on the polyline, with that segment's unit tangent.
+fn edge_nearest_segment_projection(
+    points: &[(f32, f32)],
+    center: (f32, f32),
+) -> Option<((f32, f32), (f32, f32))> {
     if points.len() < 2 {
         return None;
     }
     let mut best_dist2 = f32::INFINITY;
-    let mut best_tangent: Option<(f32, f32)> = None;
+    let mut best: Option<((f32, f32), (f32, f32))> = None;
     for seg in points.windows(2) {
         let p1 = seg[0];
         let p2 = seg[1];
@@ -1981,10 +2078,10 @@ fn edge_nearest_segment_tangent(points: &[(f32, f32)], center: (f32, f32)) -> Op
         if dist2 < best_dist2 {
             best_dist2 = dist2;
             let seg_len = seg_len2.sqrt().max(1e-3);
-            best_tangent = Some((dx / seg_len, dy / seg_len));
+            best = Some(((proj_x, proj_y), (dx / seg_len, dy / seg_len)));
         }
     }
-    best_tangent
+    best
 }
 
 fn flowchart_pose_center_target(
@@ -2355,7 +2452,7 @@ fn resolve_endpoint_labels(
         DiagramKind::Class => node_obstacle_pad * 0.4,
         DiagramKind::State => node_obstacle_pad * 0.65,
         _ => node_obstacle_pad,
-    };
+    } + config.labels.node_clearance;
     let mut endpoint_occupied = build_label_obstacles(
         nodes,
         subgraphs,
@@ -2382,11 +2479,11 @@ fn resolve_endpoint_labels(
         }
     }
 
-    let end_label_offset = match kind {
+    let end_label_offset = config.labels.endpoint_label_offset.unwrap_or(match kind {
         DiagramKind::Class => (theme.font_size * 0.18).max(2.8),
         DiagramKind::Flowchart => (theme.font_size * 0.75).max(9.0),
         _ => (theme.font_size * 0.6).max(8.0),
-    };
+    });
     let state_font_size = if kind == DiagramKind::State {
         theme.font_size * 0.85
     } else {
@@ -3494,6 +3591,46 @@ fn edge_endpoint_label_position_with_avoid(
 mod tests {
     use super::*;
 
+    #[test]
+    fn node_clearance_keeps_labels_off_nearby_nodes() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nA -->|a fairly long label| B\nC").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        // Start alignment leaves room above the edge for the label to move into.
+        config.flowchart.rank_alignment = crate::config::RankAlign::Start;
+        let mut layout = crate::layout::compute_layout(&parsed.graph, &theme, &config);
+        layout.height += 120.0;
+        let place = |config: &LayoutConfig, layout: &mut crate::layout::Layout| -> Rect {
+            layout.edges[0].label_anchor = None;
+            resolve_all_label_positions(layout, &theme, config);
+            let (x, y) = layout.edges[0].label_anchor.unwrap();
+            let label = layout.edges[0].label.as_ref().unwrap();
+            (
+                x - label.width / 2.0,
+                y - label.height / 2.0,
+                label.width,
+                label.height,
+            )
+        };
+        // Park C just below where the label settles without clearance.
+        let label = place(&config, &mut layout);
+        let c = layout.nodes.get_mut("C").unwrap();
+        c.x = label.0 + label.2 / 2.0 - c.width / 2.0;
+        c.y = label.1 + label.3 + 14.0;
+        let c_rect = (c.x, c.y, c.width, c.height);
+        let gap = |rect: Rect| {
+            let dx = (c_rect.0 - (rect.0 + rect.2)).max(rect.0 - (c_rect.0 + c_rect.2));
+            let dy = (c_rect.1 - (rect.1 + rect.3)).max(rect.1 - (c_rect.1 + c_rect.3));
+            dx.max(dy)
+        };
+
+        assert!(gap(place(&config, &mut layout)) < 24.0);
+        config.labels.node_clearance = 24.0;
+        let spaced = gap(place(&config, &mut layout));
+        assert!(spaced >= 24.0, "label sits {spaced}px from C");
+    }
+
     #[test]
     fn overlap_area_no_overlap() {
         let a: Rect = (0.0, 0.0, 10.0, 10.0);
@@ -3818,6 +3955,7 @@ mod tests {
                 lines: vec!["1".into()],
                 width: 12.0,
                 height: 16.0,
+                spans: Vec::new(),
             }),
             end_label: None,
             label_anchor: None,
@@ -3874,6 +4012,7 @@ mod tests {
                 lines: vec!["x".into()],
                 width: 8.0,
                 height: 8.0,
+                spans: Vec::new(),
             }),
             start_label: None,
             end_label: None,
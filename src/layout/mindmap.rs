@@ -289,6 +289,7 @@ pub(super) fn compute_mindmap_layout(
                 anchor_subgraph: None,
                 hidden: false,
                 icon: None,
+                tooltip: None,
             },
         );
 
@@ -475,6 +476,7 @@ pub(super) fn compute_mindmap_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs: Vec::new(),
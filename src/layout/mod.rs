@@ -35,6 +35,7 @@ use pie::*;
 use quadrant::*;
 use radar::*;
 use ranking::*;
+pub(crate) use routing::edge_label_anchor_from_points;
 use routing::*;
 use sankey::*;
 use sequence::*;
@@ -44,7 +45,8 @@ use treemap::*;
 pub use types::*;
 use xychart::*;
 
-use crate::config::{LayoutConfig, PieRenderMode, TreemapRenderMode};
+use crate::config::{DirectionInheritance, LayoutConfig, PieRenderMode, TreemapRenderMode};
+use crate::error::MmdrError;
 use crate::ir::{Direction, Graph};
 use crate::text_metrics;
 use crate::theme::{Theme, adjust_color, parse_color_to_hsl};
@@ -73,6 +75,8 @@ const LABEL_RANK_MIN_GAP: f32 = 8.0;
 
 // Minimum padding around the entire layout bounding box.
 const LAYOUT_BOUNDARY_PAD: f32 = 16.0;
+const COMPLEXITY_EDGE_WEIGHT: usize = 2;
+const COMPLEXITY_SUBGRAPH_WEIGHT: usize = 4;
 const PREFERRED_ASPECT_TOLERANCE: f32 = 0.02;
 const PREFERRED_ASPECT_MAX_EXPANSION: f32 = 6.0;
 
@@ -83,6 +87,7 @@ const STATE_DEFAULT_HEIGHT_SCALE: f32 = 2.4;
 const STATE_MARKER_DIV: f32 = 3.0;
 const STATE_MARKER_MIN_SCALE: f32 = 0.5;
 const STATE_MARKER_MAX_SCALE: f32 = 0.95;
+const STATE_HISTORY_PAD_SCALE: f32 = 0.5;
 const STATE_NOTE_PAD_X_SCALE: f32 = 0.75;
 const STATE_NOTE_PAD_Y_SCALE: f32 = 0.5;
 const STATE_NOTE_GAP_SCALE: f32 = 0.9;
@@ -168,6 +173,9 @@ const CYLINDER_SCALE: f32 = 1.1;
 const HEXAGON_WIDTH_SCALE: f32 = 1.2;
 const HEXAGON_HEIGHT_SCALE: f32 = 1.1;
 const TRAPEZOID_WIDTH_SCALE: f32 = 1.2;
+const DOCUMENT_HEIGHT_SCALE: f32 = 1.2;
+const BRACE_WIDTH_SCALE: f32 = 1.1;
+const BOLT_SCALE: f32 = 1.3;
 const CLASS_MIN_HEIGHT_SCALE: f32 = 6.5;
 const REQUIREMENT_MIN_WIDTH_SCALE: f32 = 9.5;
 const KANBAN_MIN_WIDTH_SCALE: f32 = 11.0;
@@ -208,6 +216,84 @@ const MULTI_EDGE_OFFSET_RATIO: f32 = 0.35;
 // ── State subgraph rank spacing boost ────────────────────────────────
 const STATE_RANK_SPACING_BOOST: f32 = 25.0;
 
+/// Graph-style diagrams, whose edges must join declared nodes.
+fn drops_orphan_edges(kind: crate::ir::DiagramKind) -> bool {
+    matches!(
+        kind,
+        crate::ir::DiagramKind::Class
+            | crate::ir::DiagramKind::State
+            | crate::ir::DiagramKind::Er
+            | crate::ir::DiagramKind::Requirement
+            | crate::ir::DiagramKind::Packet
+            | crate::ir::DiagramKind::Flowchart
+    )
+}
+
+/// Removes and returns the edges layout would skip because an endpoint is
+/// not a declared node, so callers can report them before laying out.
+pub fn take_orphan_edges(graph: &mut Graph) -> Vec<crate::ir::Edge> {
+    if !drops_orphan_edges(graph.kind) {
+        return Vec::new();
+    }
+    graph.remove_orphan_edges()
+}
+
+/// Returns a cleaned-up copy of `graph` when it needs one: edges whose
+/// endpoints are not declared nodes are dropped for graph-style diagrams,
+/// duplicate edges are merged when `dedupe_edges` is on, and flowchart
+/// subgraphs nested deeper than `max_subgraph_depth` are flattened.
+fn prepare_graph(graph: &Graph, config: &LayoutConfig) -> Option<Graph> {
+    let has_orphans = drops_orphan_edges(graph.kind)
+        && graph.edges.iter().any(|edge| {
+            !graph.nodes.contains_key(&edge.from) || !graph.nodes.contains_key(&edge.to)
+        });
+    let dedupe = config.flowchart.dedupe_edges && graph.kind == crate::ir::DiagramKind::Flowchart;
+    let max_depth = config
+        .flowchart
+        .max_subgraph_depth
+        .filter(|_| graph.kind == crate::ir::DiagramKind::Flowchart && !graph.subgraphs.is_empty());
+    if !has_orphans && !dedupe && max_depth.is_none() {
+        return None;
+    }
+    let mut copy = graph.clone();
+    let mut changed = !take_orphan_edges(&mut copy).is_empty();
+    if dedupe {
+        changed |= copy.dedupe_edges() > 0;
+    }
+    if let Some(max_depth) = max_depth {
+        changed |= flatten_deep_subgraphs(&mut copy, max_depth);
+    }
+    changed.then_some(copy)
+}
+
+/// Drop subgraphs nested more than `max_depth` levels deep (top level is
+/// depth 1). Their nodes already belong to every enclosing subgraph, so they
+/// stay inside the ancestor at the limit.
+fn flatten_deep_subgraphs(graph: &mut Graph, max_depth: usize) -> bool {
+    let tree = SubgraphTree::build(graph);
+    let depth = |mut idx: usize| {
+        let mut depth = 1;
+        while let Some(parent) = tree.parent[idx] {
+            depth += 1;
+            idx = parent;
+        }
+        depth
+    };
+    let keep: Vec<bool> = (0..graph.subgraphs.len())
+        .map(|idx| depth(idx) <= max_depth)
+        .collect();
+    if keep.iter().all(|keep| *keep) {
+        return false;
+    }
+    let mut flags = keep.into_iter();
+    graph.subgraphs.retain(|_| flags.next().unwrap_or(true));
+    true
+}
+
+fn is_state_history_id(id: &str) -> bool {
+    id.starts_with("__history_") || id.starts_with("__deep_history_")
+}
+
 fn is_region_subgraph(sub: &crate::ir::Subgraph) -> bool {
     sub.label.trim().is_empty()
         && sub
@@ -222,6 +308,22 @@ pub struct LayoutStageMetrics {
     pub port_assignment_us: u128,
     pub edge_routing_us: u128,
     pub label_placement_us: u128,
+    pub counts: LayoutMetrics,
+}
+
+/// Work counters from the graph layout passes, for profiling pathological
+/// inputs. Zero for diagram kinds that skip those passes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutMetrics {
+    /// Crossing-reduction sweeps run by rank ordering.
+    pub order_passes_run: usize,
+    /// Passes of the node overlap resolver.
+    pub overlap_iterations: usize,
+    /// Edges whose first route was replaced by a detour with fewer
+    /// crossings or overlaps.
+    pub edges_rerouted: usize,
+    /// Span and edge-label dummy nodes inserted into the rank grid.
+    pub dummy_nodes_created: usize,
 }
 
 impl LayoutStageMetrics {
@@ -234,12 +336,113 @@ pub fn compute_layout(graph: &Graph, theme: &Theme, config: &LayoutConfig) -> La
     compute_layout_with_metrics(graph, theme, config).0
 }
 
+/// Like [`compute_layout`], but returns the [`check_size_limits`] error
+/// instead of an error layout.
+pub fn try_compute_layout(
+    graph: &Graph,
+    theme: &Theme,
+    config: &LayoutConfig,
+) -> Result<Layout, MmdrError> {
+    check_size_limits(graph, config)?;
+    Ok(compute_layout(graph, theme, config))
+}
+
+/// Weighted size of a diagram: nodes count once, edges and subgraphs more
+/// since they drive crossing reduction and routing cost.
+pub fn complexity_score(graph: &Graph) -> usize {
+    graph.nodes.len()
+        + graph.edges.len() * COMPLEXITY_EDGE_WEIGHT
+        + graph.subgraphs.len() * COMPLEXITY_SUBGRAPH_WEIGHT
+}
+
+/// Small graphs skip crossing sweeps and the grid router entirely.
+fn is_tiny_graph(graph: &Graph) -> bool {
+    graph.subgraphs.is_empty() && graph.nodes.len() <= 4 && graph.edges.len() <= 4
+}
+
+/// Predicted cost of laying out and rendering a parsed diagram, see
+/// [`estimate_render_cost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderCost {
+    pub nodes: usize,
+    pub edges: usize,
+    pub subgraphs: usize,
+    /// Edges would be routed with the A* grid router, the slowest stage.
+    pub grid_routing: bool,
+    /// Relative cost units: [`complexity_score`], plus `edges * nodes` when
+    /// the grid router runs since every edge searches a grid that grows with
+    /// the node count.
+    pub score: usize,
+}
+
+/// Estimate render cost from graph size alone, without running layout.
+pub fn estimate_render_cost(graph: &Graph, config: &LayoutConfig) -> RenderCost {
+    let routed_as_graph = matches!(
+        graph.kind,
+        crate::ir::DiagramKind::Class
+            | crate::ir::DiagramKind::State
+            | crate::ir::DiagramKind::Er
+            | crate::ir::DiagramKind::Requirement
+            | crate::ir::DiagramKind::Packet
+            | crate::ir::DiagramKind::Flowchart
+    );
+    let grid_routing =
+        routed_as_graph && config.flowchart.routing.enable_grid_router && !is_tiny_graph(graph);
+    let mut score = complexity_score(graph);
+    if grid_routing {
+        score += graph.edges.len() * graph.nodes.len();
+    }
+    RenderCost {
+        nodes: graph.nodes.len(),
+        edges: graph.edges.len(),
+        subgraphs: graph.subgraphs.len(),
+        grid_routing,
+        score,
+    }
+}
+
+/// Fail when the graph exceeds [`LayoutConfig::max_nodes`],
+/// [`LayoutConfig::max_edges`] or [`LayoutConfig::complexity_budget`].
+/// [`compute_layout`] shows the same error as an error layout instead.
+pub fn check_size_limits(graph: &Graph, config: &LayoutConfig) -> Result<(), MmdrError> {
+    if graph.nodes.len() > config.max_nodes {
+        return Err(MmdrError::TooManyNodes {
+            nodes: graph.nodes.len(),
+            limit: config.max_nodes,
+        });
+    }
+    if graph.edges.len() > config.max_edges {
+        return Err(MmdrError::TooManyEdges {
+            edges: graph.edges.len(),
+            limit: config.max_edges,
+        });
+    }
+    if let Some(budget) = config.complexity_budget {
+        let score = complexity_score(graph);
+        if score > budget {
+            return Err(MmdrError::TooComplex { score, budget });
+        }
+    }
+    Ok(())
+}
+
 pub fn compute_layout_with_metrics(
     graph: &Graph,
     theme: &Theme,
     config: &LayoutConfig,
 ) -> (Layout, LayoutStageMetrics) {
     let mut stage_metrics = LayoutStageMetrics::default();
+    if let Some(layout) = compute_size_limit_error_layout(graph, config) {
+        return (layout, stage_metrics);
+    }
+    let prepared;
+    let graph = match prepare_graph(graph, config) {
+        Some(copy) => {
+            prepared = copy;
+            &prepared
+        }
+        None => graph,
+    };
     let mut layout = match graph.kind {
         crate::ir::DiagramKind::Sequence | crate::ir::DiagramKind::ZenUML => {
             compute_sequence_layout(graph, theme, config)
@@ -387,9 +590,7 @@ fn compute_flowchart_layout(
             prefer_direct_hub_routing = true;
         }
     }
-    let node_count = graph.nodes.len();
-    let edge_count = graph.edges.len();
-    let tiny_graph = graph.subgraphs.is_empty() && node_count <= 4 && edge_count <= 4;
+    let tiny_graph = is_tiny_graph(graph);
     if tiny_graph {
         effective_config.flowchart.order_passes = 1;
         effective_config.flowchart.routing.enable_grid_router = false;
@@ -400,7 +601,14 @@ fn compute_flowchart_layout(
         effective_config.flowchart.routing.snap_ports_to_grid = false;
     }
     let mut nodes = BTreeMap::new();
-    let measure_font_size = theme.font_size;
+    let measure_font_size = if matches!(
+        graph.kind,
+        crate::ir::DiagramKind::Flowchart | crate::ir::DiagramKind::State
+    ) {
+        theme.font_size * config.labels.node_label_scale
+    } else {
+        theme.font_size
+    };
     let mut label_config = effective_config.clone();
     if graph.kind == crate::ir::DiagramKind::Class {
         label_config.label_line_height = label_config.class_label_line_height();
@@ -410,13 +618,41 @@ fn compute_flowchart_layout(
     let mut state_height_count = 0usize;
 
     for node in graph.nodes.values() {
-        let label = measure_label_with_font_size(
-            &node.label,
-            measure_font_size,
-            &label_config,
-            true,
-            theme.font_family.as_str(),
-        );
+        let label_text = if node.fields.is_empty() {
+            node.label.clone()
+        } else {
+            requirement_label_text(node, config.requirement.field_compartments)
+        };
+        let mut label = if graph.kind == crate::ir::DiagramKind::Flowchart {
+            measure_markdown_label(
+                &label_text,
+                measure_font_size,
+                &label_config,
+                theme.font_family.as_str(),
+            )
+        } else {
+            measure_label_with_font_size(
+                &label_text,
+                measure_font_size,
+                &label_config,
+                true,
+                theme.font_family.as_str(),
+            )
+        };
+        let mut tooltip = None;
+        if graph.kind == crate::ir::DiagramKind::Flowchart
+            && let Some(max_lines) = config.flowchart.max_label_lines
+            && let Some(clamped) = limit_label_lines(
+                &label,
+                max_lines,
+                measure_font_size,
+                theme.font_family.as_str(),
+                config.fast_text_metrics,
+            )
+        {
+            tooltip = Some(label.lines.join(" "));
+            label = clamped;
+        }
         let label_empty = label.lines.len() == 1 && label.lines[0].trim().is_empty();
         let (mut width, mut height) =
             shape_size(node.shape, &label, &effective_config, theme, graph.kind);
@@ -431,15 +667,19 @@ fn compute_flowchart_layout(
             width = size;
             height = size;
             state_marker_ids.push(node.id.clone());
+        } else if graph.kind == crate::ir::DiagramKind::State && is_state_history_id(&node.id) {
+            // A small circle hugging its `H`/`H*` rather than a padded node.
+            let size = label.width.max(label.height) + theme.font_size * STATE_HISTORY_PAD_SCALE;
+            width = size;
+            height = size;
         } else if graph.kind == crate::ir::DiagramKind::State {
             state_height_total += height;
             state_height_count += 1;
         }
-        let style = resolve_node_style(node.id.as_str(), graph);
-        nodes.insert(
-            node.id.clone(),
-            build_node_layout(node, label, width, height, style, graph),
-        );
+        let style = resolve_graph_node_style(node.id.as_str(), graph, theme);
+        let mut layout = build_node_layout(node, label, width, height, style, graph);
+        layout.tooltip = tooltip;
+        nodes.insert(node.id.clone(), layout);
     }
 
     if graph.kind == crate::ir::DiagramKind::State && !state_marker_ids.is_empty() {
@@ -483,9 +723,11 @@ fn compute_flowchart_layout(
     }
 
     let config = &effective_config;
+    let subgraph_directions = resolve_subgraph_directions(graph, config);
 
     let anchor_ids = mark_subgraph_anchor_nodes_hidden(graph, &mut nodes);
-    let mut anchor_info = apply_subgraph_anchor_sizes(graph, &mut nodes, theme, config);
+    let mut anchor_info =
+        apply_subgraph_anchor_sizes(graph, &mut nodes, theme, config, &subgraph_directions);
     let mut anchored_subgraph_nodes: HashSet<String> = HashSet::new();
     for info in anchor_info.values() {
         if let Some(sub) = graph.subgraphs.get(info.sub_idx) {
@@ -539,7 +781,8 @@ fn compute_flowchart_layout(
     let mut layout_set: HashSet<String> = layout_node_ids.iter().cloned().collect();
 
     if anchor_info.is_empty() {
-        anchor_info = apply_subgraph_anchor_sizes(graph, &mut nodes, theme, config);
+        anchor_info =
+            apply_subgraph_anchor_sizes(graph, &mut nodes, theme, config, &subgraph_directions);
         anchored_subgraph_nodes.clear();
         for info in anchor_info.values() {
             if let Some(sub) = graph.subgraphs.get(info.sub_idx) {
@@ -562,7 +805,7 @@ fn compute_flowchart_layout(
             };
             if graph.kind == crate::ir::DiagramKind::Flowchart
                 && !label_text.contains('\n')
-                && !label_text.contains("<br")
+                && !has_br_tag(&label_text)
                 && label_text.chars().count() >= FLOWCHART_EDGE_LABEL_WRAP_TRIGGER_CHARS
             {
                 // Keep very long flowchart edge labels in a narrower block so
@@ -571,11 +814,17 @@ fn compute_flowchart_layout(
                 wrap_cfg.max_label_width_chars = wrap_cfg
                     .max_label_width_chars
                     .min(FLOWCHART_EDGE_LABEL_WRAP_MAX_CHARS);
-                measure_label_with_font_size(
+                measure_markdown_label(
                     &label_text,
                     theme.font_size.max(16.0),
                     &wrap_cfg,
-                    true,
+                    theme.font_family.as_str(),
+                )
+            } else if graph.kind == crate::ir::DiagramKind::Flowchart {
+                measure_markdown_label(
+                    &label_text,
+                    theme.font_size.max(16.0),
+                    config,
                     theme.font_family.as_str(),
                 )
             } else {
@@ -599,6 +848,7 @@ fn compute_flowchart_layout(
         .map(|e| measure_edge_field(&e.end_label))
         .collect();
 
+    let mut counts = LayoutMetrics::default();
     let mut label_dummy_ids: Vec<Option<String>> = vec![None; graph.edges.len()];
     assign_positions_manual(
         graph,
@@ -610,15 +860,27 @@ fn compute_flowchart_layout(
         theme,
         &edge_route_labels,
         &mut label_dummy_ids,
+        &mut counts,
     );
 
     if !graph.subgraphs.is_empty() {
         if graph.kind != crate::ir::DiagramKind::State {
-            apply_subgraph_direction_overrides(graph, &mut nodes, config, &anchored_indices);
+            apply_subgraph_direction_overrides(
+                graph,
+                &mut nodes,
+                config,
+                &subgraph_directions,
+                &anchored_indices,
+            );
         }
         if !anchor_info.is_empty() {
-            let _anchored_nodes =
-                align_subgraphs_to_anchor_nodes(graph, &anchor_info, &mut nodes, config);
+            let _anchored_nodes = align_subgraphs_to_anchor_nodes(
+                graph,
+                &anchor_info,
+                &mut nodes,
+                config,
+                &subgraph_directions,
+            );
         }
         if graph.kind == crate::ir::DiagramKind::State && !anchor_info.is_empty() {
             apply_state_subgraph_layouts(graph, &mut nodes, config, &anchored_indices);
@@ -629,14 +891,21 @@ fn compute_flowchart_layout(
         }
     }
 
-    compress_linear_subgraphs(graph, &mut nodes, config);
+    compress_linear_subgraphs(graph, &mut nodes, config, &subgraph_directions);
     enforce_top_level_subgraph_gap(graph, &mut nodes, theme, config);
 
     // Separate overlapping sibling subgraphs
     separate_sibling_subgraphs(graph, &mut nodes, theme, config);
     align_disconnected_top_level_subgraphs(graph, &mut nodes);
     align_disconnected_components(graph, &mut nodes, config);
-    apply_visual_objectives(graph, &layout_edges, &mut nodes, theme, &effective_config);
+    apply_visual_objectives(
+        graph,
+        &layout_edges,
+        &mut nodes,
+        theme,
+        &effective_config,
+        &mut counts,
+    );
 
     // Keep non-member nodes outside subgraph bounds for diagram kinds where
     // subgraphs are visual containers.
@@ -648,7 +917,7 @@ fn compute_flowchart_layout(
         push_non_members_out_of_subgraphs(graph, &mut nodes, theme, config);
     }
 
-    let mut subgraphs = build_subgraph_layouts(graph, &nodes, theme, config);
+    let mut subgraphs = build_subgraph_layouts(graph, &nodes, theme, config, &subgraph_directions);
     apply_subgraph_anchors(graph, &subgraphs, &mut nodes);
     let obstacles = build_obstacles(&nodes, &subgraphs, config);
     let label_obstacles = build_label_obstacles_for_routing(&nodes, &subgraphs);
@@ -665,7 +934,8 @@ fn compute_flowchart_layout(
     }
     let mut side_loads: HashMap<String, [usize; 4]> = HashMap::new();
     let mut edge_ports: Vec<EdgePortInfo> = Vec::with_capacity(graph.edges.len());
-    let mut port_candidates: HashMap<(String, EdgeSide), Vec<PortCandidate>> = HashMap::new();
+    // Ordered so shared node sides are spread in the same order every run.
+    let mut port_candidates: BTreeMap<(String, EdgeSide), Vec<PortCandidate>> = BTreeMap::new();
     let mut side_choice_segments: Vec<Segment> = Vec::with_capacity(graph.edges.len());
     for (idx, edge) in graph.edges.iter().enumerate() {
         let from_layout = nodes.get(&edge.from).expect("from node missing");
@@ -1281,6 +1551,7 @@ fn compute_flowchart_layout(
                     || (fast_cross == cur_cross && fast_overlap + 0.25 < cur_overlap)
                 {
                     points = fast_points;
+                    counts.edges_rerouted += 1;
                 }
             }
         }
@@ -1318,15 +1589,33 @@ fn compute_flowchart_layout(
     }
 
     if graph.kind == crate::ir::DiagramKind::Flowchart {
-        reduce_orthogonal_path_crossings(graph, &nodes, &mut routed_points, config);
-        deoverlap_flowchart_paths(graph, &nodes, &mut routed_points, config);
+        counts.edges_rerouted +=
+            reduce_orthogonal_path_crossings(graph, &nodes, &mut routed_points, config);
+        counts.edges_rerouted +=
+            deoverlap_flowchart_paths(graph, &nodes, &mut routed_points, config);
     } else if matches!(
         graph.kind,
         crate::ir::DiagramKind::Class | crate::ir::DiagramKind::Er | crate::ir::DiagramKind::State
     ) {
-        reduce_orthogonal_path_crossings(graph, &nodes, &mut routed_points, config);
+        counts.edges_rerouted +=
+            reduce_orthogonal_path_crossings(graph, &nodes, &mut routed_points, config);
         if graph.kind == crate::ir::DiagramKind::Er {
-            deoverlap_flowchart_paths(graph, &nodes, &mut routed_points, config);
+            counts.edges_rerouted +=
+                deoverlap_flowchart_paths(graph, &nodes, &mut routed_points, config);
+        }
+        if graph.kind == crate::ir::DiagramKind::State {
+            let endpoints: Vec<(&str, &str)> = graph
+                .edges
+                .iter()
+                .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+                .collect();
+            counts.edges_rerouted += clear_exited_composite_walls(
+                &endpoints,
+                &nodes,
+                &subgraphs,
+                &mut routed_points,
+                config,
+            );
         }
     }
 
@@ -1342,11 +1631,16 @@ fn compute_flowchart_layout(
             {
                 continue;
             }
+            let edge = &graph.edges[idx];
+            // Self-loops are already a minimal orthogonal detour; stub
+            // simplification would fold them into a diagonal.
+            if edge.from == edge.to {
+                continue;
+            }
             let points = &mut routed_points[idx];
             if points.len() < 3 {
                 continue;
             }
-            let edge = &graph.edges[idx];
             let from = nodes.get(&edge.from).expect("from node missing");
             let to = nodes.get(&edge.to).expect("to node missing");
             let port_info = edge_ports
@@ -1376,6 +1670,9 @@ fn compute_flowchart_layout(
             simplify_endpoint_stubs(points, &ctx);
             *points = compress_path(points);
         }
+        if config.flowchart.routing.channel_routing {
+            snap_paths_to_rank_channels(graph, &nodes, &mut routed_points);
+        }
     }
 
     // Global post-routing passes (crossing reduction/deoverlap) can move paths
@@ -1427,6 +1724,10 @@ fn compute_flowchart_layout(
         metrics.edge_routing_us = metrics
             .edge_routing_us
             .saturating_add(edge_routing_start.elapsed().as_micros());
+        metrics.counts.order_passes_run += counts.order_passes_run;
+        metrics.counts.overlap_iterations += counts.overlap_iterations;
+        metrics.counts.edges_rerouted += counts.edges_rerouted;
+        metrics.counts.dummy_nodes_created += counts.dummy_nodes_created;
     }
 
     let mut edges = Vec::new();
@@ -1562,6 +1863,7 @@ fn compute_flowchart_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
@@ -1581,6 +1883,7 @@ fn assign_positions_manual(
     theme: &Theme,
     pre_measured_labels: &[Option<TextBlock>],
     label_dummy_ids: &mut Vec<Option<String>>,
+    counts: &mut LayoutMetrics,
 ) {
     let mut edge_labels_vec: Vec<Option<TextBlock>> = Vec::new();
     let mut original_edge_indices: Vec<usize> = Vec::new();
@@ -1595,7 +1898,12 @@ fn assign_positions_manual(
         })
         .collect();
     let edge_labels = edge_labels_vec;
-    let rank_edges = rank_edges_for_manual_layout(graph, layout_node_ids, &layout_edges);
+    let rank_edges = rank_edges_for_manual_layout(
+        graph,
+        layout_node_ids,
+        &layout_edges,
+        config.flowchart.cycle_breaking,
+    );
     let mut ranks = compute_ranks_subset(layout_node_ids, &rank_edges, &graph.node_order);
     if graph.kind == crate::ir::DiagramKind::Class {
         let mut hierarchy_nodes: HashSet<String> = HashSet::new();
@@ -1792,6 +2100,7 @@ fn assign_positions_manual(
                         lines: vec![],
                         width: 0.0,
                         height: 0.0,
+                        spans: Vec::new(),
                     },
                     shape: crate::ir::NodeShape::Rectangle,
                     style: crate::ir::NodeStyle::default(),
@@ -1799,6 +2108,7 @@ fn assign_positions_manual(
                     anchor_subgraph: None,
                     hidden: true,
                     icon: None,
+                    tooltip: None,
                 },
             );
 
@@ -1932,25 +2242,41 @@ fn assign_positions_manual(
     for bucket in &mut rank_nodes {
         bucket.sort_by_key(|id| order_map.get(id).copied().unwrap_or(usize::MAX));
     }
-    order_rank_nodes(
+    counts.dummy_nodes_created += dummy_counter;
+    counts.order_passes_run += order_rank_nodes(
         &mut rank_nodes,
         &expanded_edges,
         &order_map,
         config.flowchart.order_passes,
     );
 
+    let align = match config.flowchart.rank_alignment {
+        crate::config::RankAlign::Start => 0.0,
+        crate::config::RankAlign::Center => 0.5,
+        crate::config::RankAlign::End => 1.0,
+    };
     let mut main_cursor = 0.0;
     for (rank_idx, bucket) in rank_nodes.iter().enumerate() {
-        let mut max_main: f32 = 0.0;
         let is_label_rank = label_dummy_ranks.contains(&rank_idx);
+        let main_size = |node: &NodeLayout| {
+            if is_horizontal(graph.direction) {
+                node.width
+            } else {
+                node.height
+            }
+        };
+        let max_main = bucket
+            .iter()
+            .filter_map(|node_id| nodes.get(node_id))
+            .map(main_size)
+            .fold(0.0_f32, f32::max);
         for node_id in bucket {
             if let Some(node_layout) = nodes.get_mut(node_id) {
+                let main = main_cursor + (max_main - main_size(node_layout)) * align;
                 if is_horizontal(graph.direction) {
-                    node_layout.x = main_cursor;
-                    max_main = max_main.max(node_layout.width);
+                    node_layout.x = main;
                 } else {
-                    node_layout.y = main_cursor;
-                    max_main = max_main.max(node_layout.height);
+                    node_layout.y = main;
                 }
             }
         }
@@ -1994,6 +2320,70 @@ fn assign_positions_manual(
         }
     }
 
+    // Children whose only parent is `P` get a fixed offset from `P`'s center so
+    // that sibling groups sit symmetrically under their parent.
+    let mut centered_offsets: HashMap<String, (String, f32)> = HashMap::new();
+    if config.flowchart.center_children {
+        for bucket in &rank_nodes {
+            let mut groups: Vec<(String, Vec<(String, f32)>)> = Vec::new();
+            for node_id in bucket {
+                let Some([parent]) = incoming.get(node_id).map(Vec::as_slice) else {
+                    continue;
+                };
+                let Some(node) = nodes.get(node_id) else {
+                    continue;
+                };
+                let size = if is_horizontal(graph.direction) {
+                    node.height
+                } else {
+                    node.width
+                };
+                match groups.iter_mut().find(|(id, _)| id == parent) {
+                    Some((_, members)) => members.push((node_id.clone(), size)),
+                    None => groups.push((parent.clone(), vec![(node_id.clone(), size)])),
+                }
+            }
+            for (parent, members) in groups {
+                // Equal slots keep sibling centers mirror-symmetric even when
+                // their sizes differ.
+                let slot = members.iter().map(|(_, size)| *size).fold(0.0f32, f32::max)
+                    + config.node_spacing;
+                let first = -slot * (members.len() - 1) as f32 / 2.0;
+                for (idx, (node_id, _)) in members.into_iter().enumerate() {
+                    centered_offsets.insert(node_id, (parent.clone(), first + slot * idx as f32));
+                }
+            }
+        }
+    }
+
+    // Per-rank sibling gap, widened next to ranks holding a much wider node.
+    let rank_cross_max: Vec<f32> = rank_nodes
+        .iter()
+        .map(|bucket| {
+            bucket
+                .iter()
+                .filter_map(|node_id| nodes.get(node_id))
+                .map(|node| {
+                    if is_horizontal(graph.direction) {
+                        node.height
+                    } else {
+                        node.width
+                    }
+                })
+                .fold(0.0_f32, f32::max)
+        })
+        .collect();
+    let rank_gaps: Vec<f32> = (0..rank_nodes.len())
+        .map(|rank_idx| {
+            let before = rank_idx
+                .checked_sub(1)
+                .map_or(0.0, |idx| rank_cross_max[idx]);
+            let after = rank_cross_max.get(rank_idx + 1).copied().unwrap_or(0.0);
+            let excess = (before.max(after) - rank_cross_max[rank_idx]).max(0.0);
+            config.node_spacing + config.flowchart.wide_node_clearance * excess
+        })
+        .collect();
+
     let mut place_rank = |rank_idx: usize,
                           use_incoming: bool,
                           nodes: &mut BTreeMap<String, NodeLayout>| {
@@ -2015,7 +2405,13 @@ fn assign_positions_manual(
                     }
                 }
             }
-            let mut desired = if neighbor_centers.is_empty() {
+            let centered = use_incoming
+                .then(|| centered_offsets.get(node_id))
+                .flatten()
+                .and_then(|(parent, offset)| cross_pos.get(parent).map(|center| center + offset));
+            let mut desired = if let Some(center) = centered {
+                center
+            } else if neighbor_centers.is_empty() {
                 cross_pos.get(node_id).copied().unwrap_or(0.0)
             } else {
                 neighbor_centers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
@@ -2026,7 +2422,9 @@ fn assign_positions_manual(
                     (neighbor_centers[mid - 1] + neighbor_centers[mid]) * 0.5
                 }
             };
-            if let Some(current) = cross_pos.get(node_id) {
+            if centered.is_none()
+                && let Some(current) = cross_pos.get(node_id)
+            {
                 if !neighbor_centers.is_empty() {
                     desired = desired * 0.85 + current * 0.15;
                 } else {
@@ -2052,7 +2450,7 @@ fn assign_positions_manual(
         let mut prev_half = 0.0;
         for (node_id, desired, half, _idx) in entries {
             let center = if let Some(prev) = prev_center {
-                let min_center = prev + prev_half + half + config.node_spacing;
+                let min_center = prev + prev_half + half + rank_gaps[rank_idx];
                 if desired < min_center {
                     min_center
                 } else {
@@ -2114,6 +2512,12 @@ fn merge_edge_style(
     if source.label_color.is_some() {
         target.label_color = source.label_color.clone();
     }
+    if source.opacity.is_some() {
+        target.opacity = source.opacity;
+    }
+    if source.midpoint_marker.is_some() {
+        target.midpoint_marker = source.midpoint_marker.clone();
+    }
 }
 
 fn apply_subgraph_bands(
@@ -2122,7 +2526,8 @@ fn apply_subgraph_bands(
     config: &LayoutConfig,
 ) {
     let mut group_nodes: Vec<Vec<String>> = Vec::new();
-    let mut node_group: HashMap<String, usize> = HashMap::new();
+    // Ordered so each group's bucket lists its nodes in id order every run.
+    let mut node_group: BTreeMap<String, usize> = BTreeMap::new();
 
     // Group 0: nodes not in any subgraph.
     group_nodes.push(Vec::new());
@@ -2439,18 +2844,33 @@ fn compress_linear_subgraphs(
     graph: &Graph,
     nodes: &mut BTreeMap<String, NodeLayout>,
     config: &LayoutConfig,
+    directions: &[Direction],
 ) {
     if graph.kind != crate::ir::DiagramKind::Flowchart || graph.subgraphs.is_empty() {
         return;
     }
     let gap = config.flowchart.auto_spacing.min_spacing;
-    let horizontal = is_horizontal(graph.direction);
 
-    for sub in &graph.subgraphs {
+    for (idx, sub) in graph.subgraphs.iter().enumerate() {
         if sub.nodes.len() < 3 {
             continue;
         }
+        let direction = subgraph_layout_direction(graph, directions, idx);
+        let horizontal = is_horizontal(direction);
         let sub_set: HashSet<&str> = sub.nodes.iter().map(|id| id.as_str()).collect();
+        // A nested subgraph flowing the other way keeps its own arrangement.
+        let nested_turns = graph
+            .subgraphs
+            .iter()
+            .enumerate()
+            .any(|(other_idx, other)| {
+                other.nodes.len() < sub.nodes.len()
+                    && other.nodes.iter().all(|id| sub_set.contains(id.as_str()))
+                    && subgraph_layout_direction(graph, directions, other_idx) != direction
+            });
+        if nested_turns {
+            continue;
+        }
         let mut in_deg: HashMap<String, usize> = HashMap::new();
         let mut out_deg: HashMap<String, usize> = HashMap::new();
         let mut next_map: HashMap<String, String> = HashMap::new();
@@ -2562,7 +2982,8 @@ fn apply_orthogonal_region_bands(
         .map(|sub| sub.nodes.iter().cloned().collect())
         .collect();
 
-    let mut parent_map: HashMap<usize, Vec<usize>> = HashMap::new();
+    // Ordered so nested regions are separated in the same order every run.
+    let mut parent_map: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
     for region_idx in region_indices {
         let region_set = &sets[region_idx];
         let mut parent: Option<usize> = None;
@@ -2718,12 +3139,24 @@ fn apply_subgraph_direction_overrides(
     graph: &Graph,
     nodes: &mut BTreeMap<String, NodeLayout>,
     config: &LayoutConfig,
+    directions: &[Direction],
     skip_indices: &HashSet<usize>,
 ) {
-    if graph.kind == crate::ir::DiagramKind::Flowchart {
-        return;
-    }
-    for (idx, sub) in graph.subgraphs.iter().enumerate() {
+    // Lay out enclosing subgraphs first so a nested override is not undone
+    // by its parent re-flowing every node it contains.
+    let tree = SubgraphTree::build(graph);
+    let depth = |mut idx: usize| {
+        let mut depth = 0;
+        while let Some(parent) = tree.parent[idx] {
+            depth += 1;
+            idx = parent;
+        }
+        depth
+    };
+    let mut order: Vec<usize> = (0..graph.subgraphs.len()).collect();
+    order.sort_by_key(|&idx| depth(idx));
+    for idx in order {
+        let sub = &graph.subgraphs[idx];
         if skip_indices.contains(&idx) {
             continue;
         }
@@ -2736,13 +3169,17 @@ fn apply_subgraph_direction_overrides(
                 if graph.kind != crate::ir::DiagramKind::Flowchart {
                     continue;
                 }
-                subgraph_layout_direction(graph, sub)
+                subgraph_layout_direction(graph, directions, idx)
             }
         };
         if sub.nodes.is_empty() {
             continue;
         }
-        if direction == graph.direction {
+        let enclosing = tree.parent[idx]
+            .filter(|parent| !skip_indices.contains(parent))
+            .map(|parent| directions[parent])
+            .unwrap_or(graph.direction);
+        if direction == enclosing {
             continue;
         }
 
@@ -2905,14 +3342,38 @@ struct SubgraphAnchorInfo {
     top_padding: f32,
 }
 
-fn subgraph_layout_direction(graph: &Graph, sub: &crate::ir::Subgraph) -> Direction {
-    if graph.kind == crate::ir::DiagramKind::State {
-        return graph.direction;
+fn subgraph_layout_direction(graph: &Graph, directions: &[Direction], idx: usize) -> Direction {
+    if graph.kind == crate::ir::DiagramKind::Flowchart {
+        return directions[idx];
     }
-    let _ = sub; // Subgraph direction is currently ignored for CLI parity.
     graph.direction
 }
 
+/// Each subgraph's own direction, else the fallback chosen by
+/// `flowchart.subgraph_direction_inheritance`, indexed like `graph.subgraphs`.
+fn resolve_subgraph_directions(graph: &Graph, config: &LayoutConfig) -> Vec<Direction> {
+    let tree = (graph.kind == crate::ir::DiagramKind::Flowchart
+        && config.flowchart.subgraph_direction_inheritance == DirectionInheritance::FromParent)
+        .then(|| SubgraphTree::build(graph));
+    (0..graph.subgraphs.len())
+        .map(|idx| {
+            if let Some(direction) = graph.subgraphs[idx].direction {
+                return direction;
+            }
+            if let Some(tree) = &tree {
+                let mut cur = idx;
+                while let Some(parent) = tree.parent[cur] {
+                    if let Some(direction) = graph.subgraphs[parent].direction {
+                        return direction;
+                    }
+                    cur = parent;
+                }
+            }
+            graph.direction
+        })
+        .collect()
+}
+
 fn subgraph_layout_config(graph: &Graph, anchorable: bool, config: &LayoutConfig) -> LayoutConfig {
     let mut local = config.clone();
     if graph.kind == crate::ir::DiagramKind::Flowchart && anchorable {
@@ -2990,16 +3451,18 @@ fn subgraph_padding_from_label(
 }
 fn estimate_subgraph_box_size(
     graph: &Graph,
-    sub: &crate::ir::Subgraph,
+    sub_idx: usize,
     nodes: &BTreeMap<String, NodeLayout>,
     theme: &Theme,
     config: &LayoutConfig,
+    directions: &[Direction],
     anchorable: bool,
 ) -> Option<(f32, f32, f32, f32)> {
+    let sub = &graph.subgraphs[sub_idx];
     if sub.nodes.is_empty() {
         return None;
     }
-    let direction = subgraph_layout_direction(graph, sub);
+    let direction = subgraph_layout_direction(graph, directions, sub_idx);
     let mut temp_nodes: BTreeMap<String, NodeLayout> = BTreeMap::new();
     for node_id in &sub.nodes {
         if let Some(node) = nodes.get(node_id) {
@@ -3054,6 +3517,7 @@ fn apply_subgraph_anchor_sizes(
     nodes: &mut BTreeMap<String, NodeLayout>,
     theme: &Theme,
     config: &LayoutConfig,
+    directions: &[Direction],
 ) -> HashMap<String, SubgraphAnchorInfo> {
     let mut anchors: HashMap<String, SubgraphAnchorInfo> = HashMap::new();
     if graph.subgraphs.is_empty() {
@@ -3070,7 +3534,7 @@ fn apply_subgraph_anchor_sizes(
             continue;
         };
         let Some((width, height, padding_x, top_padding)) =
-            estimate_subgraph_box_size(graph, sub, nodes, theme, config, true)
+            estimate_subgraph_box_size(graph, idx, nodes, theme, config, directions, true)
         else {
             continue;
         };
@@ -3095,6 +3559,7 @@ fn align_subgraphs_to_anchor_nodes(
     anchor_info: &HashMap<String, SubgraphAnchorInfo>,
     nodes: &mut BTreeMap<String, NodeLayout>,
     config: &LayoutConfig,
+    directions: &[Direction],
 ) -> HashSet<String> {
     let mut anchored_nodes = HashSet::new();
     if anchor_info.is_empty() {
@@ -3142,7 +3607,7 @@ fn align_subgraphs_to_anchor_nodes(
         let Some(sub) = graph.subgraphs.get(info.sub_idx) else {
             continue;
         };
-        let direction = subgraph_layout_direction(graph, sub);
+        let direction = subgraph_layout_direction(graph, directions, info.sub_idx);
         let local_config = subgraph_layout_config(graph, true, config);
         let ranks = compute_ranks_subset(&sub.nodes, &graph.edges, &graph.node_order);
         assign_positions(
@@ -3718,8 +4183,8 @@ fn reduce_crossing_sweep(
     outer_right: f32,
     outer_top: f32,
     outer_bottom: f32,
-) -> bool {
-    let mut changed = false;
+) -> usize {
+    let mut changed = 0;
     let mut existing_segments: Vec<Segment> = Vec::new();
     // Keep crossing fixes from introducing visually extreme detours.
     const MAX_LEN_RATIO_HARD: f32 = 2.8;
@@ -3849,21 +4314,22 @@ fn reduce_crossing_sweep(
                 continue;
             }
             routed_points[idx] = best_points;
-            changed = true;
+            changed += 1;
         }
         append_path_segments(&routed_points[idx], &mut existing_segments);
     }
     changed
 }
 
+/// Returns how many edge routes were replaced.
 fn reduce_orthogonal_path_crossings(
     graph: &Graph,
     nodes: &BTreeMap<String, NodeLayout>,
     routed_points: &mut [Vec<(f32, f32)>],
     config: &LayoutConfig,
-) {
+) -> usize {
     if graph.edges.len() < 2 {
-        return;
+        return 0;
     }
     let base_delta = (config.node_spacing * 0.22).max(8.0);
     let deltas = [
@@ -3910,6 +4376,7 @@ fn reduce_orthogonal_path_crossings(
     let forward: Vec<usize> = (0..routed_points.len()).collect();
     let reverse: Vec<usize> = (0..routed_points.len()).rev().collect();
 
+    let mut rerouted = 0;
     for _ in 0..3 {
         let mut changed = reduce_crossing_sweep(
             &forward,
@@ -3923,7 +4390,7 @@ fn reduce_orthogonal_path_crossings(
             outer_top,
             outer_bottom,
         );
-        changed |= reduce_crossing_sweep(
+        changed += reduce_crossing_sweep(
             &reverse,
             graph,
             nodes,
@@ -3935,10 +4402,12 @@ fn reduce_orthogonal_path_crossings(
             outer_top,
             outer_bottom,
         );
-        if !changed {
+        rerouted += changed;
+        if changed == 0 {
             break;
         }
     }
+    rerouted
 }
 
 fn flowchart_path_hits_non_endpoint_nodes(
@@ -4004,14 +4473,100 @@ fn bump_orthogonal_segment(
     Some(compress_path(&bumped))
 }
 
+/// Moves cross-rank segments onto the midline of the gap between the ranks
+/// they run through, so parallel edges share one lane per gap. Diagonal runs
+/// across a gap are bent into a dogleg through that lane.
+fn snap_paths_to_rank_channels(
+    graph: &Graph,
+    nodes: &BTreeMap<String, NodeLayout>,
+    routed_points: &mut [Vec<(f32, f32)>],
+) {
+    let horizontal = is_horizontal(graph.direction);
+    let main = |p: (f32, f32)| if horizontal { p.0 } else { p.1 };
+    let cross = |p: (f32, f32)| if horizontal { p.1 } else { p.0 };
+    let point = |main: f32, cross: f32| {
+        if horizontal {
+            (main, cross)
+        } else {
+            (cross, main)
+        }
+    };
+    let spans: Vec<(f32, f32)> = nodes
+        .values()
+        .filter(|node| !node.hidden)
+        .map(|node| {
+            if horizontal {
+                (node.x, node.x + node.width)
+            } else {
+                (node.y, node.y + node.height)
+            }
+        })
+        .collect();
+    let channel_at = |coord: f32| -> Option<f32> {
+        let mut before = f32::NEG_INFINITY;
+        let mut after = f32::INFINITY;
+        for &(lo, hi) in &spans {
+            if lo < coord && coord < hi {
+                return None;
+            }
+            if hi <= coord {
+                before = before.max(hi);
+            } else {
+                after = after.min(lo);
+            }
+        }
+        (before.is_finite() && after.is_finite()).then(|| (before + after) / 2.0)
+    };
+    let between = |v: f32, a: f32, b: f32| v > a.min(b) && v < a.max(b);
+    for (idx, points) in routed_points.iter_mut().enumerate() {
+        let edge = &graph.edges[idx];
+        if edge.from == edge.to || points.len() < 2 {
+            continue;
+        }
+        let mut changed = false;
+        let mut snapped = vec![points[0]];
+        for i in 0..points.len() - 1 {
+            let (a, b) = (points[i], points[i + 1]);
+            let diagonal = (main(a) - main(b)).abs() > 0.5 && (cross(a) - cross(b)).abs() > 0.5;
+            if diagonal {
+                if let Some(lane) = channel_at((main(a) + main(b)) / 2.0)
+                    .filter(|&lane| between(lane, main(a), main(b)))
+                {
+                    snapped.push(point(lane, cross(a)));
+                    snapped.push(point(lane, cross(b)));
+                    changed = true;
+                }
+            } else if i > 0 && i + 2 < points.len() && (main(a) - main(b)).abs() <= 0.5 {
+                let prev = main(points[i - 1]);
+                let next = main(points[i + 2]);
+                // Keep the neighbouring segments pointing the same way.
+                if let Some(lane) = channel_at(main(a)).filter(|&lane| {
+                    (lane - prev) * (main(a) - prev) >= 0.0
+                        && (next - lane) * (next - main(a)) >= 0.0
+                }) {
+                    *snapped.last_mut().expect("path start") = point(lane, cross(a));
+                    snapped.push(point(lane, cross(b)));
+                    changed = true;
+                    continue;
+                }
+            }
+            snapped.push(b);
+        }
+        if changed {
+            *points = compress_path(&snapped);
+        }
+    }
+}
+
+/// Returns how many edge routes were replaced.
 fn deoverlap_flowchart_paths(
     graph: &Graph,
     nodes: &BTreeMap<String, NodeLayout>,
     routed_points: &mut [Vec<(f32, f32)>],
     config: &LayoutConfig,
-) {
+) -> usize {
     if graph.edges.len() < 2 {
-        return;
+        return 0;
     }
     let overlap_threshold = 0.75f32;
     let base_delta = (config.node_spacing * 0.25).max(8.0);
@@ -4025,6 +4580,7 @@ fn deoverlap_flowchart_paths(
         -base_delta * 2.0,
     ];
 
+    let mut rerouted = 0;
     for _ in 0..3 {
         let mut changed = false;
         for idx in 1..routed_points.len() {
@@ -4069,12 +4625,14 @@ fn deoverlap_flowchart_paths(
             if best_overlap + 0.05 < baseline {
                 routed_points[idx] = best_points;
                 changed = true;
+                rerouted += 1;
             }
         }
         if !changed {
             break;
         }
     }
+    rerouted
 }
 
 fn apply_direction_mirror(
@@ -4190,6 +4748,19 @@ fn resolve_node_style(node_id: &str, graph: &Graph) -> crate::ir::NodeStyle {
     style
 }
 
+/// [`resolve_node_style`] on top of the theme's status colors for nodes
+/// tagged `@{ status: ... }`, so explicit styles still win.
+fn resolve_graph_node_style(node_id: &str, graph: &Graph, theme: &Theme) -> crate::ir::NodeStyle {
+    let mut style = crate::ir::NodeStyle::default();
+    if let Some(status) = graph.node_statuses.get(node_id) {
+        let color = theme.status_colors.get(*status).to_string();
+        style.fill = Some(color.clone());
+        style.stroke = Some(color);
+    }
+    merge_node_style(&mut style, &resolve_node_style(node_id, graph));
+    style
+}
+
 /// Build a `NodeLayout` with the standard defaults (position at origin, no
 /// anchor, not hidden, no icon).  Callers that need custom x/y or
 /// width/height can mutate the returned value.
@@ -4214,6 +4785,7 @@ fn build_node_layout(
         anchor_subgraph: None,
         hidden: false,
         icon: None,
+        tooltip: None,
     }
 }
 
@@ -4230,7 +4802,7 @@ fn build_graph_node_layouts(
     for node in graph.nodes.values() {
         let label = measure_label(&node.label, theme, config);
         let (width, height) = shape_size(node.shape, &label, config, theme, graph.kind);
-        let style = resolve_node_style(node.id.as_str(), graph);
+        let style = resolve_graph_node_style(node.id.as_str(), graph, theme);
         nodes.insert(
             node.id.clone(),
             build_node_layout(node, label, width, height, style, graph),
@@ -4241,9 +4813,8 @@ fn build_graph_node_layouts(
 
 fn resolve_subgraph_style(sub: &crate::ir::Subgraph, graph: &Graph) -> crate::ir::NodeStyle {
     let mut style = crate::ir::NodeStyle::default();
-    let Some(id) = sub.id.as_ref() else {
-        return style;
-    };
+    // Subgraphs declared only by a quoted label are addressed by that label.
+    let id = sub.id.as_deref().unwrap_or(sub.label.as_str());
 
     if let Some(classes) = graph.subgraph_classes.get(id) {
         for class_name in classes {
@@ -4606,9 +5177,17 @@ fn separate_sibling_subgraphs(
                 let label_block = measure_label(&sub.label, theme, config);
                 let (pad_x, pad_y, top_padding) =
                     subgraph_padding_from_label(graph, sub, theme, &label_block);
-                let padded_min_x = min_x - pad_x;
+                // Match build_subgraph_layouts: a title wider than the
+                // contents widens the box evenly on both sides.
+                let label_width = if sub.label.trim().is_empty() {
+                    0.0
+                } else {
+                    label_block.width + pad_x * 2.0
+                };
+                let extra_x = (label_width - (max_x - min_x + pad_x * 2.0)).max(0.0) / 2.0;
+                let padded_min_x = min_x - pad_x - extra_x;
                 let padded_min_y = min_y - top_padding;
-                let padded_max_x = max_x + pad_x;
+                let padded_max_x = max_x + pad_x + extra_x;
                 let padded_max_y = max_y + pad_y;
                 bounds.push((idx, padded_min_x, padded_min_y, padded_max_x, padded_max_y));
             }
@@ -4994,6 +5573,7 @@ fn apply_visual_objectives(
     nodes: &mut BTreeMap<String, NodeLayout>,
     theme: &Theme,
     config: &LayoutConfig,
+    counts: &mut LayoutMetrics,
 ) {
     if !config.flowchart.objective.enabled {
         return;
@@ -5009,7 +5589,7 @@ fn apply_visual_objectives(
         _ => false,
     };
     if overlap_pass_enabled {
-        resolve_node_overlaps(graph, nodes, config);
+        counts.overlap_iterations += resolve_node_overlaps(graph, nodes, config);
     }
 }
 
@@ -5072,6 +5652,10 @@ fn relax_edge_span_constraints(
     for _ in 0..passes {
         let mut changed = false;
         for edge in layout_edges {
+            // A self-loop has no span to relax; its label rides on the loop.
+            if edge.from == edge.to {
+                continue;
+            }
             let Some(from_node) = nodes.get(&edge.from) else {
                 continue;
             };
@@ -5194,11 +5778,12 @@ fn relax_edge_span_constraints(
     }
 }
 
+/// Pushes overlapping nodes apart; returns how many passes ran.
 fn resolve_node_overlaps(
     graph: &Graph,
     nodes: &mut BTreeMap<String, NodeLayout>,
     config: &LayoutConfig,
-) {
+) -> usize {
     let horizontal = is_horizontal(graph.direction);
     let min_gap = (config.node_spacing * OVERLAP_MIN_GAP_RATIO).max(OVERLAP_MIN_GAP_FLOOR);
     let mut ids: Vec<String> = nodes
@@ -5207,56 +5792,83 @@ fn resolve_node_overlaps(
         .map(|node| node.id.clone())
         .collect();
     if ids.len() < 2 {
-        return;
+        return 0;
     }
     ids.sort_by_key(|id| graph.node_order.get(id).copied().unwrap_or(usize::MAX));
 
+    // Shifts only move nodes along the cross axis, so which pairs share a
+    // main-axis span is fixed up front. Sweep over main-axis starts to find
+    // them, then visit them in the same (i, j) order as a full pairwise scan.
+    let main_span = |id: &String| {
+        let node = &nodes[id];
+        if horizontal {
+            (node.x, node.x + node.width)
+        } else {
+            (node.y, node.y + node.height)
+        }
+    };
+    let spans: Vec<(f32, f32)> = ids.iter().map(main_span).collect();
+    let mut by_start: Vec<usize> = (0..ids.len()).collect();
+    by_start.sort_by(|&a, &b| {
+        spans[a]
+            .0
+            .partial_cmp(&spans[b].0)
+            .unwrap_or(Ordering::Equal)
+    });
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (pos, &a) in by_start.iter().enumerate() {
+        for &b in &by_start[pos + 1..] {
+            if spans[b].0 >= spans[a].1 {
+                break;
+            }
+            pairs.push((a.min(b), a.max(b)));
+        }
+    }
+    pairs.sort_unstable();
+
+    let mut passes = 0;
     for _ in 0..OVERLAP_RESOLVE_PASSES {
+        passes += 1;
         let mut moved = false;
-        for i in 0..ids.len() {
-            for j in (i + 1)..ids.len() {
-                let id_a = &ids[i];
-                let id_b = &ids[j];
-                let (ax, ay, aw, ah, bx, by, bw, bh) = {
-                    let Some(a) = nodes.get(id_a) else {
-                        continue;
-                    };
-                    let Some(b) = nodes.get(id_b) else {
-                        continue;
-                    };
-                    (a.x, a.y, a.width, a.height, b.x, b.y, b.width, b.height)
-                };
-                let overlap_x = (ax + aw).min(bx + bw) - ax.max(bx);
-                let overlap_y = (ay + ah).min(by + bh) - ay.max(by);
-                if overlap_x <= 0.0 || overlap_y <= 0.0 {
-                    continue;
-                }
-                let (center_a, center_b) = if horizontal {
-                    (ay + ah / 2.0, by + bh / 2.0)
-                } else {
-                    (ax + aw / 2.0, bx + bw / 2.0)
-                };
-                let mut sign = if center_b >= center_a { 1.0 } else { -1.0 };
-                if (center_b - center_a).abs() < OVERLAP_CENTER_THRESHOLD {
-                    let order_a = graph.node_order.get(id_a).copied().unwrap_or(usize::MAX);
-                    let order_b = graph.node_order.get(id_b).copied().unwrap_or(usize::MAX);
-                    sign = if order_b >= order_a { 1.0 } else { -1.0 };
-                }
-                let delta = if horizontal {
-                    overlap_y + min_gap
-                } else {
-                    overlap_x + min_gap
-                };
-                if let Some(node_b) = nodes.get_mut(id_b) {
-                    shift_node_cross(node_b, horizontal, sign * delta);
-                    moved = true;
-                }
+        for &(i, j) in &pairs {
+            let id_a = &ids[i];
+            let id_b = &ids[j];
+            let (ax, ay, aw, ah, bx, by, bw, bh) = {
+                let a = &nodes[id_a];
+                let b = &nodes[id_b];
+                (a.x, a.y, a.width, a.height, b.x, b.y, b.width, b.height)
+            };
+            let overlap_x = (ax + aw).min(bx + bw) - ax.max(bx);
+            let overlap_y = (ay + ah).min(by + bh) - ay.max(by);
+            if overlap_x <= 0.0 || overlap_y <= 0.0 {
+                continue;
+            }
+            let (center_a, center_b) = if horizontal {
+                (ay + ah / 2.0, by + bh / 2.0)
+            } else {
+                (ax + aw / 2.0, bx + bw / 2.0)
+            };
+            let mut sign = if center_b >= center_a { 1.0 } else { -1.0 };
+            if (center_b - center_a).abs() < OVERLAP_CENTER_THRESHOLD {
+                let order_a = graph.node_order.get(id_a).copied().unwrap_or(usize::MAX);
+                let order_b = graph.node_order.get(id_b).copied().unwrap_or(usize::MAX);
+                sign = if order_b >= order_a { 1.0 } else { -1.0 };
+            }
+            let delta = if horizontal {
+                overlap_y + min_gap
+            } else {
+                overlap_x + min_gap
+            };
+            if let Some(node_b) = nodes.get_mut(id_b) {
+                shift_node_cross(node_b, horizontal, sign * delta);
+                moved = true;
             }
         }
         if !moved {
             break;
         }
     }
+    passes
 }
 
 fn has_visible_node_overlap(nodes: &BTreeMap<String, NodeLayout>) -> bool {
@@ -5501,6 +6113,7 @@ fn build_subgraph_layouts(
     nodes: &BTreeMap<String, NodeLayout>,
     theme: &Theme,
     config: &LayoutConfig,
+    directions: &[Direction],
 ) -> Vec<SubgraphLayout> {
     let mut subgraphs = Vec::new();
     let mut retained_indices = Vec::new();
@@ -5555,6 +6168,7 @@ fn build_subgraph_layouts(
             height: (max_y - min_y) + padding_y + top_padding,
             style,
             icon: sub.icon.clone(),
+            direction: directions[sub_idx],
         });
     }
 
@@ -5668,16 +6282,26 @@ fn merge_node_style(target: &mut crate::ir::NodeStyle, source: &crate::ir::NodeS
     if source.line_color.is_some() {
         target.line_color = source.line_color.clone();
     }
+    if source.opacity.is_some() {
+        target.opacity = source.opacity;
+    }
 }
 
-fn shape_padding_factors(shape: crate::ir::NodeShape) -> (f32, f32) {
-    match shape {
-        crate::ir::NodeShape::Stadium => (0.43, 0.5),
-        crate::ir::NodeShape::Subroutine => (0.54, 0.5),
-        crate::ir::NodeShape::Parallelogram => (0.894, 0.5),
-        crate::ir::NodeShape::ParallelogramAlt => (0.904, 0.5),
-        _ => (1.0, 1.0),
-    }
+fn shape_padding_factors(shape: crate::ir::NodeShape, config: &LayoutConfig) -> (f32, f32) {
+    config
+        .flowchart
+        .shape_padding
+        .get(&shape)
+        .copied()
+        .unwrap_or((1.0, 1.0))
+}
+
+/// Header followed by the requirement's fields; with `compartments` each
+/// field after the first is preceded by a `---` line, which the renderer
+/// draws as a divider as it does for class compartments.
+fn requirement_label_text(node: &crate::ir::Node, compartments: bool) -> String {
+    let separator = if compartments { "\n---\n" } else { "\n" };
+    format!("{}\n{}", node.label, node.fields.join(separator))
 }
 
 fn has_divider_line(label: &TextBlock) -> bool {
@@ -5691,7 +6315,7 @@ fn shape_size(
     theme: &Theme,
     kind: crate::ir::DiagramKind,
 ) -> (f32, f32) {
-    let (pad_x_factor, pad_y_factor) = shape_padding_factors(shape);
+    let (pad_x_factor, pad_y_factor) = shape_padding_factors(shape, config);
     let (kind_pad_x_scale, kind_pad_y_scale) = match kind {
         crate::ir::DiagramKind::Class => {
             let pad_x_scale = if has_divider_line(label) { 0.85 } else { 0.4 };
@@ -5759,6 +6383,18 @@ fn shape_size(
         | crate::ir::NodeShape::Asymmetric => {
             width *= TRAPEZOID_WIDTH_SCALE;
         }
+        crate::ir::NodeShape::Document | crate::ir::NodeShape::LinedDocument => {
+            // Leave room under the label for the wavy bottom edge.
+            height *= DOCUMENT_HEIGHT_SCALE;
+        }
+        crate::ir::NodeShape::Brace | crate::ir::NodeShape::Braces => {
+            width *= BRACE_WIDTH_SCALE;
+        }
+        crate::ir::NodeShape::Bolt => {
+            let size = width.max(height) * BOLT_SCALE;
+            width = size;
+            height = size;
+        }
         crate::ir::NodeShape::Subroutine => {}
         _ => {}
     }
@@ -5841,6 +6477,847 @@ mod tests {
         assert!(b.x >= a.x);
     }
 
+    #[test]
+    fn center_children_places_siblings_symmetrically() {
+        let parsed = parse_mermaid("flowchart TD\nP --> A\nP --> B\nP --> C\nP --> D").unwrap();
+        let mut config = LayoutConfig::default();
+        config.flowchart.center_children = true;
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let center = |id: &str| {
+            let node = &layout.nodes[id];
+            node.x + node.width / 2.0
+        };
+        let parent = center("P");
+        let mut children: Vec<f32> = ["A", "B", "C", "D"].iter().map(|id| center(id)).collect();
+        children.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for idx in 0..2 {
+            let left = parent - children[idx];
+            let right = children[3 - idx] - parent;
+            assert!((left - right).abs() < 0.5, "left {left} vs right {right}");
+        }
+    }
+
+    #[test]
+    fn xychart_si_tick_format_abbreviates_large_values() {
+        let source = "xychart-beta\nx-axis [a, b, c]\ny-axis \"Revenue\" 0 --> 2000000\nbar [500000, 1200000, 2000000]";
+        let parsed = parse_mermaid(source).unwrap();
+        let mut config = LayoutConfig::default();
+        let ticks = |config: &LayoutConfig| {
+            let layout = compute_layout(&parsed.graph, &Theme::modern(), config);
+            let DiagramData::XYChart(chart) = layout.diagram else {
+                panic!("expected xychart layout");
+            };
+            chart
+                .y_axis_ticks
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ticks(&config).last().map(String::as_str), Some("2000000"));
+        config.xychart.y_tick_format = crate::config::TickFormat::SI;
+        assert_eq!(ticks(&config), ["0", "400k", "800k", "1.2M", "1.6M", "2M"]);
+        config.xychart.y_tick_format = crate::config::TickFormat::Currency("$".into());
+        assert_eq!(ticks(&config)[1], "$400k");
+        config.xychart.y_tick_format = crate::config::TickFormat::Percent;
+        assert_eq!(ticks(&config)[0], "0%");
+    }
+
+    #[test]
+    fn gantt_bar_height_grows_rows_and_chart() {
+        let source = "gantt\n  dateFormat YYYY-MM-DD\n  section One\n  A : a1, 2026-01-01, 2d\n  B : b1, after a1, 2d";
+        let parsed = parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let base = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+        let mut config = LayoutConfig::default();
+        config.gantt.bar_height = Some(48.0);
+        config.gantt.bar_corner_radius = 12.0;
+        let tall = compute_layout(&parsed.graph, &theme, &config);
+        let (DiagramData::Gantt(base_gantt), DiagramData::Gantt(tall_gantt)) =
+            (&base.diagram, &tall.diagram)
+        else {
+            panic!("expected gantt layouts");
+        };
+        assert!(tall_gantt.tasks[0].height > base_gantt.tasks[0].height);
+        assert!(tall_gantt.tasks[0].height >= 48.0);
+        assert!(tall.height > base.height);
+        let svg = crate::render::render_svg(&tall, &theme, &config);
+        assert!(svg.contains("height=\"48.00\" rx=\"12\""));
+    }
+
+    #[test]
+    fn gantt_alternate_bands_alternate_section_colors() {
+        let source = "gantt\n  dateFormat YYYY-MM-DD\n  section One\n  A : a1, 2026-01-01, 2d\n  section Two\n  B : b1, after a1, 2d\n  section Three\n  C : c1, after b1, 2d";
+        let parsed = parse_mermaid(source).unwrap();
+        let mut config = LayoutConfig::default();
+        config.gantt.alternate_bands = true;
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let DiagramData::Gantt(gantt) = &layout.diagram else {
+            panic!("expected gantt layout");
+        };
+        let bands: Vec<&str> = gantt
+            .sections
+            .iter()
+            .map(|section| section.band_color.as_str())
+            .collect();
+        assert_eq!(bands.len(), 3);
+        assert_ne!(bands[0], bands[1]);
+        assert_eq!(bands[0], bands[2]);
+    }
+
+    #[test]
+    fn class_multiplicities_sit_at_their_own_ends() {
+        let parsed = parse_mermaid("classDiagram\nOrder \"1\" --> \"*\" Item : places").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let edge = &layout.edges[0];
+        assert!(edge.label_anchor.is_some());
+        let dist = |a: (f32, f32), node: &str| {
+            let n = &layout.nodes[node];
+            (a.0 - (n.x + n.width / 2.0)).hypot(a.1 - (n.y + n.height / 2.0))
+        };
+        let start = edge.start_label_anchor.expect("start multiplicity");
+        let end = edge.end_label_anchor.expect("end multiplicity");
+        assert!(dist(start, "Order") < dist(start, "Item"));
+        assert!(dist(end, "Item") < dist(end, "Order"));
+
+        let svg = crate::render::render_svg(&layout, &theme, &config);
+        for text in ["places", "1", "*"] {
+            assert!(svg.contains(&format!(">{text}</tspan>")), "{text}");
+        }
+
+        config.labels.endpoint_label_offset = Some(30.0);
+        let spaced = compute_layout(&parsed.graph, &theme, &config);
+        let spaced_start = spaced.edges[0].start_label_anchor.unwrap();
+        let origin = spaced.edges[0].points[0];
+        assert!(
+            (spaced_start.0 - origin.0).hypot(spaced_start.1 - origin.1)
+                > (start.0 - origin.0).hypot(start.1 - origin.1)
+        );
+    }
+
+    #[test]
+    fn gantt_hidden_sections_drop_rows_and_compact_chart() {
+        let source = "gantt\n  dateFormat YYYY-MM-DD\n  section Design\n  Sketch : a1, 2026-01-01, 2d\n  section Build\n  Code : b1, after a1, 3d\n  Test : b2, after b1, 2d\n  section Ship\n  Release : c1, after b2, 1d";
+        let parsed = parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let full = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+
+        let mut config = LayoutConfig::default();
+        config.gantt.hidden_sections.insert("Build".to_string());
+        config.gantt.show_dependencies = true;
+        let focused = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Gantt(gantt) = &focused.diagram else {
+            panic!("expected gantt layout");
+        };
+        let labels: Vec<&str> = gantt
+            .tasks
+            .iter()
+            .map(|task| task.label.lines[0].as_str())
+            .collect();
+        assert_eq!(labels, vec!["Sketch", "Release"]);
+        let sections: Vec<&str> = gantt
+            .sections
+            .iter()
+            .map(|section| section.label.lines[0].as_str())
+            .collect();
+        assert_eq!(sections, vec!["Design", "Ship"]);
+        assert!(focused.height < full.height);
+        assert!(gantt.dependencies.is_empty());
+        // Hidden tasks still push back the ones that depend on them.
+        assert_eq!(gantt.tasks[1].start - gantt.tasks[0].start, 7.0);
+    }
+
+    #[test]
+    fn gantt_dependencies_draw_arrows_between_tasks() {
+        let source = "gantt\n  dateFormat YYYY-MM-DD\n  section One\n  First : task1, 2026-01-01, 2d\n  Other : other, 2026-01-01, 4d\n  Second : task2, after task1, 2d\n  Last : task3, after task1 other, 1d";
+        let parsed = parse_mermaid(source).unwrap();
+        let last = &parsed.graph.gantt_tasks[3];
+        assert_eq!(last.after.as_deref(), Some("task1"));
+        assert_eq!(last.after_all, vec!["task1", "other"]);
+        let theme = Theme::modern();
+        let plain = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+        let DiagramData::Gantt(plain_gantt) = &plain.diagram else {
+            panic!("expected gantt layout");
+        };
+        assert!(plain_gantt.dependencies.is_empty());
+
+        let mut config = LayoutConfig::default();
+        config.gantt.show_dependencies = true;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Gantt(gantt) = &layout.diagram else {
+            panic!("expected gantt layout");
+        };
+        assert_eq!(gantt.dependencies, vec![(0, 2), (0, 3), (1, 3)]);
+        // A task with several prerequisites waits for the last one.
+        assert_eq!(
+            gantt.tasks[3].start,
+            gantt.tasks[1].start + gantt.tasks[1].duration
+        );
+
+        let svg = crate::render::render_svg(&layout, &theme, &config);
+        let task1 = &gantt.tasks[0];
+        let task2 = &gantt.tasks[2];
+        let start = format!(
+            "d=\"M {:.2},{:.2} ",
+            task1.x + task1.width,
+            task1.y + gantt.row_height / 2.0
+        );
+        let end = format!(" H {:.2}\"", task2.x);
+        let arrow = svg
+            .split("<path class=\"gantt-dependency\" ")
+            .nth(1)
+            .expect("dependency arrow");
+        assert!(arrow.starts_with(&start), "{arrow}");
+        let d_end = arrow.find("\" fill=").unwrap() + 1;
+        assert!(arrow[..d_end].ends_with(&end), "{arrow}");
+        assert_eq!(svg.matches("class=\"gantt-dependency\"").count(), 3);
+    }
+
+    #[test]
+    fn subgraph_layout_reports_declared_direction() {
+        let source = "flowchart TD\nsubgraph Inner\ndirection LR\nF[One] --> G[Two]\nend\nsubgraph Plain\nH --> I\nend\nA --> F";
+        let parsed = parse_mermaid(source).unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let direction_of = |label: &str| {
+            layout
+                .subgraphs
+                .iter()
+                .find(|sub| sub.label == label)
+                .map(|sub| sub.direction)
+        };
+        assert_eq!(direction_of("Inner"), Some(Direction::LeftRight));
+        assert_eq!(direction_of("Plain"), Some(Direction::TopDown));
+    }
+
+    #[test]
+    fn element_boxes_match_node_layouts() {
+        let parsed = parse_mermaid("flowchart LR\nsubgraph S\nA --> B\nend\nB -->|go| C").unwrap();
+        let mut layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        // A node whose id matches an edge's SVG id keeps its own entry.
+        let mut clash = layout.nodes["C"].clone();
+        clash.id = "edge-1".to_string();
+        clash.x += 500.0;
+        layout.nodes.insert(clash.id.clone(), clash);
+        let boxes = layout.element_boxes();
+        let key = |kind: ElementKind, id: &str| (kind, id.to_string());
+        for node in layout.nodes.values() {
+            assert_eq!(
+                boxes[&key(ElementKind::Node, &node.id)],
+                (node.x, node.y, node.width, node.height)
+            );
+        }
+        let sub = &layout.subgraphs[0];
+        assert_eq!(
+            boxes[&key(ElementKind::Subgraph, "subgraph-0")],
+            (sub.x, sub.y, sub.width, sub.height)
+        );
+        let edge = &layout.edges[1];
+        let (x, y, w, h) = boxes[&key(ElementKind::Edge, "edge-1")];
+        for &(px, py) in &edge.points {
+            assert!(px >= x && px <= x + w && py >= y && py <= y + h);
+        }
+        let label = edge.label.as_ref().unwrap();
+        assert!(w >= label.width && h >= label.height);
+    }
+
+    #[test]
+    fn add_edge_routes_between_existing_nodes() {
+        let parsed = parse_mermaid("flowchart TD\nA --> B\nB --> C").unwrap();
+        let mut layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let opts = AddEdgeOptions::default();
+        let added = layout.add_edge("A", "C", &opts).unwrap();
+        assert_eq!(added.index, 2);
+        assert!(!added.relayout_advised);
+        let points = &layout.edges[added.index].points;
+        assert!(points.len() >= 2);
+        let (a, c) = (&layout.nodes["A"], &layout.nodes["C"]);
+        let near = |(x, y): (f32, f32), node: &NodeLayout| {
+            x >= node.x - 1.0
+                && x <= node.x + node.width + 1.0
+                && y >= node.y - 1.0
+                && y <= node.y + node.height + 1.0
+        };
+        assert!(near(points[0], a));
+        assert!(near(*points.last().unwrap(), c));
+        let back = layout.add_edge("C", "A", &opts).unwrap();
+        assert!(back.relayout_advised);
+        for edge in &layout.edges {
+            for &(x, y) in &edge.points {
+                assert!(x <= layout.width && y <= layout.height);
+            }
+        }
+        assert!(layout.add_edge("A", "missing", &opts).is_none());
+
+        // A self-loop on a node at the top-left corner pushes the content
+        // over instead of leaving the canvas.
+        let parsed = parse_mermaid("flowchart LR\nA --> B").unwrap();
+        let mut layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let before = layout.nodes["A"].clone();
+        let opts = AddEdgeOptions {
+            direction: Direction::LeftRight,
+            ..AddEdgeOptions::default()
+        };
+        let looped = layout.add_edge("A", "A", &opts).unwrap();
+        assert!(!looped.relayout_advised);
+        let a = &layout.nodes["A"];
+        assert_eq!(a.x, before.x);
+        assert!(a.y > before.y);
+        for edge in &layout.edges {
+            for &(x, y) in &edge.points {
+                assert!(x >= 0.0 && y >= 0.0 && x <= layout.width && y <= layout.height);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_label_offset_moves_label_off_the_line() {
+        let parsed = parse_mermaid("flowchart LR\nA -->|label| B").unwrap();
+        let mut config = LayoutConfig::default();
+        config.flowchart.edge_label_offset = 12.0;
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let edge = &layout.edges[0];
+        let (a, b) = (edge.points[0], *edge.points.last().unwrap());
+        assert!((a.1 - b.1).abs() < 1e-3, "expected a straight LR edge");
+        let label = edge.label.as_ref().unwrap();
+        let (x, y) = edge.label_anchor.unwrap();
+        assert!(x > a.0.min(b.0) && x < a.0.max(b.0));
+        assert!((y - a.1).abs() >= 12.0 + label.height / 2.0 - 1e-3);
+
+        // The offset is a flowchart setting; other graph diagrams ignore it.
+        let parsed = parse_mermaid("stateDiagram-v2\nA --> B: label").unwrap();
+        let plain = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let offset = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        assert_eq!(offset.edges[0].label_anchor, plain.edges[0].label_anchor);
+    }
+
+    #[test]
+    fn max_subgraph_depth_flattens_deeper_levels() {
+        let input = "flowchart TD\nsubgraph L1[Level one]\nA\nsubgraph L2[Level two]\nB\nsubgraph L3[Level three]\nC\nsubgraph L4[Level four]\nD\nsubgraph L5[Level five]\nE\nend\nend\nend\nend\nend";
+        let parsed = parse_mermaid(input).unwrap();
+        assert_eq!(parsed.graph.subgraphs.len(), 5);
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.flowchart.max_subgraph_depth = Some(2);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let labels: Vec<&str> = layout
+            .subgraphs
+            .iter()
+            .map(|sub| sub.label.as_str())
+            .collect();
+        assert_eq!(labels, ["Level one", "Level two"]);
+        let level_two = &layout.subgraphs[1];
+        for id in ["B", "C", "D", "E"] {
+            assert!(level_two.nodes.iter().any(|node| node == id));
+            let node = &layout.nodes[id];
+            assert!(node.x >= level_two.x && node.x + node.width <= level_two.x + level_two.width);
+        }
+        let svg = crate::render::render_svg(&layout, &theme, &config);
+        assert!(svg.contains("Level two"));
+        assert!(!svg.contains("Level three") && !svg.contains("Level five"));
+    }
+
+    #[test]
+    fn flowchart_self_loop_routes_orthogonal_loop() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        for input in [
+            "flowchart TD\nA-->A\nA-->B",
+            "flowchart LR\nA-->|retry|A\nA-->B",
+        ] {
+            let parsed = parse_mermaid(input).unwrap();
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let node = &layout.nodes["A"];
+            let next = &layout.nodes["B"];
+            if input.contains("LR") {
+                assert!(node.x + node.width <= next.x, "{input}: A should precede B");
+            } else {
+                assert!(
+                    node.y + node.height <= next.y,
+                    "{input}: A should precede B"
+                );
+            }
+            let points = &layout.edges[0].points;
+            assert!((4..=6).contains(&points.len()), "{input}: {points:?}");
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert!(
+                    (a.0 - b.0).abs() < 0.01 || (a.1 - b.1).abs() < 0.01,
+                    "{input}: diagonal segment {a:?} -> {b:?}"
+                );
+            }
+            let on_boundary = |(x, y): (f32, f32)| {
+                let within_x = x >= node.x - 0.01 && x <= node.x + node.width + 0.01;
+                let within_y = y >= node.y - 0.01 && y <= node.y + node.height + 0.01;
+                let on_x_edge = (x - node.x).abs() < 0.01 || (x - node.x - node.width).abs() < 0.01;
+                let on_y_edge =
+                    (y - node.y).abs() < 0.01 || (y - node.y - node.height).abs() < 0.01;
+                within_x && within_y && (on_x_edge || on_y_edge)
+            };
+            assert!(on_boundary(points[0]) && on_boundary(*points.last().unwrap()));
+            for &(x, y) in points {
+                assert!(x >= 0.0 && x <= layout.width && y >= 0.0 && y <= layout.height);
+            }
+        }
+    }
+
+    #[test]
+    fn flowchart_subgraph_direction_overrides_parent() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let cases = [
+            "flowchart TD\nsubgraph LoopBlock\ndirection LR\nF[One]-->G[Two]\nend",
+            "flowchart TD\nX --> LoopBlock\nsubgraph LoopBlock\ndirection LR\nF[One]-->G[Two]\nend\nLoopBlock --> Y",
+        ];
+        for input in cases {
+            let parsed = parse_mermaid(input).unwrap();
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let (f, g) = (&layout.nodes["F"], &layout.nodes["G"]);
+            assert!(g.x > f.x + f.width, "{input}");
+            assert!((f.y + f.height / 2.0 - g.y - g.height / 2.0).abs() < 1.0);
+            let sub = &layout.subgraphs[0];
+            assert_eq!(sub.direction, Direction::LeftRight);
+            assert!(
+                f.x >= sub.x && g.x + g.width <= sub.x + sub.width,
+                "{input}"
+            );
+        }
+
+        let parsed = parse_mermaid(
+            "flowchart TD\nsubgraph One\ndirection LR\nA --> B\nend\nsubgraph Two\ndirection TB\nC --> D\nend\nB --> C",
+        )
+        .unwrap();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        assert!(layout.nodes["B"].x > layout.nodes["A"].x);
+        assert!(layout.nodes["D"].y > layout.nodes["C"].y);
+    }
+
+    #[test]
+    fn nested_subgraph_direction_follows_inheritance_setting() {
+        let theme = Theme::modern();
+        let input = "flowchart TD\nsubgraph Outer\ndirection LR\nsubgraph Inner\nA --> B\nend\nB --> C\nend";
+        let parsed = parse_mermaid(input).unwrap();
+
+        let mut config = LayoutConfig::default();
+        config.flowchart.subgraph_direction_inheritance = DirectionInheritance::FromParent;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let (a, b) = (&layout.nodes["A"], &layout.nodes["B"]);
+        assert!(b.x >= a.x + a.width);
+        assert!((a.y - b.y).abs() < 1.0);
+        let inner = layout
+            .subgraphs
+            .iter()
+            .find(|sub| sub.label == "Inner")
+            .unwrap();
+        assert_eq!(inner.direction, Direction::LeftRight);
+
+        config.flowchart.subgraph_direction_inheritance = DirectionInheritance::FromGraph;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let (a, b) = (&layout.nodes["A"], &layout.nodes["B"]);
+        assert!(b.y >= a.y + a.height);
+        assert!((a.x + a.width / 2.0 - b.x - b.width / 2.0).abs() < 1.0);
+        let inner = layout
+            .subgraphs
+            .iter()
+            .find(|sub| sub.label == "Inner")
+            .unwrap();
+        assert_eq!(inner.direction, Direction::TopDown);
+    }
+
+    #[test]
+    fn subgraph_class_applies_by_id_or_quoted_label() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let input = "flowchart LR\nclassDef highlight fill:#fe0\nsubgraph foo\nA --> B\nend\nsubgraph \"Quoted\"\nC\nend\nclass foo highlight\nclass Quoted highlight";
+        let parsed = parse_mermaid(input).unwrap();
+        assert_eq!(parsed.graph.subgraphs[1].id, None);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        for label in ["foo", "Quoted"] {
+            let sub = layout
+                .subgraphs
+                .iter()
+                .find(|sub| sub.label == label)
+                .unwrap();
+            assert_eq!(sub.style.fill.as_deref(), Some("#fe0"), "{label}");
+        }
+    }
+
+    #[test]
+    fn compose_places_layouts_side_by_side() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let first = compute_layout(
+            &parse_mermaid("flowchart LR\nA --> B").unwrap().graph,
+            &theme,
+            &config,
+        );
+        let second = compute_layout(
+            &parse_mermaid("flowchart TD\nA --> C").unwrap().graph,
+            &theme,
+            &config,
+        );
+        let combined = Layout::compose(
+            &[first.clone(), second.clone()],
+            Arrangement::Horizontal { gap: 40.0 },
+        );
+        assert!((combined.width - (first.width + 40.0 + second.width)).abs() < 0.01);
+        assert_eq!(combined.height, first.height.max(second.height));
+        for id in ["0:A", "0:B", "1:A", "1:C"] {
+            assert!(combined.nodes.contains_key(id), "missing {id}");
+        }
+        assert_eq!(combined.nodes["0:A"].x, first.nodes["A"].x);
+        assert_eq!(
+            combined.nodes["1:A"].x,
+            second.nodes["A"].x + first.width + 40.0
+        );
+        assert_eq!(combined.edges.len(), 2);
+        assert_eq!(combined.edges[1].from, "1:A");
+        let svg = crate::render::render_svg(&combined, &theme, &config);
+        assert_eq!(svg.matches("class=\"edgePath\"").count(), 2);
+    }
+
+    #[test]
+    fn orphan_edges_are_skipped_instead_of_panicking() {
+        let mut graph = parse_mermaid("flowchart LR\nA --> B\nB --> C\nlinkStyle 1 stroke:red")
+            .unwrap()
+            .graph;
+        graph.nodes.remove("C");
+        let orphan = graph.edges[1].clone();
+        graph.edges.insert(0, orphan);
+        graph.edges[0].to = "Missing".to_string();
+        let theme = Theme::modern();
+        let layout = compute_layout(&graph, &theme, &LayoutConfig::default());
+        assert_eq!(layout.edges.len(), 1);
+        assert_eq!(
+            (layout.edges[0].from.as_str(), layout.edges[0].to.as_str()),
+            ("A", "B")
+        );
+        // linkStyle 1 now points at A --> B and follows it to its new index.
+        assert_eq!(
+            layout.edges[0].override_style.stroke.as_deref(),
+            Some("red")
+        );
+        assert!(
+            crate::render::render_svg(&layout, &theme, &LayoutConfig::default()).contains("</svg>")
+        );
+
+        let orphans = take_orphan_edges(&mut graph);
+        let ends: Vec<(&str, &str)> = orphans
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect();
+        assert_eq!(ends, vec![("B", "Missing"), ("B", "C")]);
+        assert_eq!(graph.edge_styles.keys().collect::<Vec<_>>(), vec![&0]);
+        assert!(take_orphan_edges(&mut graph).is_empty());
+    }
+
+    #[test]
+    fn dedupe_edges_merges_identical_edges() {
+        let parsed =
+            parse_mermaid("flowchart LR\nA --> B\nA --> B\nA -->|x| B\nlinkStyle 2 stroke:red")
+                .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        assert_eq!(
+            compute_layout(&parsed.graph, &theme, &config).edges.len(),
+            3
+        );
+        config.flowchart.dedupe_edges = true;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        assert_eq!(layout.edges.len(), 2);
+        assert_eq!(
+            layout.edges[1].label.as_ref().map(|l| l.lines.join("")),
+            Some("x".into())
+        );
+
+        let mut graph = parsed.graph.clone();
+        assert_eq!(graph.dedupe_edges(), 1);
+        assert!(graph.edge_styles.contains_key(&1));
+        assert!(!graph.edge_styles.contains_key(&2));
+    }
+
+    #[test]
+    fn rank_alignment_positions_differing_heights() {
+        let parsed =
+            parse_mermaid("flowchart TD\nA --> B\nA --> C[\"one<br/>two<br/>three\"]").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let mut check = |align, expect: fn(&NodeLayout) -> f32| {
+            config.flowchart.rank_alignment = align;
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let (b, c) = (&layout.nodes["B"], &layout.nodes["C"]);
+            assert!(c.height > b.height);
+            assert!((expect(b) - expect(c)).abs() < 0.01, "{align:?}");
+        };
+        check(crate::config::RankAlign::Start, |n| n.y);
+        check(crate::config::RankAlign::Center, |n| n.y + n.height / 2.0);
+        check(crate::config::RankAlign::End, |n| n.y + n.height);
+    }
+
+    #[test]
+    fn shape_padding_override_grows_only_that_shape() {
+        let parsed = parse_mermaid("flowchart LR\nA{Decide} --> B[Box]").unwrap();
+        let theme = Theme::modern();
+        let base = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+        let mut config = LayoutConfig::default();
+        config
+            .flowchart
+            .shape_padding
+            .insert(NodeShape::Diamond, (2.0, 2.0));
+        let padded = compute_layout(&parsed.graph, &theme, &config);
+        assert!(padded.nodes["A"].width > base.nodes["A"].width);
+        assert!(padded.nodes["A"].height > base.nodes["A"].height);
+        assert_eq!(padded.nodes["B"].width, base.nodes["B"].width);
+        assert_eq!(padded.nodes["B"].height, base.nodes["B"].height);
+    }
+
+    #[test]
+    fn layout_metrics_count_dummy_nodes_and_order_passes() {
+        let parsed = parse_mermaid("flowchart TD\nA --> B\nB --> C\nC --> D\nA --> D").unwrap();
+        let (_, metrics) =
+            compute_layout_with_metrics(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        assert!(metrics.counts.dummy_nodes_created > 0);
+        assert!(metrics.counts.order_passes_run > 0);
+    }
+
+    #[test]
+    fn channel_routing_aligns_parallel_edges_to_shared_lane() {
+        let source = "flowchart TD\nA --> X\nA --> Y\nB --> X\nB --> Z\nC --> Y\nC --> Z";
+        let parsed = parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let lanes = |config: &LayoutConfig| {
+            let layout = compute_layout(&parsed.graph, &theme, config);
+            let top = layout.nodes["A"].y + layout.nodes["A"].height;
+            let bottom = layout.nodes["X"].y;
+            let mut lanes = Vec::new();
+            for edge in &layout.edges {
+                for pair in edge.points.windows(2) {
+                    let (a, b) = (pair[0], pair[1]);
+                    if (a.1 - b.1).abs() < 0.01 && a.1 > top && a.1 < bottom {
+                        lanes.push(a.1);
+                    }
+                }
+            }
+            lanes
+        };
+        let mut config = LayoutConfig::default();
+        config.flowchart.routing.channel_routing = true;
+        let lanes = lanes(&config);
+        assert!(lanes.len() >= 4, "expected bused edges: {lanes:?}");
+        assert!(
+            lanes.iter().all(|y| (y - lanes[0]).abs() < 0.01),
+            "{lanes:?}"
+        );
+    }
+
+    #[test]
+    fn nested_state_transition_leaves_composite_walls_clear() {
+        let source = "stateDiagram-v2\n[*] --> Idle\nIdle --> Outer\nstate Outer {\n[*] --> A\nstate Inner {\n[*] --> B\nB --> C\n}\nA --> Inner\n}\nC --> Idle\nC --> Finished\nFinished --> [*]";
+        let parsed = parse_mermaid(source).unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let inner = layout
+            .subgraphs
+            .iter()
+            .find(|sub| sub.label == "Inner")
+            .expect("inner composite");
+        let edge = layout
+            .edges
+            .iter()
+            .find(|edge| edge.from == "C" && edge.to == "Idle")
+            .expect("exit transition");
+        let inside = |(x, y): (f32, f32)| {
+            x > inner.x && x < inner.x + inner.width && y > inner.y && y < inner.y + inner.height
+        };
+        assert!(inside(edge.points[0]));
+        assert!(!inside(*edge.points.last().unwrap()));
+        for pair in edge.points[1..edge.points.len() - 1].windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if (a.0 - b.0).abs() < 0.5 {
+                for wall in [inner.x, inner.x + inner.width] {
+                    assert!(
+                        (a.0 - wall).abs() > 3.0,
+                        "{:?} hugs wall {wall}",
+                        edge.points
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn graph_over_node_limit_gets_error_layout() {
+        let mut source = String::from("flowchart TD\n");
+        for i in 0..30 {
+            source.push_str(&format!("N{i} --> N{}\n", i + 1));
+        }
+        let parsed = parse_mermaid(&source).unwrap();
+        let config = LayoutConfig {
+            max_nodes: 20,
+            ..LayoutConfig::default()
+        };
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let DiagramData::Error(error) = &layout.diagram else {
+            panic!("expected an error layout");
+        };
+        assert!(error.message.contains("31 nodes"), "{}", error.message);
+        assert!(layout.nodes.is_empty());
+
+        let config = LayoutConfig {
+            max_edges: 10,
+            ..LayoutConfig::default()
+        };
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        assert!(matches!(layout.diagram, DiagramData::Error(_)));
+        assert_eq!(
+            try_compute_layout(&parsed.graph, &Theme::modern(), &config).unwrap_err(),
+            MmdrError::TooManyEdges {
+                edges: 30,
+                limit: 10
+            }
+        );
+
+        let config = LayoutConfig {
+            complexity_budget: Some(50),
+            ..LayoutConfig::default()
+        };
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let DiagramData::Error(error) = &layout.diagram else {
+            panic!("expected an error layout");
+        };
+        assert!(error.message.contains("budget 50"), "{}", error.message);
+
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        assert!(!matches!(layout.diagram, DiagramData::Error(_)));
+    }
+
+    #[test]
+    fn sibling_subgraph_title_bands_do_not_overlap() {
+        let source = "flowchart TD\nsubgraph one [A considerably long title for the first cluster]\na\nend\nsubgraph two [Another equally long title for the second cluster]\nb\nend";
+        let parsed = parse_mermaid(source).unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        assert_eq!(layout.subgraphs.len(), 2);
+        let band = |sub: &SubgraphLayout| {
+            (
+                sub.x,
+                sub.y,
+                sub.x + sub.width,
+                sub.y + sub.label_block.height,
+            )
+        };
+        let (ax1, ay1, ax2, ay2) = band(&layout.subgraphs[0]);
+        let (bx1, by1, bx2, by2) = band(&layout.subgraphs[1]);
+        let overlap = ax1 < bx2 && bx1 < ax2 && ay1 < by2 && by1 < ay2;
+        assert!(
+            !overlap,
+            "title bands overlap: {:?} {:?}",
+            (ax1, ax2),
+            (bx1, bx2)
+        );
+    }
+
+    #[test]
+    fn pie_legend_labels_fit_max_width() {
+        let source = "pie\n\"A very long category name that keeps on going\" : 3\n\"Short\" : 1";
+        let parsed = parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let legend = |config: &LayoutConfig| {
+            let layout = compute_layout(&parsed.graph, &theme, config);
+            let DiagramData::Pie(pie) = layout.diagram else {
+                panic!("expected pie layout");
+            };
+            pie.legend
+        };
+        assert!(legend(&config)[0].label.width > 120.0);
+
+        config.labels.legend_max_width = Some(120.0);
+        let wrapped = legend(&config);
+        assert!(wrapped[0].label.lines.len() > 1);
+        assert!(wrapped[0].label.width <= 120.0);
+        assert_eq!(wrapped[1].label.lines, vec!["Short"]);
+
+        config.labels.legend_overflow = crate::config::LabelOverflow::Truncate;
+        let truncated = legend(&config);
+        assert_eq!(truncated[0].label.lines.len(), 1);
+        assert!(truncated[0].label.lines[0].ends_with('…'));
+        assert!(truncated[0].label.width <= 120.0);
+    }
+
+    #[test]
+    fn pie_legend_position_bottom_stacks_items_below_pie() {
+        let source = "pie title Pets\n\"Dogs\" : 386\n\"Cats\" : 85\n\"Rats\" : 15";
+        let parsed = parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let right = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+        let mut config = LayoutConfig::default();
+        config.pie.legend_position = crate::config::LegendPosition::Bottom;
+        let bottom = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Pie(pie) = &bottom.diagram else {
+            panic!("expected pie layout");
+        };
+        assert_eq!(pie.legend.len(), 3);
+        let below = pie.center.1 + pie.radius;
+        for pair in pie.legend.windows(2) {
+            assert_eq!(pair[0].y, pair[1].y);
+            assert!(pair[1].x > pair[0].x + pair[0].label.width);
+        }
+        assert!(pie.legend.iter().all(|item| item.y >= below));
+        assert!(bottom.height > right.height);
+
+        config.pie.legend_position = crate::config::LegendPosition::None;
+        let hidden = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Pie(pie) = &hidden.diagram else {
+            panic!("expected pie layout");
+        };
+        assert!(pie.legend.is_empty());
+        assert!((pie.center.0 * 2.0 - hidden.width).abs() < 0.01);
+    }
+
+    #[test]
+    fn pie_legend_max_items_adds_more_indicator() {
+        let mut source = String::from("pie title Ten");
+        for idx in 1..=10 {
+            source.push_str(&format!("\n\"Slice {idx}\" : {}", 11 - idx));
+        }
+        let parsed = parse_mermaid(&source).unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.pie.legend_max_items = Some(5);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Pie(pie) = &layout.diagram else {
+            panic!("expected pie layout");
+        };
+        assert_eq!(pie.legend.len(), 5);
+        let more = pie.legend_more.as_ref().expect("more indicator");
+        assert_eq!(more.hidden, 5);
+        assert_eq!(more.label.lines, vec!["+5 more"]);
+        assert!(more.y > pie.legend[4].y);
+
+        let svg = crate::render::render_svg(&layout, &theme, &config);
+        assert!(svg.contains("+5 more"));
+        assert!(!svg.contains("Slice 6"));
+    }
+
+    #[test]
+    fn wide_node_clearance_spreads_neighbors_of_wide_node() {
+        let theme = Theme::modern();
+        let sibling_gap = |source: &str, clearance: f32| {
+            let parsed = parse_mermaid(source).unwrap();
+            let mut config = LayoutConfig::default();
+            config.flowchart.wide_node_clearance = clearance;
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let (b, c) = (&layout.nodes["B"], &layout.nodes["C"]);
+            let (left, right) = if b.x < c.x { (b, c) } else { (c, b) };
+            right.x - (left.x + left.width)
+        };
+        let wide = "flowchart TD\nA[This source node has a very long label indeed] --> B\nA --> C";
+        let uniform = "flowchart TD\nA --> B\nA --> C";
+
+        assert!(sibling_gap(wide, 0.5) > sibling_gap(wide, 0.0) + 10.0);
+        assert!((sibling_gap(uniform, 0.5) - sibling_gap(uniform, 0.0)).abs() < 0.01);
+        assert!(sibling_gap(wide, 0.5) > sibling_gap(uniform, 0.5) + 10.0);
+    }
+
     #[test]
     fn edge_style_merges_default_and_override() {
         let mut graph = Graph::new();
@@ -5867,6 +7344,8 @@ mod tests {
             stroke_width: None,
             dasharray: None,
             label_color: Some("#222222".to_string()),
+            opacity: None,
+            midpoint_marker: None,
         });
         graph.edge_styles.insert(
             0,
@@ -5875,6 +7354,8 @@ mod tests {
                 stroke_width: Some(4.0),
                 dasharray: None,
                 label_color: None,
+                opacity: None,
+                midpoint_marker: None,
             },
         );
 
@@ -5923,6 +7404,7 @@ mod tests {
                 lines: vec![String::new()],
                 width: 0.0,
                 height: 0.0,
+                spans: Vec::new(),
             },
             shape: crate::ir::NodeShape::Rectangle,
             style: crate::ir::NodeStyle::default(),
@@ -5930,6 +7412,7 @@ mod tests {
             anchor_subgraph: None,
             hidden: false,
             icon: None,
+            tooltip: None,
         }
     }
 
@@ -6113,6 +7596,31 @@ mod tests {
         assert!((center.1 - 40.0).abs() <= 1e-3);
     }
 
+    #[test]
+    fn resolve_node_overlaps_separates_shared_rank_and_skips_other_ranks() {
+        let mut graph = Graph::new();
+        graph.direction = Direction::TopDown;
+        let mut nodes = BTreeMap::new();
+        for (i, (id, x, y)) in [
+            ("A", 0.0, 0.0),
+            ("B", 20.0, 5.0),
+            ("C", 30.0, 10.0),
+            ("D", 10.0, 200.0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            graph.node_order.insert(id.to_string(), i);
+            nodes.insert(id.to_string(), make_node(id, x, y, 40.0, 30.0));
+        }
+        let config = LayoutConfig::default();
+        let passes = resolve_node_overlaps(&graph, &mut nodes, &config);
+        assert!(passes >= 1);
+        assert!(!has_visible_node_overlap(&nodes));
+        assert_eq!(nodes["A"].x, 0.0);
+        assert_eq!(nodes["D"].x, 10.0);
+    }
+
     #[test]
     fn rank_edges_prefers_non_dotted_flow_edges_when_coverage_is_good() {
         let graph = Graph::new();
@@ -6128,7 +7636,12 @@ mod tests {
             make_edge("C", "D", crate::ir::EdgeStyle::Solid),
             make_edge("A", "D", crate::ir::EdgeStyle::Dotted),
         ];
-        let rank_edges = rank_edges_for_manual_layout(&graph, &nodes, &edges);
+        let rank_edges = rank_edges_for_manual_layout(
+            &graph,
+            &nodes,
+            &edges,
+            crate::config::CycleBreaking::Greedy,
+        );
         assert_eq!(rank_edges.len(), 3);
         assert!(
             rank_edges
@@ -6153,7 +7666,12 @@ mod tests {
             make_edge("D", "E", crate::ir::EdgeStyle::Dotted),
             make_edge("E", "C", crate::ir::EdgeStyle::Dotted),
         ];
-        let rank_edges = rank_edges_for_manual_layout(&graph, &nodes, &edges);
+        let rank_edges = rank_edges_for_manual_layout(
+            &graph,
+            &nodes,
+            &edges,
+            crate::config::CycleBreaking::Greedy,
+        );
         assert_eq!(rank_edges.len(), edges.len());
     }
 
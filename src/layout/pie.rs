@@ -1,13 +1,14 @@
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 
-use crate::config::LayoutConfig;
+use crate::config::{LayoutConfig, LegendPosition};
 use crate::ir::Graph;
 use crate::theme::Theme;
 
-use super::text::measure_label_with_font_size;
+use super::text::{fit_secondary_label, measure_label_with_font_size};
 use super::{
-    DiagramData, Layout, PieData, PieLegendItem, PieSliceLayout, PieTitleLayout, TextBlock,
+    DiagramData, Layout, PieData, PieLegendItem, PieLegendMore, PieSliceLayout, PieTitleLayout,
+    TextBlock,
 };
 
 fn pie_palette(theme: &Theme) -> Vec<String> {
@@ -31,7 +32,7 @@ pub(super) fn compute_pie_layout(graph: &Graph, theme: &Theme, config: &LayoutCo
     let title_block = graph.pie_title.as_ref().map(|title| {
         measure_label_with_font_size(
             title,
-            theme.pie_title_text_size,
+            theme.pie_title_text_size * config.labels.title_scale,
             config,
             false,
             theme.font_family.as_str(),
@@ -123,55 +124,168 @@ pub(super) fn compute_pie_layout(graph: &Graph, theme: &Theme, config: &LayoutCo
         } else {
             slice.label.clone()
         };
-        let label = measure_label_with_font_size(
-            &label_text,
+        let label = fit_secondary_label(
+            measure_label_with_font_size(
+                &label_text,
+                theme.pie_legend_text_size,
+                config,
+                false,
+                theme.font_family.as_str(),
+            ),
             theme.pie_legend_text_size,
             config,
-            false,
             theme.font_family.as_str(),
         );
         legend_width = legend_width.max(label.width);
         let color = resolve_color(&slice.label);
         legend_items.push((label, color));
     }
+    let legend_count = legend_items.len();
+
+    let mut more_block = None;
+    if let Some(max_items) = pie_cfg.legend_max_items
+        && legend_items.len() > max_items
+    {
+        legend_items.truncate(max_items);
+        let hidden = legend_count - max_items;
+        let label = measure_label_with_font_size(
+            &format!("+{hidden} more"),
+            theme.pie_legend_text_size,
+            config,
+            false,
+            theme.font_family.as_str(),
+        );
+        legend_width = legend_items
+            .iter()
+            .map(|(label, _)| label.width)
+            .fold(label.width, f32::max);
+        let value = graph.pie_slices[max_items..]
+            .iter()
+            .map(|slice| slice.value)
+            .sum();
+        more_block = Some((label, hidden, value));
+    }
+    let legend_rows = legend_items.len() + usize::from(more_block.is_some());
 
-    let legend_text_height = theme.pie_legend_text_size * 1.25;
+    let legend_lines = legend_items
+        .iter()
+        .map(|(label, _)| label.lines.len())
+        .max()
+        .unwrap_or(1);
+    let legend_text_height = theme.pie_legend_text_size * 1.25 * legend_lines as f32;
     let legend_item_height =
         (pie_cfg.legend_rect_size + pie_cfg.legend_spacing).max(legend_text_height);
-    let legend_offset = legend_item_height * legend_items.len() as f32 / 2.0;
-
-    let height = pie_cfg.height.max(1.0);
-    let pie_width = height;
-    let radius = (pie_width.min(height) / 2.0 - pie_cfg.margin).max(1.0);
-    let center_x = pie_width / 2.0;
-    let center_y = height / 2.0;
-    let legend_x = center_x + radius + pie_cfg.margin * 0.6;
-
-    for (idx, (label, color)) in legend_items.into_iter().enumerate() {
-        let vertical = idx as f32 * legend_item_height - legend_offset;
-        legend.push(PieLegendItem {
-            x: legend_x,
-            y: center_y + vertical,
-            label,
-            color,
-            marker_size: pie_cfg.legend_rect_size,
-            value: graph.pie_slices[idx].value,
-        });
+    let legend_offset = legend_item_height * legend_rows as f32 / 2.0;
+
+    let pie_height = pie_cfg.height.max(1.0);
+    let pie_width = pie_height;
+    let radius = (pie_width.min(pie_height) / 2.0 - pie_cfg.margin).max(1.0);
+    let mut center_x = pie_width / 2.0;
+    let center_y = pie_height / 2.0;
+    let mut height = pie_height;
+    // Without a legend beside the pie, keep room for category labels the
+    // renderer places outside thin slices (it suppresses them at 4+ items).
+    let outside_label_room = if legend_count >= 4 {
+        0.0
+    } else {
+        let bump = (theme.pie_section_text_size * 1.6).max(radius * 0.18);
+        slices
+            .iter()
+            .map(|slice| slice.label.width)
+            .fold(0.0, f32::max)
+            + bump
+            - pie_cfg.margin
     }
+    .max(0.0);
+    let padded_pie_width = pie_width + outside_label_room * 2.0;
+    let mut legend_more = None;
 
-    let width = legend_x
-        + pie_cfg.legend_rect_size
-        + pie_cfg.legend_spacing
-        + legend_width
-        + pie_cfg.margin * 0.4;
+    let width = match pie_cfg.legend_position {
+        LegendPosition::Right => {
+            let legend_x = center_x + radius + pie_cfg.margin * 0.6;
+            for (idx, (label, color)) in legend_items.into_iter().enumerate() {
+                let vertical = idx as f32 * legend_item_height - legend_offset;
+                legend.push(PieLegendItem {
+                    x: legend_x,
+                    y: center_y + vertical,
+                    label,
+                    color,
+                    marker_size: pie_cfg.legend_rect_size,
+                    value: graph.pie_slices[idx].value,
+                });
+            }
+            if let Some((label, hidden, value)) = more_block {
+                let vertical = legend.len() as f32 * legend_item_height - legend_offset;
+                legend_more = Some(PieLegendMore {
+                    x: legend_x,
+                    y: center_y + vertical,
+                    label,
+                    hidden,
+                    value,
+                });
+            }
+            legend_x
+                + pie_cfg.legend_rect_size
+                + pie_cfg.legend_spacing
+                + legend_width
+                + pie_cfg.margin * 0.4
+        }
+        LegendPosition::Bottom => {
+            // A single row centered under the pie; the canvas widens if the
+            // row is wider than the pie itself.
+            let item_gap = theme.pie_legend_text_size;
+            let mut item_widths: Vec<f32> = legend_items
+                .iter()
+                .map(|(label, _)| pie_cfg.legend_rect_size + pie_cfg.legend_spacing + label.width)
+                .collect();
+            if let Some((label, _, _)) = &more_block {
+                item_widths.push(label.width);
+            }
+            let row_width = item_widths.iter().sum::<f32>()
+                + item_gap * item_widths.len().saturating_sub(1) as f32;
+            let width = padded_pie_width.max(row_width + pie_cfg.margin * 0.8);
+            center_x = width / 2.0;
+            let legend_y = pie_height - pie_cfg.margin * 0.5;
+            let mut cursor_x = (width - row_width) / 2.0;
+            for (idx, (label, color)) in legend_items.into_iter().enumerate() {
+                legend.push(PieLegendItem {
+                    x: cursor_x,
+                    y: legend_y,
+                    label,
+                    color,
+                    marker_size: pie_cfg.legend_rect_size,
+                    value: graph.pie_slices[idx].value,
+                });
+                cursor_x += item_widths[idx] + item_gap;
+            }
+            if let Some((label, hidden, value)) = more_block {
+                legend_more = Some(PieLegendMore {
+                    x: cursor_x,
+                    y: legend_y,
+                    label,
+                    hidden,
+                    value,
+                });
+            }
+            if legend_rows > 0 {
+                height = legend_y + legend_item_height + pie_cfg.margin * 0.4;
+            }
+            width
+        }
+        LegendPosition::None => {
+            center_x = padded_pie_width / 2.0;
+            padded_pie_width
+        }
+    };
     let title_layout = title_block.map(|text| PieTitleLayout {
         x: center_x,
-        y: center_y - (height - 50.0) / 2.0,
+        y: center_y - (pie_height - 50.0) / 2.0,
         text,
     });
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -180,6 +294,7 @@ pub(super) fn compute_pie_layout(graph: &Graph, theme: &Theme, config: &LayoutCo
         diagram: DiagramData::Pie(PieData {
             slices,
             legend,
+            legend_more,
             center: (center_x, center_y),
             radius,
             title: title_layout,
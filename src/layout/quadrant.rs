@@ -4,7 +4,7 @@ use crate::config::LayoutConfig;
 use crate::ir::Graph;
 use crate::theme::Theme;
 
-use super::text::measure_label;
+use super::text::{fit_secondary_label, measure_label, measure_scaled_label};
 use super::{DiagramData, Layout, QuadrantLayout, QuadrantPointLayout, TextBlock};
 use super::{PlacementBounds, place_anchored_rect, rect_from_center};
 
@@ -31,45 +31,47 @@ pub(super) fn compute_quadrant_layout(
         .quadrant
         .title
         .as_ref()
-        .map(|t| measure_label(t, theme, config));
+        .map(|t| measure_scaled_label(t, theme, config, config.labels.title_scale));
     let title_height = title.as_ref().map(|t| t.height + padding).unwrap_or(0.0);
 
+    let secondary_label = |text: &String| {
+        fit_secondary_label(
+            measure_label(text, theme, config),
+            theme.font_size,
+            config,
+            theme.font_family.as_str(),
+        )
+    };
+    let axis_scale = config.labels.axis_label_scale;
+    let axis_label = |text: &String| {
+        fit_secondary_label(
+            measure_scaled_label(text, theme, config, axis_scale),
+            theme.font_size * axis_scale,
+            config,
+            theme.font_family.as_str(),
+        )
+    };
+
     // Measure axis labels
-    let x_left = graph
-        .quadrant
-        .x_axis_left
-        .as_ref()
-        .map(|t| measure_label(t, theme, config));
-    let x_right = graph
-        .quadrant
-        .x_axis_right
-        .as_ref()
-        .map(|t| measure_label(t, theme, config));
-    let y_bottom = graph
-        .quadrant
-        .y_axis_bottom
-        .as_ref()
-        .map(|t| measure_label(t, theme, config));
-    let y_top = graph
-        .quadrant
-        .y_axis_top
-        .as_ref()
-        .map(|t| measure_label(t, theme, config));
+    let x_left = graph.quadrant.x_axis_left.as_ref().map(axis_label);
+    let x_right = graph.quadrant.x_axis_right.as_ref().map(axis_label);
+    let y_bottom = graph.quadrant.y_axis_bottom.as_ref().map(axis_label);
+    let y_top = graph.quadrant.y_axis_top.as_ref().map(axis_label);
 
     // Measure quadrant labels
     let q_labels: [Option<TextBlock>; 4] = [
         graph.quadrant.quadrant_labels[0]
             .as_ref()
-            .map(|t| measure_label(t, theme, config)),
+            .map(secondary_label),
         graph.quadrant.quadrant_labels[1]
             .as_ref()
-            .map(|t| measure_label(t, theme, config)),
+            .map(secondary_label),
         graph.quadrant.quadrant_labels[2]
             .as_ref()
-            .map(|t| measure_label(t, theme, config)),
+            .map(secondary_label),
         graph.quadrant.quadrant_labels[3]
             .as_ref()
-            .map(|t| measure_label(t, theme, config)),
+            .map(secondary_label),
     ];
 
     let y_axis_label_width = y_bottom
@@ -213,6 +215,7 @@ pub(super) fn compute_quadrant_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
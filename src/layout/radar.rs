@@ -51,6 +51,7 @@ pub(super) fn compute_radar_layout(graph: &Graph, theme: &Theme, config: &Layout
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges: Vec::new(),
         subgraphs: Vec::new(),
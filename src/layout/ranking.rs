@@ -1,12 +1,32 @@
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
+use crate::config::CycleBreaking;
 use crate::ir::Graph;
 
 pub(super) fn rank_edges_for_manual_layout(
     graph: &Graph,
     layout_node_ids: &[String],
     layout_edges: &[crate::ir::Edge],
+    cycle_breaking: CycleBreaking,
+) -> Vec<crate::ir::Edge> {
+    let edges = primary_rank_edges(graph, layout_node_ids, layout_edges);
+    if graph.kind != crate::ir::DiagramKind::Flowchart || cycle_breaking == CycleBreaking::Greedy {
+        return edges;
+    }
+    let back_edges = dfs_back_edges(layout_node_ids, &edges, &graph.node_order);
+    edges
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !back_edges.contains(idx))
+        .map(|(_, edge)| edge)
+        .collect()
+}
+
+fn primary_rank_edges(
+    graph: &Graph,
+    layout_node_ids: &[String],
+    layout_edges: &[crate::ir::Edge],
 ) -> Vec<crate::ir::Edge> {
     if graph.kind != crate::ir::DiagramKind::Flowchart || layout_edges.len() < 3 {
         return layout_edges.to_vec();
@@ -34,14 +54,15 @@ pub(super) fn rank_edges_for_manual_layout(
     layout_edges.to_vec()
 }
 
+/// Reorders each rank to reduce crossings; returns the sweeps run.
 pub(super) fn order_rank_nodes(
     rank_nodes: &mut [Vec<String>],
     edges: &[crate::ir::Edge],
     node_order: &HashMap<String, usize>,
     passes: usize,
-) {
+) -> usize {
     if rank_nodes.len() <= 1 {
-        return;
+        return 0;
     }
     let mut incoming: HashMap<String, Vec<String>> = HashMap::new();
     let mut outgoing: HashMap<String, Vec<String>> = HashMap::new();
@@ -118,6 +139,7 @@ pub(super) fn order_rank_nodes(
             update_positions(rank_nodes, &mut positions);
         }
     }
+    passes
 }
 
 fn pair_crossings(
@@ -216,6 +238,54 @@ pub(super) fn median_position(
     }
 }
 
+/// Indices of edges that close a cycle when walking the graph depth-first,
+/// starting from nodes in declaration order. Self-loops count as back-edges.
+pub(super) fn dfs_back_edges(
+    node_ids: &[String],
+    edges: &[crate::ir::Edge],
+    node_order: &HashMap<String, usize>,
+) -> HashSet<usize> {
+    let mut adj: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+    for (idx, edge) in edges.iter().enumerate() {
+        adj.entry(edge.from.as_str())
+            .or_default()
+            .push((idx, edge.to.as_str()));
+    }
+    let mut roots: Vec<&str> = node_ids.iter().map(String::as_str).collect();
+    roots.sort_by_key(|id| node_order.get(*id).copied().unwrap_or(usize::MAX));
+
+    // 1 = on the current DFS path, 2 = finished.
+    let mut state: HashMap<&str, u8> = HashMap::new();
+    let mut back_edges = HashSet::new();
+    for root in roots {
+        if state.contains_key(root) {
+            continue;
+        }
+        state.insert(root, 1);
+        let mut stack: Vec<(&str, usize)> = vec![(root, 0)];
+        while let Some((node, next_child)) = stack.last_mut() {
+            let children = adj.get(*node).map(Vec::as_slice).unwrap_or_default();
+            let Some(&(edge_idx, child)) = children.get(*next_child) else {
+                state.insert(*node, 2);
+                stack.pop();
+                continue;
+            };
+            *next_child += 1;
+            match state.get(child) {
+                Some(1) => {
+                    back_edges.insert(edge_idx);
+                }
+                Some(_) => {}
+                None => {
+                    state.insert(child, 1);
+                    stack.push((child, 0));
+                }
+            }
+        }
+    }
+    back_edges
+}
+
 pub(super) fn compute_ranks_subset(
     node_ids: &[String],
     edges: &[crate::ir::Edge],
@@ -390,6 +460,43 @@ mod tests {
         assert_eq!(ranks.len(), 3);
     }
 
+    #[test]
+    fn cycle_breaking_strategies_rank_acyclically() {
+        let nodes: Vec<String> = vec!["A".into(), "B".into(), "C".into(), "D".into()];
+        let edges = vec![
+            edge("A", "B"),
+            edge("B", "C"),
+            edge("C", "A"),
+            edge("C", "D"),
+        ];
+        let order: HashMap<String, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, id)| (id.clone(), idx))
+            .collect();
+
+        let back_edges = dfs_back_edges(&nodes, &edges, &order);
+        assert_eq!(back_edges, HashSet::from([2]));
+
+        let forward: Vec<crate::ir::Edge> = edges
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !back_edges.contains(idx))
+            .map(|(_, edge)| edge.clone())
+            .collect();
+        for rank_edges in [&edges, &forward] {
+            let ranks = compute_ranks_subset(&nodes, rank_edges, &order);
+            assert_eq!(ranks.len(), 4);
+            let backward: Vec<usize> = edges
+                .iter()
+                .enumerate()
+                .filter(|(_, edge)| ranks[&edge.to] <= ranks[&edge.from])
+                .map(|(idx, _)| idx)
+                .collect();
+            assert_eq!(backward, vec![2]);
+        }
+    }
+
     #[test]
     fn compute_ranks_disconnected_nodes() {
         let nodes = vec!["A".into(), "B".into(), "C".into()];
@@ -65,6 +65,8 @@ const CHANNEL_CANDIDATE_RATIO: f32 = 0.75;
 const OBSTACLE_PAD_RATIO: f32 = 0.35;
 /// Minimum obstacle padding.
 const OBSTACLE_PAD_MIN: f32 = 6.0;
+/// Minimum gap kept between an edge and the wall of a composite it leaves.
+const COMPOSITE_EXIT_CLEARANCE_MIN: f32 = 12.0;
 
 // ── Occupancy overlap detection ─────────────────────────────────────
 /// Fraction of path-length-in-cells used to trigger occupancy detour.
@@ -87,7 +89,7 @@ const LABEL_OBSTACLE_NODE_PAD: f32 = 2.0;
 /// Padding around subgraph labels when building label obstacles.
 const LABEL_OBSTACLE_SUB_PAD: f32 = 3.0;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(super) enum EdgeSide {
     Left,
     Right,
@@ -783,6 +785,10 @@ pub(super) fn shape_polygon_points(node: &NodeLayout) -> Option<Vec<(f32, f32)>>
         | crate::ir::NodeShape::ActorBox
         | crate::ir::NodeShape::Stadium
         | crate::ir::NodeShape::Subroutine
+        | crate::ir::NodeShape::Document
+        | crate::ir::NodeShape::LinedDocument
+        | crate::ir::NodeShape::Brace
+        | crate::ir::NodeShape::Braces
         | crate::ir::NodeShape::Text => Some(vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h)]),
         crate::ir::NodeShape::Diamond => {
             let cx = x + w / 2.0;
@@ -2464,7 +2470,7 @@ pub(super) fn path_bend_count(points: &[(f32, f32)]) -> usize {
     bends
 }
 
-pub(super) fn edge_label_anchor_from_points(points: &[(f32, f32)]) -> Option<(f32, f32)> {
+pub(crate) fn edge_label_anchor_from_points(points: &[(f32, f32)]) -> Option<(f32, f32)> {
     // Center labels should stay on the geometric midpoint of the routed path
     // (arc-length progress 0.5), not merely the midpoint of the longest run.
     path_point_at_progress(points, 0.5)
@@ -2598,6 +2604,123 @@ pub(super) fn build_obstacles(
     obstacles
 }
 
+/// Pushes interior segments that run along a wall of a composite holding
+/// exactly one endpoint out past that wall, so the edge crosses the border
+/// instead of hugging it. Composites are handled innermost first. Returns
+/// how many edges moved.
+pub(super) fn clear_exited_composite_walls(
+    edges: &[(&str, &str)],
+    nodes: &BTreeMap<String, NodeLayout>,
+    subgraphs: &[SubgraphLayout],
+    routed_points: &mut [Vec<(f32, f32)>],
+    config: &LayoutConfig,
+) -> usize {
+    let clearance = (config.node_spacing * OBSTACLE_PAD_RATIO).max(COMPOSITE_EXIT_CLEARANCE_MIN);
+    let blocked = |vertical: bool, at: f32, lo: f32, hi: f32, margin: f32, ends: (&str, &str)| {
+        nodes.values().any(|node| {
+            if node.hidden
+                || node.anchor_subgraph.is_some()
+                || node.id == ends.0
+                || node.id == ends.1
+            {
+                return false;
+            }
+            let (near, far, span_lo, span_hi) = if vertical {
+                (node.x, node.x + node.width, node.y, node.y + node.height)
+            } else {
+                (node.y, node.y + node.height, node.x, node.x + node.width)
+            };
+            at > near - margin && at < far + margin && hi > span_lo && lo < span_hi
+        })
+    };
+    let mut moved = 0;
+    for (idx, &(from, to)) in edges.iter().enumerate() {
+        let points = &mut routed_points[idx];
+        if from == to || points.len() < 4 {
+            continue;
+        }
+        let mut exited: Vec<&SubgraphLayout> = subgraphs
+            .iter()
+            .filter(|sub| {
+                let has = |id: &str| sub.nodes.iter().any(|node| node == id);
+                has(from) != has(to)
+            })
+            .collect();
+        exited.sort_by(|a, b| {
+            (a.width * a.height)
+                .partial_cmp(&(b.width * b.height))
+                .unwrap_or(Ordering::Equal)
+        });
+        let mut changed = false;
+        for sub in exited {
+            for i in 1..points.len() - 2 {
+                let (a, b) = (points[i], points[i + 1]);
+                let vertical = (a.0 - b.0).abs() < 0.5;
+                if !vertical && (a.1 - b.1).abs() >= 0.5 {
+                    continue;
+                }
+                let (at, lo, hi, near, far, span_lo, span_hi) = if vertical {
+                    let (x0, x1) = (sub.x, sub.x + sub.width);
+                    (
+                        a.0,
+                        a.1.min(b.1),
+                        a.1.max(b.1),
+                        x0,
+                        x1,
+                        sub.y,
+                        sub.y + sub.height,
+                    )
+                } else {
+                    let (y0, y1) = (sub.y, sub.y + sub.height);
+                    (
+                        a.1,
+                        a.0.min(b.0),
+                        a.0.max(b.0),
+                        y0,
+                        y1,
+                        sub.x,
+                        sub.x + sub.width,
+                    )
+                };
+                if hi <= span_lo || lo >= span_hi {
+                    continue;
+                }
+                let (wall, outward) = if (at - near).abs() < clearance {
+                    (near, -1.0)
+                } else if (at - far).abs() < clearance {
+                    (far, 1.0)
+                } else {
+                    continue;
+                };
+                // Fall back to half the clearance when a neighbour sits
+                // close outside the wall.
+                let Some(target) = [clearance, clearance * 0.5]
+                    .into_iter()
+                    .map(|offset| (wall + outward * offset, offset))
+                    .find(|&(target, offset)| {
+                        !blocked(vertical, target, lo, hi, offset, (from, to))
+                    })
+                    .map(|(target, _)| target)
+                else {
+                    continue;
+                };
+                for p in [i, i + 1] {
+                    if vertical {
+                        points[p].0 = target;
+                    } else {
+                        points[p].1 = target;
+                    }
+                }
+                changed = true;
+            }
+        }
+        if changed {
+            moved += 1;
+        }
+    }
+    moved
+}
+
 pub(super) fn build_label_obstacles_for_routing(
     nodes: &BTreeMap<String, NodeLayout>,
     subgraphs: &[SubgraphLayout],
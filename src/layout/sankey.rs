@@ -15,10 +15,6 @@ pub(super) fn compute_sankey_layout(graph: &Graph, theme: &Theme, config: &Layou
     const SANKEY_MAX_WIDTH: f32 = 640.0;
     const SANKEY_HEIGHT: f32 = 360.0;
     const SANKEY_NODE_WIDTH: f32 = 10.0;
-    const SANKEY_PALETTE: [&str; 10] = [
-        "#4e79a7", "#f28e2c", "#e15759", "#76b7b2", "#59a14f", "#edc949", "#af7aa1", "#ff9da7",
-        "#9c755f", "#bab0ab",
-    ];
 
     let mut node_ids: Vec<String> = graph.nodes.keys().cloned().collect();
     node_ids.sort_by(|a, b| {
@@ -220,7 +216,7 @@ pub(super) fn compute_sankey_layout(graph: &Graph, theme: &Theme, config: &Layou
 
     let mut node_colors = Vec::with_capacity(node_count);
     for idx in 0..node_count {
-        let default_color = SANKEY_PALETTE[idx % SANKEY_PALETTE.len()].to_string();
+        let default_color = theme.sankey_colors[idx % theme.sankey_colors.len()].clone();
         let mut style = resolve_node_style(node_ids[idx].as_str(), graph);
         let color = style.fill.clone().unwrap_or(default_color);
         if style.fill.is_none() {
@@ -261,6 +257,7 @@ pub(super) fn compute_sankey_layout(graph: &Graph, theme: &Theme, config: &Layou
                 anchor_subgraph: None,
                 hidden: false,
                 icon: None,
+                tooltip: None,
             },
         );
         sankey_nodes.push(SankeyNodeLayout {
@@ -317,6 +314,8 @@ pub(super) fn compute_sankey_layout(graph: &Graph, theme: &Theme, config: &Layou
                 stroke_width: Some(thickness),
                 dasharray: None,
                 label_color: None,
+                opacity: None,
+                midpoint_marker: None,
             },
         });
         sankey_links.push(SankeyLinkLayout {
@@ -334,6 +333,7 @@ pub(super) fn compute_sankey_layout(graph: &Graph, theme: &Theme, config: &Layou
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs: Vec::new(),
@@ -148,7 +148,12 @@ fn expand_sequence_frames_for_nested_children(
     }
 
     let mut child_order: Vec<usize> = (0..frames.len()).collect();
-    child_order.sort_by_key(|&idx| specs[idx].end_idx.saturating_sub(specs[idx].start_idx));
+    child_order.sort_by_key(|&idx| {
+        (
+            specs[idx].end_idx.saturating_sub(specs[idx].start_idx),
+            std::cmp::Reverse(idx),
+        )
+    });
     let nested_pad_x = (theme.font_size * 0.95).max(10.0);
     let nested_pad_y = (theme.font_size * 0.75).max(8.0);
 
@@ -164,16 +169,27 @@ fn expand_sequence_frames_for_nested_children(
                 continue;
             }
             let child_spec = &specs[child_idx];
+            // `specs` lists enclosing frames first, so of two frames over the
+            // same messages the earlier one is the parent.
             let contains_child = parent_spec.start_idx <= child_spec.start_idx
                 && child_spec.end_idx <= parent_spec.end_idx
                 && (parent_spec.start_idx < child_spec.start_idx
-                    || child_spec.end_idx < parent_spec.end_idx);
+                    || child_spec.end_idx < parent_spec.end_idx
+                    || parent_idx < child_idx);
             if !contains_child {
                 continue;
             }
             let parent = &mut frames[parent_idx];
             let mut min_x = parent.x;
-            let mut min_y = parent.y;
+            // Opening on the same message, the parent's label box would
+            // cover the child's; lift the parent clear of it.
+            let mut min_y = if parent_spec.start_idx == child_spec.start_idx {
+                parent
+                    .y
+                    .min(child_rect.1 + nested_pad_y - parent.label_box.3 - nested_pad_y * 0.5)
+            } else {
+                parent.y
+            };
             let mut max_x = parent.x + parent.width;
             let mut max_y = parent.y + parent.height;
             extend_bounds(
@@ -246,10 +262,58 @@ pub(super) fn compute_sequence_layout(
         actor_gap *= 0.8;
     }
 
+    // Widen gaps so every message label fits between its two lifelines,
+    // resolving short spans first so longer ones only add what is still missing.
+    let participant_index: HashMap<&str, usize> = participants
+        .iter()
+        .enumerate()
+        .map(|(idx, id)| (id.as_str(), idx))
+        .collect();
+    let mut gaps = vec![
+        actor_gap.max(config.sequence.min_participant_gap);
+        participant_count.saturating_sub(1)
+    ];
+    let max_gap = config.sequence.max_participant_gap.max(actor_gap);
+    let mut label_spans: Vec<(usize, usize, f32)> = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let from = *participant_index.get(edge.from.as_str())?;
+            let to = *participant_index.get(edge.to.as_str())?;
+            let label = edge.label.as_ref()?;
+            if from == to {
+                return None;
+            }
+            let width = measure_label(label, theme, config).width + theme.font_size;
+            Some((from.min(to), from.max(to), width))
+        })
+        .collect();
+    label_spans.sort_by_key(|(lo, hi, _)| hi - lo);
+    let half_width = |idx: usize| {
+        participant_widths
+            .get(&participants[idx])
+            .copied()
+            .unwrap_or(min_actor_width)
+            / 2.0
+    };
+    for (lo, hi, required) in label_spans {
+        let current = half_width(lo)
+            + half_width(hi)
+            + (lo + 1..hi).map(|idx| half_width(idx) * 2.0).sum::<f32>()
+            + gaps[lo..hi].iter().sum::<f32>();
+        if required <= current {
+            continue;
+        }
+        let share = (required - current) / (hi - lo) as f32;
+        for gap in &mut gaps[lo..hi] {
+            *gap = (*gap + share).min(max_gap);
+        }
+    }
+
     // Add consistent margins to center the diagram
     let margin = 8.0;
     let mut cursor_x = margin;
-    for id in &participants {
+    for (idx, id) in participants.iter().enumerate() {
         let node = graph.nodes.get(id).expect("participant missing");
         let actor_width = participant_widths
             .get(id)
@@ -259,6 +323,7 @@ pub(super) fn compute_sequence_layout(
             lines: vec![id.clone()],
             width: 0.0,
             height: 0.0,
+            spans: Vec::new(),
         });
         nodes.insert(
             id.clone(),
@@ -275,9 +340,10 @@ pub(super) fn compute_sequence_layout(
                 anchor_subgraph: None,
                 hidden: false,
                 icon: None,
+                tooltip: None,
             },
         );
-        cursor_x += actor_width + actor_gap;
+        cursor_x += actor_width + gaps.get(idx).copied().unwrap_or(actor_gap);
     }
 
     let base_spacing = (theme.font_size * 2.1).max(18.0);
@@ -433,8 +499,17 @@ pub(super) fn compute_sequence_layout(
         }
         if idx < graph.edges.len() {
             message_cursor += extra_before[idx];
-            row_tops.push(message_cursor);
             let metrics = &edge_metrics[idx];
+            // Keep every message on its own line, however short the rows
+            // before it were.
+            if let Some(&prev_y) = message_ys.last() {
+                let min_gap = config.sequence.min_message_gap.max(1.0);
+                let shortfall = prev_y + min_gap - (message_cursor + metrics.line_y_offset);
+                if shortfall > 0.0 {
+                    message_cursor += shortfall;
+                }
+            }
+            row_tops.push(message_cursor);
             message_ys.push(message_cursor + metrics.line_y_offset);
             message_cursor += metrics.row_height;
         }
@@ -515,16 +590,26 @@ pub(super) fn compute_sequence_layout(
     let mut sequence_frames = Vec::new();
     let mut frame_specs_for_layout = Vec::new();
     if !graph.sequence_frames.is_empty() && !message_ys.is_empty() {
-        let mut frames = graph.sequence_frames.clone();
-        frames.sort_by(|a, b| {
+        // Frames close innermost first, so among frames covering the same
+        // messages the later one is the enclosing frame; sort it first.
+        let mut frames: Vec<(usize, crate::ir::SequenceFrame)> =
+            graph.sequence_frames.iter().cloned().enumerate().collect();
+        frames.sort_by(|(a_idx, a), (b_idx, b)| {
             a.start_idx
                 .cmp(&b.start_idx)
                 .then_with(|| b.end_idx.cmp(&a.end_idx))
+                .then_with(|| b_idx.cmp(a_idx))
         });
-        for frame in frames {
+        for (_, frame) in frames {
             if frame.start_idx >= frame.end_idx || frame.start_idx >= message_ys.len() {
                 continue;
             }
+            let depth = frame_specs_for_layout
+                .iter()
+                .filter(|outer: &&crate::ir::SequenceFrame| {
+                    outer.start_idx <= frame.start_idx && frame.end_idx <= outer.end_idx
+                })
+                .count();
             let mut min_x = f32::INFINITY;
             let mut max_x = f32::NEG_INFINITY;
             for edge in graph
@@ -552,8 +637,10 @@ pub(super) fn compute_sequence_layout(
                 continue;
             }
             let frame_pad_x = theme.font_size * 0.7;
-            let frame_x = min_x - frame_pad_x;
-            let frame_width = (max_x - min_x) + frame_pad_x * 2.0;
+            // Each enclosing frame pulls this one further inside its span.
+            let inset = (depth as f32 * frame_pad_x).min((max_x - min_x) * 0.25);
+            let frame_x = min_x - frame_pad_x + inset;
+            let frame_width = (max_x - min_x) + (frame_pad_x - inset) * 2.0;
 
             let first_y = message_ys
                 .get(frame.start_idx)
@@ -647,6 +734,7 @@ pub(super) fn compute_sequence_layout(
 
     let mut sequence_footboxes = participants
         .iter()
+        .filter(|_| config.sequence.show_footbox)
         .filter_map(|id| nodes.get(id))
         .map(|node| {
             let mut foot = node.clone();
@@ -795,7 +883,7 @@ pub(super) fn compute_sequence_layout(
                     y: number_y,
                     value,
                 });
-                value += 1;
+                value = value.saturating_add(graph.sequence_autonumber_step);
             }
         }
     }
@@ -1086,6 +1174,7 @@ pub(super) fn compute_sequence_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
@@ -1682,6 +1771,171 @@ fn extend_bounds(
 mod tests {
     use super::*;
 
+    #[test]
+    fn sequence_participants_widen_for_long_messages() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let short = crate::parser::parse_mermaid("sequenceDiagram\nAlice->>Bob: hi").unwrap();
+        let message = "a very long message that certainly does not fit between two lifelines";
+        let long =
+            crate::parser::parse_mermaid(&format!("sequenceDiagram\nAlice->>Bob: {message}"))
+                .unwrap();
+        let gap = |graph: &Graph| {
+            let layout = compute_sequence_layout(graph, &theme, &config);
+            let alice = &layout.nodes["Alice"];
+            let bob = &layout.nodes["Bob"];
+            (
+                bob.x - (alice.x + alice.width),
+                (bob.x + bob.width / 2.0) - (alice.x + alice.width / 2.0),
+            )
+        };
+        let (default_gap, _) = gap(&short.graph);
+        let (long_gap, center_distance) = gap(&long.graph);
+        let label_width = measure_label(message, &theme, &config).width;
+        assert!(long_gap > default_gap);
+        assert!(center_distance >= label_width);
+
+        let mut capped = LayoutConfig::default();
+        capped.sequence.max_participant_gap = default_gap;
+        let layout = compute_sequence_layout(&long.graph, &theme, &capped);
+        let capped_gap =
+            layout.nodes["Bob"].x - (layout.nodes["Alice"].x + layout.nodes["Alice"].width);
+        assert!((capped_gap - default_gap).abs() < 0.01);
+    }
+
+    #[test]
+    fn sequence_autonumber_saturates_instead_of_overflowing() {
+        let parsed = crate::parser::parse_mermaid(&format!(
+            "sequenceDiagram\nautonumber 1 {}\nA->>B: one\nB->>A: two\nA->>B: three",
+            usize::MAX
+        ))
+        .unwrap();
+        let layout =
+            compute_sequence_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let DiagramData::Sequence(data) = &layout.diagram else {
+            panic!("expected sequence data");
+        };
+        let values: Vec<usize> = data.numbers.iter().map(|number| number.value).collect();
+        assert_eq!(values, vec![1, usize::MAX, usize::MAX]);
+    }
+
+    #[test]
+    fn sequence_consecutive_messages_get_increasing_y() {
+        let theme = Theme::modern();
+        let parsed = crate::parser::parse_mermaid(
+            "sequenceDiagram\nA->>B: one\nC->>D: two\nB->>C\nD->>A: four\nA->>A: self\nC->>B",
+        )
+        .unwrap();
+        let line_ys = |config: &LayoutConfig| {
+            let layout = compute_sequence_layout(&parsed.graph, &theme, config);
+            layout
+                .edges
+                .iter()
+                .map(|edge| edge.points[0].1)
+                .collect::<Vec<_>>()
+        };
+        let default_ys = line_ys(&LayoutConfig::default());
+        assert_eq!(default_ys.len(), 6);
+        assert!(default_ys.windows(2).all(|pair| pair[1] > pair[0]));
+
+        let mut config = LayoutConfig::default();
+        config.sequence.min_message_gap = 80.0;
+        let spaced = line_ys(&config);
+        assert!(
+            spaced
+                .windows(2)
+                .all(|pair| pair[1] - pair[0] >= 80.0 - 0.01)
+        );
+    }
+
+    #[test]
+    fn sequence_nested_frames_are_inset_inside_parent() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let parsed = crate::parser::parse_mermaid(
+            "sequenceDiagram\npar Outer\nalt Inner\nA->>B: hi\nelse other\nB->>A: yo\nend\nend",
+        )
+        .unwrap();
+        let layout = compute_sequence_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Sequence(seq) = &layout.diagram else {
+            panic!("expected sequence layout");
+        };
+        let outer = seq
+            .frames
+            .iter()
+            .find(|frame| frame.kind == crate::ir::SequenceFrameKind::Par)
+            .unwrap();
+        let inner = seq
+            .frames
+            .iter()
+            .find(|frame| frame.kind == crate::ir::SequenceFrameKind::Alt)
+            .unwrap();
+        assert!(inner.x > outer.x);
+        assert!(inner.x + inner.width < outer.x + outer.width);
+        assert!(inner.y > outer.y + outer.label_box.3);
+        assert!(inner.y + inner.height < outer.y + outer.height);
+    }
+
+    #[test]
+    fn sequence_autonumber_applies_start_and_step() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let numbers = |input: &str| {
+            let parsed = crate::parser::parse_mermaid(input).unwrap();
+            let layout = compute_sequence_layout(&parsed.graph, &theme, &config);
+            let DiagramData::Sequence(seq) = &layout.diagram else {
+                panic!("expected sequence layout");
+            };
+            seq.numbers.iter().map(|n| n.value).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            numbers("sequenceDiagram\nautonumber 5\nA->>B: one\nB->>A: two"),
+            vec![5, 6]
+        );
+        assert_eq!(
+            numbers("sequenceDiagram\nautonumber 10 10\nA->>B: one\nB->>A: two\nA->>B: three"),
+            vec![10, 20, 30]
+        );
+    }
+
+    #[test]
+    fn sequence_footbox_repeats_participants_below_lifelines() {
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let parsed =
+            crate::parser::parse_mermaid("sequenceDiagram\nAlice->>Bob: hi\nBob-->>Alice: bye")
+                .unwrap();
+        let layout = compute_sequence_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Sequence(seq) = &layout.diagram else {
+            panic!("expected sequence layout");
+        };
+        assert_eq!(seq.footboxes.len(), 2);
+        for lifeline in &seq.lifelines {
+            let top = &layout.nodes[&lifeline.id];
+            let foot = seq
+                .footboxes
+                .iter()
+                .find(|foot| foot.id == lifeline.id)
+                .unwrap();
+            assert!((top.y + top.height - lifeline.y1).abs() < 0.01);
+            assert!((foot.y - lifeline.y2).abs() < 0.01);
+            assert_eq!(foot.label.lines, top.label.lines);
+        }
+        let svg = crate::render::render_svg(&layout, &theme, &config);
+        assert_eq!(svg.matches(">Alice</tspan>").count(), 2);
+        assert_eq!(svg.matches(">Bob</tspan>").count(), 2);
+
+        config.sequence.show_footbox = false;
+        let layout_without = compute_sequence_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Sequence(seq) = &layout_without.diagram else {
+            panic!("expected sequence layout");
+        };
+        assert!(seq.footboxes.is_empty());
+        assert!(layout_without.height < layout.height);
+        let svg = crate::render::render_svg(&layout_without, &theme, &config);
+        assert_eq!(svg.matches(">Alice</tspan>").count(), 1);
+    }
+
     #[test]
     fn sequence_center_label_prefers_touching_own_path() {
         let points = vec![(0.0, 0.0), (140.0, 0.0)];
@@ -1689,6 +1943,7 @@ mod tests {
             lines: vec!["msg".to_string()],
             width: 36.0,
             height: 14.0,
+            spans: Vec::new(),
         };
         let theme = Theme::mermaid_default();
         let anchor = choose_sequence_center_label_anchor(
@@ -1715,6 +1970,7 @@ mod tests {
             lines: vec!["msg".to_string()],
             width: 36.0,
             height: 14.0,
+            spans: Vec::new(),
         };
         let theme = Theme::mermaid_default();
         let occupied = vec![(-20.0, -10.0, 180.0, 20.0)];
@@ -1737,4 +1993,20 @@ mod tests {
             anchor.0
         );
     }
+
+    #[test]
+    fn redeclared_participant_keeps_single_lifeline() {
+        let input = "sequenceDiagram\nparticipant A as First\nparticipant A as Second\nA->>B: hi";
+        let parsed = crate::parser::parse_mermaid(input).unwrap();
+        assert_eq!(parsed.warnings.len(), 1);
+        assert!(parsed.warnings[0].contains("'A'"));
+
+        let layout =
+            compute_sequence_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let DiagramData::Sequence(data) = &layout.diagram else {
+            panic!("expected sequence layout");
+        };
+        assert_eq!(data.lifelines.iter().filter(|l| l.id == "A").count(), 1);
+        assert_eq!(layout.nodes["A"].label.lines, vec!["First".to_string()]);
+    }
 }
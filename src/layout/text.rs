@@ -1,8 +1,15 @@
-use crate::config::LayoutConfig;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::{LabelOverflow, LayoutConfig};
 use crate::text_metrics;
 use crate::theme::Theme;
 
-use super::TextBlock;
+use super::{SMALL_TEXT_SCALE, TextBlock, TextSpan};
+
+/// Bold glyphs run wider than the regular weight the metrics are
+/// calibrated against.
+const BOLD_WIDTH_FACTOR: f32 = 1.1;
 
 pub(super) fn measure_label(text: &str, theme: &Theme, config: &LayoutConfig) -> TextBlock {
     // Mermaid's layout sizing appears to use a baseline font size (~16px)
@@ -18,6 +25,205 @@ pub(super) fn measure_label(text: &str, theme: &Theme, config: &LayoutConfig) ->
     )
 }
 
+/// [`measure_label`] at `scale` times the theme's font size.
+pub(super) fn measure_scaled_label(
+    text: &str,
+    theme: &Theme,
+    config: &LayoutConfig,
+    scale: f32,
+) -> TextBlock {
+    measure_label_with_font_size(
+        text,
+        theme.font_size.max(16.0) * scale,
+        config,
+        true,
+        theme.font_family.as_str(),
+    )
+}
+
+/// Measures a wrapped flowchart label, turning `**bold**` and `*italic*`
+/// markers into styled spans. Labels without markers measure exactly like
+/// [`measure_label_with_font_size`].
+pub(super) fn measure_markdown_label(
+    text: &str,
+    font_size: f32,
+    config: &LayoutConfig,
+    font_family: &str,
+) -> TextBlock {
+    let raw_lines = if config.decode_html_entities {
+        split_lines(&decode_html_entities(text))
+    } else {
+        split_lines(text)
+    };
+    let sized: Vec<(&str, bool)> = raw_lines.iter().map(|line| strip_small_tag(line)).collect();
+    let styled: Vec<Option<Vec<(char, bool, bool)>>> =
+        sized.iter().map(|(line, _)| parse_emphasis(line)).collect();
+    if styled.iter().all(Option::is_none) && sized.iter().all(|(_, small)| !small) {
+        return measure_label_with_font_size(text, font_size, config, true, font_family);
+    }
+
+    let mut chars: Vec<StyledChar> = Vec::new();
+    let mut plain_lines = Vec::with_capacity(raw_lines.len());
+    for ((line, small), parsed) in sized.into_iter().zip(styled) {
+        let parsed = parsed.unwrap_or_else(|| line.chars().map(|ch| (ch, false, false)).collect());
+        plain_lines.push(parsed.iter().map(|(ch, _, _)| *ch).collect::<String>());
+        chars.extend(parsed.into_iter().map(|(ch, bold, italic)| StyledChar {
+            ch,
+            bold,
+            italic,
+            small,
+        }));
+        chars.push(StyledChar {
+            ch: '\n',
+            bold: false,
+            italic: false,
+            small,
+        });
+    }
+    let mut block = measure_lines(plain_lines, font_size, config, true, font_family);
+    let Some(spans) = align_spans(&block.lines, &chars) else {
+        return block;
+    };
+    let fast_metrics = config.fast_text_metrics;
+    let styled_width = spans
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|span| {
+                    let size = if span.small {
+                        font_size * SMALL_TEXT_SCALE
+                    } else {
+                        font_size
+                    };
+                    let width = text_width(&span.text, size, font_family, fast_metrics);
+                    if span.bold {
+                        width * BOLD_WIDTH_FACTOR
+                    } else {
+                        width
+                    }
+                })
+                .sum::<f32>()
+        })
+        .fold(0.0, f32::max);
+    block.width = block.width.max(styled_width);
+    block.spans = spans;
+    block.height = block.scaled_line_count() * font_size * config.label_line_height;
+    block
+}
+
+/// One character of a markdown label with the styling it is drawn in.
+#[derive(Clone, Copy)]
+struct StyledChar {
+    ch: char,
+    bold: bool,
+    italic: bool,
+    small: bool,
+}
+
+/// Unwraps a line written entirely as `<small>...</small>`, reporting
+/// whether it was.
+fn strip_small_tag(line: &str) -> (&str, bool) {
+    let lower = line.to_ascii_lowercase();
+    if lower.starts_with("<small>") && lower.ends_with("</small>") && line.len() >= 15 {
+        (line[7..line.len() - 8].trim(), true)
+    } else {
+        (line, false)
+    }
+}
+
+/// Strips markdown emphasis markers from `line`, tagging each remaining
+/// character with its (bold, italic) state. Returns `None` when the line
+/// has no balanced markers, so literal asterisks stay untouched.
+fn parse_emphasis(line: &str) -> Option<Vec<(char, bool, bool)>> {
+    if !line.contains('*') {
+        return None;
+    }
+    let chars: Vec<char> = line.chars().collect();
+    let run_at = |idx: usize| chars[idx..].iter().take_while(|ch| **ch == '*').count();
+    let closes_at = |idx: usize, len: usize| {
+        idx > 0 && !chars[idx - 1].is_whitespace() && chars[idx - 1] != '*' && run_at(idx) == len
+    };
+
+    let mut out = Vec::with_capacity(chars.len());
+    let (mut bold, mut italic) = (false, false);
+    let mut styled = false;
+    let mut idx = 0;
+    while idx < chars.len() {
+        if chars[idx] != '*' {
+            out.push((chars[idx], bold, italic));
+            idx += 1;
+            continue;
+        }
+        let len = run_at(idx);
+        let (toggles_bold, toggles_italic) = match len {
+            1 => (false, true),
+            2 => (true, false),
+            3 => (true, true),
+            _ => (false, false),
+        };
+        let active = (!toggles_bold || bold) && (!toggles_italic || italic);
+        let inactive = (!toggles_bold || !bold) && (!toggles_italic || !italic);
+        if len <= 3 && active && closes_at(idx, len) {
+            bold ^= toggles_bold;
+            italic ^= toggles_italic;
+            idx += len;
+            continue;
+        }
+        let opens = len <= 3
+            && inactive
+            && chars.get(idx + len).is_some_and(|ch| !ch.is_whitespace())
+            && (idx + len + 1..chars.len()).any(|end| closes_at(end, len));
+        if opens {
+            bold ^= toggles_bold;
+            italic ^= toggles_italic;
+            styled = true;
+        } else {
+            out.extend(chars[idx..idx + len].iter().map(|ch| (*ch, bold, italic)));
+        }
+        idx += len;
+    }
+    styled.then_some(out)
+}
+
+/// Maps wrapped `lines` back onto the styled character stream they were
+/// cut from. Wrapping only drops or collapses whitespace, so any other
+/// mismatch means the label was rewritten and spans are abandoned.
+fn align_spans(lines: &[String], chars: &[StyledChar]) -> Option<Vec<Vec<TextSpan>>> {
+    let mut cursor = 0;
+    let mut spans = Vec::with_capacity(lines.len());
+    for line in lines {
+        let mut line_spans: Vec<TextSpan> = Vec::new();
+        for ch in line.chars() {
+            while cursor < chars.len() && chars[cursor].ch != ch && chars[cursor].ch.is_whitespace()
+            {
+                cursor += 1;
+            }
+            let source = *chars.get(cursor)?;
+            if source.ch != ch {
+                return None;
+            }
+            cursor += 1;
+            match line_spans.last_mut() {
+                Some(span)
+                    if span.bold == source.bold
+                        && span.italic == source.italic
+                        && span.small == source.small =>
+                {
+                    span.text.push(ch)
+                }
+                _ => line_spans.push(TextSpan {
+                    text: ch.to_string(),
+                    bold: source.bold,
+                    italic: source.italic,
+                    small: source.small,
+                }),
+            }
+        }
+        spans.push(line_spans);
+    }
+    Some(spans)
+}
+
 pub(super) fn measure_label_with_font_size(
     text: &str,
     font_size: f32,
@@ -25,7 +231,21 @@ pub(super) fn measure_label_with_font_size(
     wrap: bool,
     font_family: &str,
 ) -> TextBlock {
-    let raw_lines = split_lines(text);
+    let raw_lines = if config.decode_html_entities {
+        split_lines(&decode_html_entities(text))
+    } else {
+        split_lines(text)
+    };
+    measure_lines(raw_lines, font_size, config, wrap, font_family)
+}
+
+fn measure_lines(
+    raw_lines: Vec<String>,
+    font_size: f32,
+    config: &LayoutConfig,
+    wrap: bool,
+    font_family: &str,
+) -> TextBlock {
     let mut lines = Vec::new();
     let fast_metrics = config.fast_text_metrics;
     let max_width_px = max_label_width_px(
@@ -61,10 +281,14 @@ pub(super) fn measure_label_with_font_size(
         lines,
         width,
         height,
+        spans: Vec::new(),
     }
 }
 
 pub(super) fn char_width_factor(ch: char) -> f32 {
+    if let Some(factor) = text_metrics::emoji_width_factor(ch) {
+        return factor;
+    }
     // Calibrated per-character widths against mermaid-cli output using the
     // default font stack and a 16px measurement baseline.
     match ch {
@@ -135,9 +359,87 @@ pub(super) fn char_width_factor(ch: char) -> f32 {
     }
 }
 
+/// Replaces named (`&amp;`) and numeric (`&#39;`, `&#x27;`) character
+/// references with the characters they stand for. Unknown or malformed
+/// references are left as written.
+pub(super) fn decode_html_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end > 0 && end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|ch| (ch, end + 2)));
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "times" => '×',
+        "divide" => '÷',
+        "plusmn" => '±',
+        "middot" => '·',
+        "bull" => '•',
+        "hellip" => '…',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "larr" => '←',
+        "rarr" => '→',
+        "uarr" => '↑',
+        "darr" => '↓',
+        "harr" => '↔',
+        _ => return None,
+    })
+}
+
+/// `<br>`, `<br/>` and `<br />` in any case: hard breaks inside labels.
+static BR_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\s*/?>").unwrap());
+
+/// Whether `text` contains an explicit `<br>` line break.
+pub(super) fn has_br_tag(text: &str) -> bool {
+    BR_TAG_RE.is_match(text)
+}
+
 pub(super) fn split_lines(text: &str) -> Vec<String> {
     let mut lines = Vec::new();
-    let mut current = text.replace("<br/>", "\n").replace("<br>", "\n");
+    let mut current = BR_TAG_RE.replace_all(text, "\n").into_owned();
     current = current.replace("\\n", "\n");
     for line in current.split('\n') {
         lines.push(line.trim().to_string());
@@ -180,6 +482,113 @@ pub(super) fn wrap_line(
     lines
 }
 
+/// Shortens `line` with a trailing ellipsis so it fits in `max_width`.
+pub(super) fn truncate_line(
+    line: &str,
+    max_width: f32,
+    font_size: f32,
+    font_family: &str,
+    fast_metrics: bool,
+) -> String {
+    if text_width(line, font_size, font_family, fast_metrics) <= max_width {
+        return line.to_string();
+    }
+    let mut chars: Vec<char> = line.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_width(&candidate, font_size, font_family, fast_metrics) <= max_width {
+            return candidate;
+        }
+    }
+    "…".to_string()
+}
+
+/// Keeps the first `max_lines` lines of `block`, ending the last kept line
+/// with an ellipsis. Returns `None` when the label already fits.
+pub(super) fn limit_label_lines(
+    block: &TextBlock,
+    max_lines: usize,
+    font_size: f32,
+    font_family: &str,
+    fast_metrics: bool,
+) -> Option<TextBlock> {
+    let max_lines = max_lines.max(1);
+    if block.lines.len() <= max_lines {
+        return None;
+    }
+    let mut lines = block.lines[..max_lines].to_vec();
+    let last = lines.last_mut().expect("at least one line kept");
+    let marked = format!("{}…", last.trim_end());
+    *last = truncate_line(&marked, block.width, font_size, font_family, fast_metrics);
+    let mut spans = Vec::new();
+    if !block.spans.is_empty() {
+        spans = block.spans[..max_lines].to_vec();
+        if let Some(line_spans) = spans.last_mut() {
+            let template = line_spans.first().cloned().unwrap_or(TextSpan {
+                text: String::new(),
+                bold: false,
+                italic: false,
+                small: false,
+            });
+            *line_spans = vec![TextSpan {
+                text: lines[max_lines - 1].clone(),
+                ..template
+            }];
+        }
+    }
+    let mut clamped = TextBlock {
+        lines,
+        width: block.width,
+        height: 0.0,
+        spans,
+    };
+    clamped.height = block.height * clamped.scaled_line_count() / block.scaled_line_count();
+    Some(clamped)
+}
+
+/// Applies the `labels` fitting policy to an already measured secondary
+/// label (legend entry, axis title), wrapping or truncating it to
+/// `legend_max_width`.
+pub(super) fn fit_secondary_label(
+    block: TextBlock,
+    font_size: f32,
+    config: &LayoutConfig,
+    font_family: &str,
+) -> TextBlock {
+    let Some(max_width) = config.labels.legend_max_width else {
+        return block;
+    };
+    if block.width <= max_width {
+        return block;
+    }
+    let fast_metrics = config.fast_text_metrics;
+    let lines: Vec<String> = match config.labels.legend_overflow {
+        LabelOverflow::Wrap => block
+            .lines
+            .iter()
+            .flat_map(|line| wrap_line(line, max_width, font_size, font_family, fast_metrics))
+            .map(|line| truncate_line(&line, max_width, font_size, font_family, fast_metrics))
+            .collect(),
+        LabelOverflow::Truncate => block
+            .lines
+            .iter()
+            .map(|line| truncate_line(line, max_width, font_size, font_family, fast_metrics))
+            .collect(),
+    };
+    let width = lines
+        .iter()
+        .map(|line| text_width(line, font_size, font_family, fast_metrics))
+        .fold(0.0, f32::max);
+    let line_height = block.height / block.lines.len().max(1) as f32;
+    TextBlock {
+        height: line_height * lines.len() as f32,
+        lines,
+        width,
+        spans: Vec::new(),
+    }
+}
+
 pub(super) fn text_width(text: &str, font_size: f32, font_family: &str, fast_metrics: bool) -> f32 {
     if fast_metrics && text.is_ascii() {
         return fallback_text_width(text, font_size);
@@ -189,7 +598,10 @@ pub(super) fn text_width(text: &str, font_size: f32, font_family: &str, fast_met
 }
 
 fn fallback_text_width(text: &str, font_size: f32) -> f32 {
-    text.chars().map(char_width_factor).sum::<f32>() * font_size
+    text_metrics::spaced_chars(text)
+        .map(char_width_factor)
+        .sum::<f32>()
+        * font_size
 }
 
 fn average_char_width(font_family: &str, font_size: f32, fast_metrics: bool) -> f32 {
@@ -213,6 +625,38 @@ fn max_label_width_px(
 mod tests {
     use super::*;
 
+    #[test]
+    fn markdown_label_splits_styled_spans_and_widens_bold() {
+        let config = LayoutConfig {
+            fast_text_metrics: true,
+            ..LayoutConfig::default()
+        };
+        let styled = measure_markdown_label("**bold** and *it*", 16.0, &config, "sans-serif");
+        assert_eq!(styled.lines, vec!["bold and it"]);
+        let runs: Vec<_> = styled.spans[0]
+            .iter()
+            .map(|span| (span.text.as_str(), span.bold, span.italic))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                ("bold", true, false),
+                (" and ", false, false),
+                ("it", false, true)
+            ]
+        );
+        let bold = measure_markdown_label("**WWWW**", 16.0, &config, "sans-serif");
+        let plain = measure_label_with_font_size("WWWW", 16.0, &config, true, "sans-serif");
+        assert!(bold.width > plain.width);
+    }
+
+    #[test]
+    fn unbalanced_asterisks_stay_literal() {
+        assert!(parse_emphasis("2 * 3 * 4").is_none());
+        assert!(parse_emphasis("note*").is_none());
+        assert!(parse_emphasis("**open only").is_none());
+    }
+
     #[test]
     fn split_lines_handles_br_tags() {
         assert_eq!(split_lines("a<br/>b"), vec!["a", "b"]);
@@ -220,6 +664,23 @@ mod tests {
         assert_eq!(split_lines("a\\nb"), vec!["a", "b"]);
     }
 
+    #[test]
+    fn split_lines_handles_br_tag_variants() {
+        assert_eq!(split_lines("a<br />b"), vec!["a", "b"]);
+        assert_eq!(split_lines("a<BR>b<Br/>c"), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn br_tag_forces_break_before_wrapping() {
+        let config = LayoutConfig {
+            fast_text_metrics: true,
+            ..LayoutConfig::default()
+        };
+        let block =
+            measure_label_with_font_size("Line1<br>Line2", 16.0, &config, true, "sans-serif");
+        assert_eq!(block.lines, vec!["Line1", "Line2"]);
+    }
+
     #[test]
     fn split_lines_trims_whitespace() {
         assert_eq!(split_lines("  hello  \n  world  "), vec!["hello", "world"]);
@@ -232,6 +693,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn emoji_measure_about_twice_a_default_glyph() {
+        for fast in [true, false] {
+            let base = text_width("Go ", 16.0, "sans-serif", fast);
+            let rocket = text_width("Go \u{1f680}", 16.0, "sans-serif", fast) - base;
+            assert!(rocket >= 16.0 * char_width_factor('?') * 1.9, "{rocket}");
+            // A ZWJ sequence draws as a single glyph.
+            let coder =
+                text_width("Go \u{1f469}\u{200d}\u{1f4bb}", 16.0, "sans-serif", fast) - base;
+            assert!((coder - rocket).abs() < 0.01, "{coder} vs {rocket}");
+        }
+    }
+
     #[test]
     fn fallback_text_width_scales_with_font_size() {
         let w16 = fallback_text_width("Hello", 16.0);
@@ -248,6 +722,13 @@ mod tests {
         assert_eq!(result.len(), 1);
     }
 
+    #[test]
+    fn decode_html_entities_handles_named_and_numeric() {
+        assert_eq!(decode_html_entities("A &amp; B"), "A & B");
+        assert_eq!(decode_html_entities("&lt;b&gt; &#39;x&#x27;"), "<b> 'x'");
+        assert_eq!(decode_html_entities("R&D &bogus; &"), "R&D &bogus; &");
+    }
+
     #[test]
     fn wrap_line_splits_long_text() {
         let result = wrap_line(
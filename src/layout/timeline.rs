@@ -21,7 +21,10 @@ pub(super) fn compute_timeline_layout(
     let width = padding * 2.0 + total_events_width;
     let height = padding * 2.0 + title_height + event_height + 100.0;
 
-    let title = data.title.as_ref().map(|t| measure_label(t, theme, config));
+    let title = data
+        .title
+        .as_ref()
+        .map(|t| measure_scaled_label(t, theme, config, config.labels.title_scale));
 
     let events: Vec<TimelineEventLayout> = data
         .events
@@ -83,6 +86,7 @@ pub(super) fn compute_timeline_layout(
                 lines: vec![String::new()],
                 width: 0.0,
                 height: 0.0,
+                spans: Vec::new(),
             },
             shape: crate::ir::NodeShape::Rectangle,
             style: crate::ir::NodeStyle::default(),
@@ -90,11 +94,13 @@ pub(super) fn compute_timeline_layout(
             anchor_subgraph: None,
             hidden: false,
             icon: None,
+            tooltip: None,
         },
     );
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -51,6 +51,7 @@ pub(super) fn compute_treemap_layout(
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes,
         edges,
         subgraphs,
@@ -163,6 +164,7 @@ fn layout_treemap_nodes(
                     lines: vec![String::new()],
                     width: 0.0,
                     height: 0.0,
+                    spans: Vec::new(),
                 }
             };
 
@@ -181,6 +183,7 @@ fn layout_treemap_nodes(
                     anchor_subgraph: None,
                     hidden: false,
                     icon: None,
+                    tooltip: None,
                 },
             );
         }
@@ -1,15 +1,55 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::ir::Direction;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TextBlock {
     pub lines: Vec<String>,
     pub width: f32,
     pub height: f32,
+    /// Styled runs for each entry in `lines`; empty for plain labels.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spans: Vec<Vec<TextSpan>>,
+}
+
+/// Size of `<small>` label text relative to the label's font size.
+pub const SMALL_TEXT_SCALE: f32 = 0.8;
+
+impl TextBlock {
+    /// Height of line `idx` relative to a full-size line:
+    /// [`SMALL_TEXT_SCALE`] when every run on it is small, otherwise 1.
+    pub fn line_scale(&self, idx: usize) -> f32 {
+        match self.spans.get(idx) {
+            Some(spans) if !spans.is_empty() && spans.iter().all(|span| span.small) => {
+                SMALL_TEXT_SCALE
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Sum of [`line_scale`](Self::line_scale) over all lines.
+    pub fn scaled_line_count(&self) -> f32 {
+        (0..self.lines.len()).map(|idx| self.line_scale(idx)).sum()
+    }
+}
+
+/// A run of label text sharing the same markdown emphasis.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    /// Wrapped in `<small>`; drawn at [`SMALL_TEXT_SCALE`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub small: bool,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeLayout {
     pub id: String,
     pub x: f32,
@@ -23,9 +63,14 @@ pub struct NodeLayout {
     pub anchor_subgraph: Option<usize>,
     pub hidden: bool,
     pub icon: Option<String>,
+    /// Full label text when the drawn label was cut short, shown as a
+    /// `<title>` tooltip.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tooltip: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EdgeLayout {
     pub from: String,
     pub to: String,
@@ -47,7 +92,16 @@ pub struct EdgeLayout {
     pub override_style: crate::ir::EdgeStyleOverride,
 }
 
+/// What a [`Layout::element_boxes`] entry describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Node,
+    Edge,
+    Subgraph,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SubgraphLayout {
     pub label: String,
     pub label_block: TextBlock,
@@ -58,6 +112,9 @@ pub struct SubgraphLayout {
     pub height: f32,
     pub style: crate::ir::NodeStyle,
     pub icon: Option<String>,
+    /// Direction the cluster's contents flow in: its declared `direction`,
+    /// otherwise the diagram's.
+    pub direction: Direction,
 }
 
 #[derive(Debug, Clone)]
@@ -157,6 +214,17 @@ pub struct PieLegendItem {
     pub value: f32,
 }
 
+/// The "+N more" line closing a legend cut short by `legend_max_items`.
+#[derive(Debug, Clone)]
+pub struct PieLegendMore {
+    pub x: f32,
+    pub y: f32,
+    pub label: TextBlock,
+    pub hidden: usize,
+    /// Combined value of the hidden entries.
+    pub value: f32,
+}
+
 #[derive(Debug, Clone)]
 pub struct PieTitleLayout {
     pub x: f32,
@@ -439,12 +507,14 @@ pub struct SequenceData {
 pub struct PieData {
     pub slices: Vec<PieSliceLayout>,
     pub legend: Vec<PieLegendItem>,
+    pub legend_more: Option<PieLegendMore>,
     pub center: (f32, f32),
     pub radius: f32,
     pub title: Option<PieTitleLayout>,
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum DiagramData {
     Graph { state_notes: Vec<StateNoteLayout> },
     Sequence(SequenceData),
@@ -460,17 +530,407 @@ pub enum DiagramData {
     Error(ErrorLayout),
 }
 
+#[cfg(feature = "serde")]
+impl DiagramData {
+    fn empty() -> Self {
+        DiagramData::Graph {
+            state_notes: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Layout {
     pub kind: crate::ir::DiagramKind,
+    pub accessibility: crate::ir::Accessibility,
     pub nodes: BTreeMap<String, NodeLayout>,
     pub edges: Vec<EdgeLayout>,
     pub subgraphs: Vec<SubgraphLayout>,
     pub width: f32,
     pub height: f32,
+    /// Diagram-specific extras; not part of the serialized form.
+    #[cfg_attr(feature = "serde", serde(skip, default = "DiagramData::empty"))]
     pub diagram: DiagramData,
 }
 
+impl Layout {
+    /// Exact bounds of the drawn content as `(x, y, width, height)`, with no
+    /// padding. Covers visible nodes, subgraph rects, edge waypoints, edge
+    /// labels and notes, plus sequence footboxes, boxes and frames. Falls back to
+    /// the full canvas when there is no such content.
+    pub fn tight_viewbox(&self) -> (f32, f32, f32, f32) {
+        let mut min_x = f32::MAX;
+        let mut min_y = f32::MAX;
+        let mut max_x = f32::MIN;
+        let mut max_y = f32::MIN;
+        let mut include = |x: f32, y: f32, width: f32, height: f32| {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x + width);
+            max_y = max_y.max(y + height);
+        };
+        for node in self.nodes.values().filter(|node| !node.hidden) {
+            include(node.x, node.y, node.width, node.height);
+        }
+        for sub in &self.subgraphs {
+            include(sub.x, sub.y, sub.width, sub.height);
+        }
+        for edge in &self.edges {
+            for point in &edge.points {
+                include(point.0, point.1, 0.0, 0.0);
+            }
+            let labels = [
+                (edge.label.as_ref(), edge.label_anchor),
+                (edge.start_label.as_ref(), edge.start_label_anchor),
+                (edge.end_label.as_ref(), edge.end_label_anchor),
+            ];
+            for (label, anchor) in labels {
+                if let (Some(label), Some((x, y))) = (label, anchor) {
+                    include(
+                        x - label.width / 2.0,
+                        y - label.height / 2.0,
+                        label.width,
+                        label.height,
+                    );
+                }
+            }
+        }
+        if let DiagramData::Graph { state_notes } = &self.diagram {
+            for note in state_notes {
+                include(note.x, note.y, note.width, note.height);
+            }
+        }
+        if let DiagramData::Sequence(seq) = &self.diagram {
+            for node in &seq.footboxes {
+                include(node.x, node.y, node.width, node.height);
+            }
+            for seq_box in &seq.boxes {
+                include(seq_box.x, seq_box.y, seq_box.width, seq_box.height);
+            }
+            for frame in &seq.frames {
+                include(frame.x, frame.y, frame.width, frame.height);
+            }
+            for note in &seq.notes {
+                include(note.x, note.y, note.width, note.height);
+            }
+        }
+        if min_x > max_x || min_y > max_y {
+            return (0.0, 0.0, self.width, self.height);
+        }
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    /// Bounding boxes `(x, y, width, height)` of visible nodes keyed by node
+    /// id, plus edges keyed by their SVG id (`edge-N`) and subgraphs keyed by
+    /// `subgraph-N`, in the rendered SVG's coordinates. The key's
+    /// [`ElementKind`] keeps a node named like an edge or subgraph apart.
+    /// Edge boxes cover the route and its center label.
+    pub fn element_boxes(&self) -> HashMap<(ElementKind, String), (f32, f32, f32, f32)> {
+        let mut boxes = HashMap::new();
+        for node in self.nodes.values().filter(|node| !node.hidden) {
+            boxes.insert(
+                (ElementKind::Node, node.id.clone()),
+                (node.x, node.y, node.width, node.height),
+            );
+        }
+        for (idx, edge) in self.edges.iter().enumerate() {
+            let mut min = (f32::MAX, f32::MAX);
+            let mut max = (f32::MIN, f32::MIN);
+            let mut include = |x: f32, y: f32| {
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            };
+            for &(x, y) in &edge.points {
+                include(x, y);
+            }
+            if let (Some(label), Some((x, y))) = (edge.label.as_ref(), edge.label_anchor) {
+                include(x - label.width / 2.0, y - label.height / 2.0);
+                include(x + label.width / 2.0, y + label.height / 2.0);
+            }
+            if min.0 <= max.0 {
+                boxes.insert(
+                    (ElementKind::Edge, format!("edge-{idx}")),
+                    (min.0, min.1, max.0 - min.0, max.1 - min.1),
+                );
+            }
+        }
+        for (idx, sub) in self.subgraphs.iter().enumerate() {
+            boxes.insert(
+                (ElementKind::Subgraph, format!("subgraph-{idx}")),
+                (sub.x, sub.y, sub.width, sub.height),
+            );
+        }
+        boxes
+    }
+
+    /// Insert an edge between two already placed nodes and route it around
+    /// the existing nodes and subgraphs, without a full relayout. The canvas
+    /// grows to fit the new route, shifting the content when the route runs
+    /// past its top or left edge. Returns `None` when either node is missing.
+    pub fn add_edge(
+        &mut self,
+        from: &str,
+        to: &str,
+        opts: &AddEdgeOptions,
+    ) -> Option<EdgeInsertion> {
+        let from_node = self.nodes.get(from)?;
+        let to_node = self.nodes.get(to)?;
+        let config = &opts.config;
+        let (start_side, end_side, _) = super::edge_sides(from_node, to_node, opts.direction);
+        let obstacles = super::build_obstacles(&self.nodes, &self.subgraphs, config);
+        let label_obstacles =
+            super::build_label_obstacles_for_routing(&self.nodes, &self.subgraphs);
+        let ctx = super::RouteContext {
+            from_id: from,
+            to_id: to,
+            from: from_node,
+            to: to_node,
+            direction: opts.direction,
+            config,
+            obstacles: &obstacles,
+            label_obstacles: &label_obstacles,
+            fast_route: false,
+            base_offset: 0.0,
+            start_side,
+            end_side,
+            start_offset: 0.0,
+            end_offset: 0.0,
+            stub_len: super::port_stub_length(config, from_node, to_node),
+            prefer_shorter_ties: true,
+            preferred_label_id: None,
+            preferred_label_center: None,
+        };
+        let mut points = if from == to {
+            super::route_self_loop(from_node, opts.direction, config)
+        } else {
+            super::compress_path(&super::route_edge_with_avoidance(&ctx, None, None, None))
+        };
+
+        // Ranks only advance along the flow; an edge into the same or an
+        // earlier rank would have reshaped them in a full layout.
+        let gap = match opts.direction {
+            Direction::TopDown => to_node.y - (from_node.y + from_node.height),
+            Direction::BottomTop => from_node.y - (to_node.y + to_node.height),
+            Direction::LeftRight => to_node.x - (from_node.x + from_node.width),
+            Direction::RightLeft => from_node.x - (to_node.x + to_node.width),
+        };
+        let mut relayout_advised = from != to && gap <= 0.0;
+
+        // A route running above or left of the canvas moves everything else
+        // over to make room, as a full layout's normalization would.
+        let pad = super::LAYOUT_BOUNDARY_PAD;
+        let min_x = points.iter().map(|p| p.0).fold(f32::MAX, f32::min);
+        let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+        let shift_x = (pad - min_x).max(0.0);
+        let shift_y = (pad - min_y).max(0.0);
+        if shift_x > 0.0 || shift_y > 0.0 {
+            self.translate(shift_x, shift_y);
+            for point in &mut points {
+                *point = (point.0 + shift_x, point.1 + shift_y);
+            }
+            // Only graph content is moved; other diagram data would be left
+            // behind.
+            relayout_advised |= !matches!(self.diagram, DiagramData::Graph { .. });
+        }
+        for &(x, y) in &points {
+            self.width = self.width.max(x + pad);
+            self.height = self.height.max(y + pad);
+        }
+        self.edges.push(EdgeLayout {
+            from: from.to_string(),
+            to: to.to_string(),
+            label: None,
+            start_label: None,
+            end_label: None,
+            label_anchor: None,
+            start_label_anchor: None,
+            end_label_anchor: None,
+            points,
+            directed: opts.directed,
+            arrow_start: false,
+            arrow_end: opts.directed,
+            arrow_start_kind: None,
+            arrow_end_kind: None,
+            start_decoration: None,
+            end_decoration: None,
+            style: opts.style,
+            override_style: crate::ir::EdgeStyleOverride::default(),
+        });
+        Some(EdgeInsertion {
+            index: self.edges.len() - 1,
+            relayout_advised,
+        })
+    }
+
+    /// Move nodes, edges, subgraphs and state notes by `(dx, dy)`, growing the
+    /// canvas to match.
+    fn translate(&mut self, dx: f32, dy: f32) {
+        for node in self.nodes.values_mut() {
+            node.x += dx;
+            node.y += dy;
+        }
+        for edge in &mut self.edges {
+            for point in &mut edge.points {
+                *point = (point.0 + dx, point.1 + dy);
+            }
+            for anchor in [
+                &mut edge.label_anchor,
+                &mut edge.start_label_anchor,
+                &mut edge.end_label_anchor,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *anchor = (anchor.0 + dx, anchor.1 + dy);
+            }
+        }
+        for sub in &mut self.subgraphs {
+            sub.x += dx;
+            sub.y += dy;
+        }
+        if let DiagramData::Graph { state_notes } = &mut self.diagram {
+            for note in state_notes {
+                note.x += dx;
+                note.y += dy;
+            }
+        }
+        self.width += dx;
+        self.height += dy;
+    }
+
+    /// Place several layouts on one canvas, side by side or stacked.
+    ///
+    /// Node ids are prefixed with the layout's index (`"0:A"`, `"1:A"`) so
+    /// diagrams sharing ids stay distinct. Nodes, edges, subgraphs and state
+    /// notes are carried over; other diagram-specific content (sequence
+    /// lifelines, pie slices, ...) is not. The result takes its `kind` from the
+    /// first layout.
+    pub fn compose(layouts: &[Layout], arrangement: Arrangement) -> Layout {
+        let mut nodes = BTreeMap::new();
+        let mut edges = Vec::new();
+        let mut subgraphs = Vec::new();
+        let mut state_notes = Vec::new();
+        let (mut width, mut height) = (0.0_f32, 0.0_f32);
+        let mut cursor = 0.0_f32;
+        for (idx, layout) in layouts.iter().enumerate() {
+            let (dx, dy) = match arrangement {
+                Arrangement::Horizontal { .. } => (cursor, 0.0),
+                Arrangement::Vertical { .. } => (0.0, cursor),
+            };
+            let rename = |id: &str| format!("{idx}:{id}");
+            let subgraph_base = subgraphs.len();
+            for node in layout.nodes.values() {
+                let mut node = node.clone();
+                node.id = rename(&node.id);
+                node.x += dx;
+                node.y += dy;
+                node.anchor_subgraph = node.anchor_subgraph.map(|sub| sub + subgraph_base);
+                nodes.insert(node.id.clone(), node);
+            }
+            for edge in &layout.edges {
+                let mut edge = edge.clone();
+                edge.from = rename(&edge.from);
+                edge.to = rename(&edge.to);
+                for point in &mut edge.points {
+                    *point = (point.0 + dx, point.1 + dy);
+                }
+                for anchor in [
+                    &mut edge.label_anchor,
+                    &mut edge.start_label_anchor,
+                    &mut edge.end_label_anchor,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    *anchor = (anchor.0 + dx, anchor.1 + dy);
+                }
+                edges.push(edge);
+            }
+            for sub in &layout.subgraphs {
+                let mut sub = sub.clone();
+                sub.x += dx;
+                sub.y += dy;
+                sub.nodes = sub.nodes.iter().map(|id| rename(id)).collect();
+                subgraphs.push(sub);
+            }
+            if let DiagramData::Graph { state_notes: notes } = &layout.diagram {
+                state_notes.extend(notes.iter().map(|note| {
+                    let mut note = note.clone();
+                    note.x += dx;
+                    note.y += dy;
+                    note.target = rename(&note.target);
+                    note
+                }));
+            }
+            match arrangement {
+                Arrangement::Horizontal { gap } => {
+                    width = cursor + layout.width;
+                    height = height.max(layout.height);
+                    cursor = width + gap;
+                }
+                Arrangement::Vertical { gap } => {
+                    width = width.max(layout.width);
+                    height = cursor + layout.height;
+                    cursor = height + gap;
+                }
+            }
+        }
+        Layout {
+            kind: layouts
+                .first()
+                .map(|layout| layout.kind)
+                .unwrap_or(crate::ir::DiagramKind::Flowchart),
+            accessibility: crate::ir::Accessibility::default(),
+            nodes,
+            edges,
+            subgraphs,
+            width,
+            height,
+            diagram: DiagramData::Graph { state_notes },
+        }
+    }
+}
+
+/// Options for [`Layout::add_edge`].
+#[derive(Debug, Clone)]
+pub struct AddEdgeOptions {
+    /// Flow direction the layout was computed with.
+    pub direction: Direction,
+    pub directed: bool,
+    pub style: crate::ir::EdgeStyle,
+    pub config: crate::config::LayoutConfig,
+}
+
+impl Default for AddEdgeOptions {
+    fn default() -> Self {
+        Self {
+            direction: Direction::TopDown,
+            directed: true,
+            style: crate::ir::EdgeStyle::Solid,
+            config: crate::config::LayoutConfig::default(),
+        }
+    }
+}
+
+/// Result of [`Layout::add_edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeInsertion {
+    /// Index of the new edge in [`Layout::edges`].
+    pub index: usize,
+    /// The edge runs within or against the rank order, so a full
+    /// [`compute_layout`](crate::compute_layout) would place nodes
+    /// differently. The edge is still routed as is.
+    pub relayout_advised: bool,
+}
+
+/// How [`Layout::compose`] arranges its layouts, with `gap` between each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrangement {
+    Horizontal { gap: f32 },
+    Vertical { gap: f32 },
+}
+
 #[derive(Debug, Clone)]
 pub struct C4Layout {
     pub shapes: Vec<C4ShapeLayout>,
@@ -587,6 +1047,9 @@ pub struct GanttLayout {
     pub task_label_width: f32,
     pub title_y: f32,
     pub ticks: Vec<GanttTick>,
+    /// `(from, to)` task indices joined by a dependency arrow; empty unless
+    /// `gantt.show_dependencies` is set.
+    pub dependencies: Vec<(usize, usize)>,
 }
 
 #[derive(Debug, Clone)]
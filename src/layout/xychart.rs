@@ -1,4 +1,5 @@
 use super::*;
+use crate::config::TickFormat;
 
 pub(super) fn compute_xychart_layout(
     graph: &Graph,
@@ -119,11 +120,19 @@ pub(super) fn compute_xychart_layout(
         }
     }
 
-    // X-axis categories
+    // X-axis categories, thinned to at most `x_tick_count` labels
+    let label_step = config
+        .xychart
+        .x_tick_count
+        .map_or(1, |count| {
+            data.x_axis_categories.len().div_ceil(count.max(1))
+        })
+        .max(1);
     let x_axis_categories: Vec<(String, f32)> = data
         .x_axis_categories
         .iter()
         .enumerate()
+        .filter(|(i, _)| i % label_step == 0)
         .map(|(i, cat)| {
             let x = plot_x + i as f32 * bar_group_width + bar_group_width / 2.0;
             (cat.clone(), x)
@@ -131,27 +140,41 @@ pub(super) fn compute_xychart_layout(
         .collect();
 
     // Y-axis ticks
-    let num_ticks = 5;
+    let num_ticks = config.xychart.y_tick_count.max(2) - 1;
     let y_axis_ticks: Vec<(String, f32)> = (0..=num_ticks)
         .map(|i| {
             let value = min_val + (i as f32 / num_ticks as f32) * range;
             let y = plot_y + plot_height - (i as f32 / num_ticks as f32) * plot_height;
-            (format!("{:.0}", value), y)
+            (format_tick(value, &config.xychart.y_tick_format), y)
         })
         .collect();
 
-    let title = data.title.as_ref().map(|t| measure_label(t, theme, config));
-    let x_axis_label = data
-        .x_axis_label
-        .as_ref()
-        .map(|l| measure_label(l, theme, config));
-    let y_axis_label = data
-        .y_axis_label
+    let title = data
+        .title
         .as_ref()
-        .map(|l| measure_label(l, theme, config));
+        .map(|t| measure_scaled_label(t, theme, config, config.labels.title_scale));
+    // Axis titles are fitted at the size the renderer draws them.
+    let axis_font_size = theme.font_size * config.labels.axis_label_scale;
+    let secondary_label = |text: &String| {
+        fit_secondary_label(
+            measure_label_with_font_size(
+                text,
+                axis_font_size,
+                config,
+                true,
+                theme.font_family.as_str(),
+            ),
+            axis_font_size,
+            config,
+            theme.font_family.as_str(),
+        )
+    };
+    let x_axis_label = data.x_axis_label.as_ref().map(secondary_label);
+    let y_axis_label = data.y_axis_label.as_ref().map(secondary_label);
 
     Layout {
         kind: graph.kind,
+        accessibility: graph.accessibility.clone(),
         nodes: BTreeMap::new(),
         edges: Vec::new(),
         subgraphs: Vec::new(),
@@ -177,3 +200,27 @@ pub(super) fn compute_xychart_layout(
         height,
     }
 }
+
+fn format_tick(value: f32, format: &TickFormat) -> String {
+    match format {
+        TickFormat::Plain => format!("{:.0}", value),
+        TickFormat::Percent => format!("{:.0}%", value),
+        TickFormat::Currency(symbol) => {
+            let sign = if value < 0.0 { "-" } else { "" };
+            format!("{sign}{symbol}{}", si_abbreviate(value.abs()))
+        }
+        TickFormat::SI => si_abbreviate(value),
+    }
+}
+
+/// `1500` -> `1.5k`, `2000000` -> `2M`; at most one decimal place.
+fn si_abbreviate(value: f32) -> String {
+    let (scaled, suffix) = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")]
+        .into_iter()
+        .find(|(scale, _)| value.abs() >= *scale)
+        .map(|(scale, suffix)| (value / scale, suffix))
+        .unwrap_or((value, ""));
+    let text = format!("{:.1}", scaled);
+    let text = text.strip_suffix(".0").unwrap_or(&text);
+    format!("{text}{suffix}")
+}
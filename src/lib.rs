@@ -75,6 +75,8 @@
 //! - ~15MB memory vs ~300MB for mermaid-cli
 //! - SVG and PNG output (PNG via resvg)
 //! - Customizable themes and layout configuration
+//! - Deterministic: identical input and configuration produce byte-identical
+//!   SVG across runs on the same machine with the same installed fonts
 //!
 //! ## Cargo Features
 //!
@@ -91,6 +93,7 @@
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod config;
+pub mod error;
 pub mod ir;
 pub mod layout;
 pub mod layout_dump;
@@ -100,21 +103,30 @@ mod text_metrics;
 pub mod theme;
 
 // Re-export commonly used types at crate root for ergonomic library usage
-pub use config::{Config, LayoutConfig, RenderConfig};
+pub use config::{
+    Axis, BackgroundMode, Config, ConfigOverride, FrameSpec, LabelAlign, LayoutConfig,
+    RenderConfig, TextRendering,
+};
+pub use error::{MmdrError, ParseError};
 pub use ir::{
-    DiagramKind, Direction, Edge, EdgeArrowhead, EdgeDecoration, EdgeStyle, Graph, Node, NodeLink,
-    NodeShape, SequenceActivation, SequenceActivationKind, SequenceBox, StateNote,
-    StateNotePosition, Subgraph,
+    DiagramKind, DiagramKindInfo, Direction, Edge, EdgeArrowhead, EdgeDecoration, EdgeStyle, Graph,
+    Node, NodeLink, NodeShape, NodeStatus, SequenceActivation, SequenceActivationKind, SequenceBox,
+    StateNote, StateNotePosition, Subgraph, SupportLevel,
 };
 pub use layout::{
-    EdgeLayout, Layout, LayoutStageMetrics, NodeLayout, SubgraphLayout, compute_layout,
-    compute_layout_with_metrics,
+    AddEdgeOptions, Arrangement, EdgeInsertion, EdgeLayout, ElementKind, Layout, LayoutMetrics,
+    LayoutStageMetrics, NodeLayout, RenderCost, SubgraphLayout, check_size_limits,
+    complexity_score, compute_layout, compute_layout_with_metrics, estimate_render_cost,
+    take_orphan_edges, try_compute_layout,
 };
 pub use parser::{ParseOutput, parse_mermaid};
 #[cfg(feature = "png")]
 pub use render::write_output_png;
-pub use render::{render_svg, write_output_svg};
-pub use theme::Theme;
+pub use render::{
+    embed_source_comment, render_minimap, render_svg, render_svg_paginated, render_svg_to_writer,
+    render_svg_with_config, write_output_svg,
+};
+pub use theme::{StatusColors, Theme};
 
 /// Options for the high-level `render` function.
 #[derive(Debug, Clone)]
@@ -123,6 +135,8 @@ pub struct RenderOptions {
     pub theme: Theme,
     /// Layout configuration (spacing, etc.).
     pub layout: LayoutConfig,
+    /// SVG output configuration.
+    pub render: RenderConfig,
 }
 
 impl Default for RenderOptions {
@@ -130,6 +144,7 @@ impl Default for RenderOptions {
         Self {
             theme: Theme::modern(),
             layout: LayoutConfig::default(),
+            render: RenderConfig::default(),
         }
     }
 }
@@ -145,6 +160,16 @@ impl RenderOptions {
         Self {
             theme: Theme::mermaid_default(),
             layout: LayoutConfig::default(),
+            render: RenderConfig::default(),
+        }
+    }
+
+    /// Create options with the dark theme.
+    pub fn dark() -> Self {
+        Self {
+            theme: Theme::dark(),
+            layout: LayoutConfig::default(),
+            render: RenderConfig::default(),
         }
     }
 
@@ -160,6 +185,85 @@ impl RenderOptions {
         self
     }
 
+    /// Emit `class`/`data-*` hooks on nodes and edges for hover styling.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.render.interactive = interactive;
+        self
+    }
+
+    /// Fit the SVG viewBox tightly to the drawn content, with no margin.
+    pub fn with_tight_viewbox(mut self, tight: bool) -> Self {
+        self.render.tight = tight;
+        self
+    }
+
+    /// Pad small diagrams up to a minimum `(width, height)` canvas.
+    pub fn with_min_size(mut self, width: f32, height: f32) -> Self {
+        self.render.min_size = Some((width, height));
+        self
+    }
+
+    /// Scale large diagrams down to fit a maximum `(width, height)` canvas.
+    pub fn with_max_size(mut self, width: f32, height: f32) -> Self {
+        self.render.max_size = Some((width, height));
+        self
+    }
+
+    /// Clip all content to the SVG viewBox.
+    pub fn with_clip_to_viewbox(mut self, clip: bool) -> Self {
+        self.render.clip_to_viewbox = clip;
+        self
+    }
+
+    /// Prefix the ids the SVG defines for itself instead of deriving one
+    /// from the diagram.
+    pub fn with_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.render.id_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set the `text-rendering` hint on all text, e.g. for crisper PNG text.
+    pub fn with_text_rendering(mut self, hint: TextRendering) -> Self {
+        self.render.text_rendering = Some(hint);
+        self
+    }
+
+    /// Expose node fill and line color as CSS custom properties.
+    pub fn with_css_variables(mut self, enabled: bool) -> Self {
+        self.render.css_variables = enabled;
+        self
+    }
+
+    /// Round the output width and height up to whole pixels.
+    pub fn with_integer_dimensions(mut self, enabled: bool) -> Self {
+        self.render.integer_dimensions = enabled;
+        self
+    }
+
+    /// Leave the canvas unfilled so the host page shows through.
+    pub fn with_transparent_background(mut self) -> Self {
+        self.render.background = Some(BackgroundMode::Transparent);
+        self
+    }
+
+    /// Mirror the rendered canvas, text included, across `axis`.
+    pub fn with_mirror(mut self, axis: Axis) -> Self {
+        self.render.mirror = Some(axis);
+        self
+    }
+
+    /// Embed the Mermaid source as an XML comment in the SVG.
+    pub fn with_embed_source(mut self, embed: bool) -> Self {
+        self.render.embed_source = embed;
+        self
+    }
+
+    /// Shift node labels vertically to correct fonts that render off-center.
+    pub fn with_text_baseline_offset(mut self, offset: f32) -> Self {
+        self.render.text_baseline_offset = offset;
+        self
+    }
+
     /// Hint the renderer to target a preferred output aspect ratio (`width / height`).
     ///
     /// Invalid values (non-finite or `<= 0`) are ignored.
@@ -214,11 +318,190 @@ pub fn render(input: &str) -> anyhow::Result<String> {
 ///
 /// let svg = render_with_options("flowchart LR; A-->B", opts).unwrap();
 /// ```
-pub fn render_with_options(input: &str, options: RenderOptions) -> anyhow::Result<String> {
+pub fn render_with_options(input: &str, mut options: RenderOptions) -> anyhow::Result<String> {
+    let parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
+    let layout = try_compute_layout(&parsed.graph, &options.theme, &options.layout)?;
+    Ok(render::render_svg_with_source(
+        &layout,
+        &options.theme,
+        &options.layout,
+        &options.render,
+        Some(input),
+    ))
+}
+
+/// Render a Mermaid diagram to SVG, writing it into `out` as it is produced
+/// rather than returning one large `String`.
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::{render_to_writer, RenderOptions};
+///
+/// let mut out = Vec::new();
+/// render_to_writer("flowchart LR; A-->B", RenderOptions::default(), &mut out).unwrap();
+/// assert!(out.starts_with(b"<svg"));
+/// ```
+pub fn render_to_writer<W: std::io::Write>(
+    input: &str,
+    mut options: RenderOptions,
+    out: &mut W,
+) -> anyhow::Result<()> {
+    let parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
+    let layout = try_compute_layout(&parsed.graph, &options.theme, &options.layout)?;
+    render::render_svg_to_writer_with_source(
+        &layout,
+        &options.theme,
+        &options.layout,
+        &options.render,
+        Some(input),
+        out,
+    )?;
+    Ok(())
+}
+
+/// Render only the nodes whose id passes `keep`.
+///
+/// Dropped nodes take their edges with them; edges between two kept nodes
+/// survive. Useful for per-team views cut from one master diagram.
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::{render_filtered, RenderOptions};
+///
+/// let svg = render_filtered(
+///     "flowchart LR; A1-->A2; A2-->B1",
+///     RenderOptions::default(),
+///     |id| id.starts_with('A'),
+/// )
+/// .unwrap();
+/// assert!(!svg.contains("B1"));
+/// ```
+pub fn render_filtered(
+    input: &str,
+    mut options: RenderOptions,
+    keep: impl Fn(&str) -> bool,
+) -> anyhow::Result<String> {
+    let mut parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
+    parsed.graph.retain_nodes(keep);
+    let layout = try_compute_layout(&parsed.graph, &options.theme, &options.layout)?;
+    Ok(render::render_svg_with_source(
+        &layout,
+        &options.theme,
+        &options.layout,
+        &options.render,
+        Some(input),
+    ))
+}
+
+/// Render a Mermaid diagram as page-sized SVGs for printing.
+///
+/// Sequence diagrams taller than `page_height` are split into several SVGs
+/// that each repeat the participant headers; other diagrams render to a
+/// single page. See [`render_svg_paginated`].
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::{render_paginated, RenderOptions};
+///
+/// let input = "sequenceDiagram\nAlice->>Bob: Hi\nBob-->>Alice: Hello";
+/// let pages = render_paginated(input, RenderOptions::default(), 1000.0).unwrap();
+/// assert_eq!(pages.len(), 1);
+/// ```
+pub fn render_paginated(
+    input: &str,
+    mut options: RenderOptions,
+    page_height: f32,
+) -> anyhow::Result<Vec<String>> {
+    let parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
+    let layout = try_compute_layout(&parsed.graph, &options.theme, &options.layout)?;
+    Ok(render_svg_paginated(
+        &layout,
+        &options.theme,
+        &options.layout,
+        &options.render,
+        page_height,
+    ))
+}
+
+/// Render each section of a gantt, journey or timeline diagram as its own
+/// SVG, keyed by section name, e.g. for collapsible sections in docs.
+///
+/// Every fragment is a standalone diagram holding only that section's
+/// tasks, steps or events. Diagrams without sections yield no fragments.
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::{render_sections, RenderOptions};
+///
+/// let input = "journey\nsection Morning\nWake up: 3: Me\nsection Evening\nSleep: 5: Me";
+/// let sections = render_sections(input, RenderOptions::default()).unwrap();
+/// assert_eq!(sections[0].0, "Morning");
+/// assert!(sections[1].1.contains("Sleep"));
+/// ```
+pub fn render_sections(
+    input: &str,
+    mut options: RenderOptions,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
+    let mut fragments = Vec::new();
+    for name in parsed.graph.section_names() {
+        let mut graph = parsed.graph.clone();
+        graph.retain_section(&name);
+        let layout = try_compute_layout(&graph, &options.theme, &options.layout)?;
+        let svg = render_svg_with_config(&layout, &options.theme, &options.layout, &options.render);
+        fragments.push((name, svg));
+    }
+    Ok(fragments)
+}
+
+/// Serialize the geometry of a computed layout (nodes, edges, subgraphs and
+/// canvas size) as pretty-printed JSON, e.g. for diffing layouts in CI.
+///
+/// Diagram-specific data such as sequence lifelines or pie slices is left
+/// out. Requires the `serde` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::{LayoutConfig, Theme, compute_layout, layout_to_json, parse_mermaid};
+///
+/// let parsed = parse_mermaid("flowchart LR; A-->B").unwrap();
+/// let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+/// let json = layout_to_json(&layout);
+/// assert!(json.contains("\"nodes\""));
+/// ```
+#[cfg(feature = "serde")]
+pub fn layout_to_json(layout: &Layout) -> String {
+    serde_json::to_string_pretty(layout).expect("layout geometry always serializes")
+}
+
+/// Parse `input` and predict how expensive it would be to render with the
+/// default layout config, without running layout. Lets hosts reject or queue
+/// costly diagrams before committing to them.
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_rs_renderer::estimate_cost;
+///
+/// let cost = estimate_cost("flowchart LR; A-->B").unwrap();
+/// assert!(!cost.grid_routing);
+/// ```
+pub fn estimate_cost(input: &str) -> anyhow::Result<RenderCost> {
     let parsed = parse_mermaid(input)?;
-    let layout = compute_layout(&parsed.graph, &options.theme, &options.layout);
-    let svg = render_svg(&layout, &options.theme, &options.layout);
-    Ok(svg)
+    Ok(estimate_render_cost(
+        &parsed.graph,
+        &LayoutConfig::default(),
+    ))
 }
 
 /// Result of rendering with timing information.
@@ -304,21 +587,29 @@ pub fn render_with_timing(input: &str, options: RenderOptions) -> anyhow::Result
 /// routing, label placement) to support architecture-level profiling.
 pub fn render_with_detailed_timing(
     input: &str,
-    options: RenderOptions,
+    mut options: RenderOptions,
 ) -> anyhow::Result<RenderDetailedResult> {
     use std::time::Instant;
 
     let t0 = Instant::now();
     let parsed = parse_mermaid(input)?;
+    apply_config_override(&mut options, &parsed.config_override);
     let parse_us = t0.elapsed().as_micros();
 
     let t1 = Instant::now();
+    check_size_limits(&parsed.graph, &options.layout)?;
     let (layout, layout_stages) =
         compute_layout_with_metrics(&parsed.graph, &options.theme, &options.layout);
     let layout_us = t1.elapsed().as_micros();
 
     let t2 = Instant::now();
-    let svg = render_svg(&layout, &options.theme, &options.layout);
+    let svg = render::render_svg_with_source(
+        &layout,
+        &options.theme,
+        &options.layout,
+        &options.render,
+        Some(input),
+    );
     let render_us = t2.elapsed().as_micros();
 
     Ok(RenderDetailedResult {
@@ -330,6 +621,44 @@ pub fn render_with_detailed_timing(
     })
 }
 
+/// Every diagram kind the renderer recognizes, with its display name and how
+/// well it is supported, e.g. for a diagram type picker.
+pub fn supported_kinds() -> Vec<DiagramKindInfo> {
+    DiagramKind::ALL
+        .iter()
+        .map(|&kind| DiagramKindInfo {
+            kind,
+            name: kind.display_name(),
+            support: kind.support_level(),
+        })
+        .collect()
+}
+
+/// Apply a diagram's frontmatter or `%%{init}%%` settings: the theme and node
+/// spacing wherever the caller left the default, then any theme variables.
+fn apply_config_override(options: &mut RenderOptions, config_override: &ConfigOverride) {
+    let defaults = RenderOptions::default();
+    if options.theme == defaults.theme
+        && let Some(theme) = config_override.theme.as_deref().and_then(Theme::from_name)
+    {
+        options.theme = theme;
+    }
+    if let Some(color) = &config_override.primary_color {
+        options.theme.primary_color = color.clone();
+    }
+    if let Some(color) = &config_override.line_color {
+        options.theme.line_color = color.clone();
+    }
+    if let Some(font) = &config_override.font_family {
+        options.theme.font_family = font.clone();
+    }
+    if options.layout.node_spacing == defaults.layout.node_spacing
+        && let Some(spacing) = config_override.node_spacing
+    {
+        options.layout.node_spacing = spacing;
+    }
+}
+
 // Re-export cli::run for the binary
 #[cfg(feature = "cli")]
 pub use cli::run;
@@ -338,6 +667,23 @@ pub use cli::run;
 mod tests {
     use super::*;
 
+    #[test]
+    fn supported_kinds_reports_support_levels() {
+        let kinds = supported_kinds();
+        assert_eq!(kinds.len(), DiagramKind::ALL.len());
+        let flowchart = kinds
+            .iter()
+            .find(|info| info.kind == DiagramKind::Flowchart)
+            .unwrap();
+        assert_eq!(flowchart.name, "Flowchart");
+        assert_eq!(flowchart.support, SupportLevel::Full);
+        assert!(
+            kinds
+                .iter()
+                .any(|info| info.support == SupportLevel::Experimental)
+        );
+    }
+
     fn parse_svg_attr(svg: &str, attr: &str) -> Option<f32> {
         let marker = format!("{attr}=\"");
         let start = svg.find(&marker)? + marker.len();
@@ -386,6 +732,293 @@ mod tests {
         assert!(result.total_us() > 0);
     }
 
+    #[test]
+    fn test_render_filtered_drops_nodes_and_edges() {
+        let input = "flowchart LR\nA1[Alpha one] --> A2[Alpha two]\nA2 --> B1[Beta one]\nB1 --> A1\nsubgraph team\nA2\nB2[Beta two]\nend";
+        let svg =
+            render_filtered(input, RenderOptions::default(), |id| id.starts_with('A')).unwrap();
+        assert!(svg.contains("Alpha one") && svg.contains("Alpha two"));
+        assert!(!svg.contains("Beta one") && !svg.contains("Beta two"));
+        assert!(svg.contains("data-edge-id=\"edge-0\""));
+        assert!(!svg.contains("data-edge-id=\"edge-1\""));
+    }
+
+    #[test]
+    fn test_render_sections_splits_gantt_by_section() {
+        let input = "gantt\ndateFormat YYYY-MM-DD\nsection Design\nMockups :a1, 2024-01-01, 3d\nReview :a2, after a1, 2d\nsection Build\nBackend :b1, 2024-01-06, 5d";
+        let sections = render_sections(input, RenderOptions::default()).unwrap();
+        let names: Vec<&str> = sections.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Design", "Build"]);
+        let (_, design) = &sections[0];
+        assert!(design.contains("Mockups") && design.contains("Review"));
+        assert!(!design.contains("Backend"));
+        let (_, build) = &sections[1];
+        assert!(build.contains("Backend"));
+        assert!(!build.contains("Mockups"));
+
+        let flowchart = render_sections("flowchart LR\nA-->B", RenderOptions::default()).unwrap();
+        assert!(flowchart.is_empty());
+    }
+
+    #[test]
+    fn test_complexity_budget_rejects_large_graphs() {
+        // 3 nodes + 2 edges * 2 + 1 subgraph * 4 = 11.
+        let input = "flowchart LR\nsubgraph S\nA --> B\nend\nB --> C";
+        let mut options = RenderOptions::default();
+        options.layout.complexity_budget = Some(10);
+        let err = render_with_options(input, options.clone()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MmdrError>(),
+            Some(&MmdrError::TooComplex {
+                score: 11,
+                budget: 10
+            })
+        );
+        options.layout.complexity_budget = Some(11);
+        assert!(render_with_options(input, options).is_ok());
+    }
+
+    #[test]
+    fn test_render_to_writer_matches_render() {
+        let input = "sequenceDiagram\nAlice->>Bob: hi\nBob-->>Alice: hello";
+        let mut mirrored = RenderOptions::default();
+        mirrored.render.mirror = Some(config::Axis::Horizontal);
+        let mut embedded = RenderOptions::default();
+        embedded.render.embed_source = true;
+        for options in [RenderOptions::default(), mirrored, embedded] {
+            let expected = render_with_options(input, options.clone()).unwrap();
+            let mut out = Vec::new();
+            render_to_writer(input, options, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+
+        struct Failing;
+        impl std::io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = render_to_writer(input, RenderOptions::default(), &mut Failing).unwrap_err();
+        assert!(err.to_string().contains("disk full"));
+    }
+
+    #[test]
+    fn test_frontmatter_theme_applies_unless_caller_set_one() {
+        let input = "---\nconfig:\n  theme: default\n---\nflowchart LR\nA --> B";
+        let classic = Theme::mermaid_default().primary_color;
+        let svg = render_with_options(input, RenderOptions::default()).unwrap();
+        assert!(svg.contains(&format!("fill=\"{classic}\"")));
+
+        let mut custom = RenderOptions::default();
+        custom.theme.primary_color = "#123456".to_string();
+        let svg = render_with_options(input, custom).unwrap();
+        assert!(svg.contains("fill=\"#123456\""));
+        assert!(!svg.contains(&format!("fill=\"{classic}\"")));
+    }
+
+    #[test]
+    fn test_init_theme_variables_override_options() {
+        let input = "%%{init: {'themeVariables': {'primaryColor': '#f00', 'lineColor': '#0f0'}}}%%\nflowchart LR\nA --> B";
+        let svg = render_with_options(input, RenderOptions::mermaid_default()).unwrap();
+        assert!(svg.contains("fill=\"#f00\""));
+        assert!(svg.contains("stroke=\"#0f0\""));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_layout_to_json_round_trips() {
+        let parsed = parse_mermaid("flowchart LR\nA[Start] -->|go| B\nsubgraph S\nB\nend").unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let json = layout_to_json(&layout);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["width"].is_number());
+        assert!(value["nodes"]["A"]["x"].is_number());
+        assert_eq!(value["nodes"]["A"]["label"]["lines"][0], "Start");
+        assert!(value.get("diagram").is_none());
+
+        let restored: Layout = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.nodes.len(), layout.nodes.len());
+        assert_eq!(restored.edges[0].points, layout.edges[0].points);
+        assert_eq!(restored.subgraphs[0].nodes, layout.subgraphs[0].nodes);
+        assert_eq!(layout_to_json(&restored), json);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_embed_layout_json_recovers_node_positions() {
+        let input = "flowchart LR\nA[Start & go] --> B";
+        assert!(!render(input).unwrap().contains("<metadata"));
+
+        let mut options = RenderOptions::default().with_id_prefix("doc");
+        options.render.embed_layout_json = true;
+        let svg = render_with_options(input, options.clone()).unwrap();
+        let start = svg
+            .find("<metadata id=\"doc-layout\">")
+            .expect("layout metadata");
+        assert!(svg.find("</title>").unwrap() < start);
+        let body = &svg[start..];
+        let json = &body[body.find('>').unwrap() + 1..body.find("</metadata>").unwrap()];
+        let json = json
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+        let restored: Layout = serde_json::from_str(&json).unwrap();
+
+        let parsed = parse_mermaid(input).unwrap();
+        let layout = compute_layout(&parsed.graph, &options.theme, &options.layout);
+        assert_eq!(restored.nodes["A"].x, layout.nodes["A"].x);
+        assert_eq!(restored.nodes["B"].y, layout.nodes["B"].y);
+        assert_eq!(restored.nodes["A"].label.lines[0], "Start & go");
+    }
+
+    #[test]
+    fn test_estimate_cost_grows_with_diagram_size() {
+        let tiny = estimate_cost("flowchart LR\nA-->B").unwrap();
+        let mut large = String::from("flowchart TD\n");
+        for i in 0..40 {
+            large.push_str(&format!(
+                "N{i}-->N{}\nN{i}-->N{}\n",
+                i + 1,
+                (i * 7 + 3) % 41
+            ));
+        }
+        let large = estimate_cost(&large).unwrap();
+        assert_eq!((tiny.nodes, tiny.edges), (2, 1));
+        assert!(!tiny.grid_routing);
+        assert!(large.grid_routing);
+        assert!(large.score > tiny.score);
+        assert_eq!(
+            large.score,
+            large.nodes + large.edges * 2 + large.edges * large.nodes
+        );
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let diagram = r#"flowchart TD
+    classDef hot fill:#f96,stroke:#333
+    subgraph outer[Outer]
+        direction LR
+        subgraph left[Left]
+            A[Start] --> B{Check}
+        end
+        subgraph right[Right]
+            C([Retry]) --> D[(Store)]
+        end
+    end
+    B -->|yes| C
+    B -->|no| E>Flag]
+    D -.-> A
+    E ==> F((Done))
+    F --> A
+    A & C --> G[Fan in]
+    class B,E hot
+    linkStyle 2 stroke:#f00
+"#;
+        let first = render(diagram).unwrap();
+        for _ in 0..5 {
+            assert_eq!(render(diagram).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn test_render_is_deterministic_for_disjoint_subgraphs_and_shared_ports() {
+        let diagram = "flowchart LR
+    subgraph one[One]
+        a1 --> a2
+    end
+    subgraph two[Two]
+        b1 --> b2
+    end
+    subgraph three[Three]
+        c1 --> c2
+    end
+    hub --> x & y & z & w
+    x & y & z & w --> sink
+";
+        let first = render(diagram).unwrap();
+        for _ in 0..5 {
+            assert_eq!(render(diagram).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn test_embed_source_comment() {
+        let input = "flowchart LR\nA --> B\nB ---> C";
+        let plain = render(input).unwrap();
+        assert!(!plain.contains("<!--"));
+
+        let svg =
+            render_with_options(input, RenderOptions::default().with_embed_source(true)).unwrap();
+        let start = svg.find("<!--").expect("source comment") + 4;
+        let end = svg[start..].find("-->").unwrap() + start;
+        assert!(svg[..start].starts_with("<svg"));
+        let comment = &svg[start..end];
+        assert!(!comment.contains("--"));
+        assert!(comment.contains(env!("CARGO_PKG_VERSION")));
+        assert!(comment.contains("flowchart LR\nA - -> B\nB - - -> C"));
+
+        // A layout rendered directly knows no source; embedding it afterwards
+        // yields the same document.
+        let parsed = parse_mermaid(input).unwrap();
+        let options = RenderOptions::default().with_embed_source(true);
+        let layout = compute_layout(&parsed.graph, &options.theme, &options.layout);
+        let direct =
+            render_svg_with_config(&layout, &options.theme, &options.layout, &options.render);
+        assert!(!direct.contains("<!--"));
+        assert_eq!(embed_source_comment(&direct, input), svg);
+    }
+
+    #[test]
+    fn test_render_paginated_sequence() {
+        let mut input = String::from("sequenceDiagram\nparticipant Alice\nparticipant Bob\n");
+        for idx in 0..40 {
+            input.push_str(&format!(
+                "Alice->>Bob: ping {idx}\nBob-->>Alice: pong {idx}\n"
+            ));
+        }
+        let full = render(&input).unwrap();
+        let full_height = parse_svg_attr(&full, "height").unwrap();
+        let page_height = 400.0;
+        let pages = render_paginated(&input, RenderOptions::default(), page_height).unwrap();
+        assert!(pages.len() > 1);
+        assert!(pages.len() as f32 >= full_height / page_height);
+        for page in &pages {
+            assert!(page.contains("Alice"));
+            assert!(page.contains("Bob"));
+            assert!(page.contains("-header-clip"));
+            assert!(parse_svg_attr(page, "height").unwrap() <= page_height + 0.01);
+        }
+
+        // Canvas-moving options are dropped so page geometry matches the
+        // layout, and each page keeps its own accessible title.
+        let mut framed = RenderOptions::default();
+        framed.render.frame = Some(config::FrameSpec {
+            title: Some("Handshake".to_string()),
+            ..Default::default()
+        });
+        let described = format!("{input}accDescr: Ping pong\n");
+        let framed_pages = render_paginated(&described, framed, page_height).unwrap();
+        assert_eq!(framed_pages.len(), pages.len());
+        for page in &framed_pages {
+            let open_tag = &page[..page.find('>').unwrap()];
+            assert!(open_tag.contains("viewBox=\"0 0 "));
+            assert!(open_tag.contains("role=\"img\""));
+            assert!(open_tag.contains("aria-describedby=\""));
+            let title = page.find("<title>").unwrap();
+            let desc = page.find("<desc ").unwrap();
+            assert!(title < desc && desc < page.find("<defs>").unwrap());
+            assert!(!page.contains("frame-title"));
+        }
+
+        let single =
+            render_paginated("flowchart LR; A-->B", RenderOptions::default(), 10.0).unwrap();
+        assert_eq!(single.len(), 1);
+    }
+
     #[test]
     fn test_class_diagram() {
         let svg = render(include_str!("../tests/fixtures/unit/lib_class_diagram.mmd")).unwrap();
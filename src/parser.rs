@@ -1,8 +1,10 @@
+use crate::config::ConfigOverride;
+use crate::error::ParseError;
 use crate::ir::{DiagramKind, Direction, Graph, NodeStyle, Subgraph};
 use anyhow::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 type NodeTokenParts = (
     String,
@@ -45,17 +47,47 @@ static ARROW_RE: Lazy<Regex> = Lazy::new(|| {
     )
     .unwrap()
 });
+/// A flowchart statement ending in a link with no target node.
+static DANGLING_ARROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\S.*?\s*(?P<arrow><?[-.=]*[-=][-.=]*[-=][-.=]*[>xo]?)(?:\|[^|]*\|)?\s*$").unwrap()
+});
 static ARROW_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"<[-.=ox]*[-=]+[-.=ox]*>|<[-.=ox]*[-=]+|[-.=ox]*[-=]+>|[-.=ox]*[-=]+").unwrap()
 });
 
+/// Link starts that open mid-link text, as in `A -- text --> B`.
+const MID_TEXT_OPENERS: [&str; 6] = ["--", "==", "-.", "<--", "<==", "<-."];
+
 #[derive(Debug, Default)]
 pub struct ParseOutput {
     pub graph: Graph,
     pub init_config: Option<serde_json::Value>,
+    /// Non-fatal problems found while parsing, such as redeclared
+    /// participants.
+    pub warnings: Vec<String>,
+    /// Recognized settings from `init_config`.
+    pub config_override: ConfigOverride,
 }
 
 pub fn parse_mermaid(input: &str) -> Result<ParseOutput> {
+    let mut output = match extract_accessibility(input) {
+        Some((stripped, accessibility)) => {
+            let mut output = parse_diagram(&stripped)?;
+            output.graph.accessibility = accessibility;
+            output
+        }
+        None => parse_diagram(input)?,
+    };
+    output.graph.title = split_frontmatter(input)
+        .1
+        .and_then(|yaml| yaml.get("title")?.as_str().map(str::to_string));
+    if let Some(init) = &output.init_config {
+        output.config_override = ConfigOverride::from_init(init);
+    }
+    Ok(output)
+}
+
+fn parse_diagram(input: &str) -> Result<ParseOutput> {
     match detect_diagram_kind(input) {
         DiagramKind::Class => parse_class_diagram(input),
         DiagramKind::State => parse_state_diagram(input),
@@ -83,12 +115,68 @@ pub fn parse_mermaid(input: &str) -> Result<ParseOutput> {
     }
 }
 
-fn extract_frontmatter_config(frontmatter: &str) -> Option<serde_json::Value> {
-    let value = serde_yaml::from_str::<serde_json::Value>(frontmatter).ok()?;
-    value.get("config").cloned()
+/// Pull `accTitle:`, `accDescr:` and `accDescr { ... }` out of `input`, since
+/// they apply to every diagram kind. Consumed lines are left blank. Returns
+/// `None` when there are none.
+fn extract_accessibility(input: &str) -> Option<(String, crate::ir::Accessibility)> {
+    let mut accessibility = crate::ir::Accessibility::default();
+    let mut found = false;
+    let mut lines: Vec<&str> = Vec::new();
+    let mut block: Option<Vec<&str>> = None;
+    for line in input.lines() {
+        let trimmed = line.trim();
+        if let Some(parts) = block.as_mut() {
+            lines.push("");
+            match trimmed.split_once('}') {
+                Some((last, _)) => {
+                    parts.push(last.trim());
+                    let text = parts.join("\n").trim().to_string();
+                    accessibility.description = Some(text);
+                    block = None;
+                }
+                None => parts.push(trimmed),
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("accTitle")
+            && let Some(value) = rest.trim_start().strip_prefix(':')
+        {
+            accessibility.title = Some(value.trim().to_string());
+            found = true;
+            lines.push("");
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("accDescr") {
+            let rest = rest.trim_start();
+            if let Some(value) = rest.strip_prefix(':') {
+                accessibility.description = Some(value.trim().to_string());
+                found = true;
+                lines.push("");
+                continue;
+            }
+            if let Some(body) = rest.strip_prefix('{') {
+                found = true;
+                lines.push("");
+                match body.split_once('}') {
+                    Some((text, _)) => accessibility.description = Some(text.trim().to_string()),
+                    None => block = Some(vec![body.trim()]),
+                }
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    found.then(|| (lines.join("\n"), accessibility))
 }
 
 fn split_leading_frontmatter(input: &str) -> (Vec<&str>, Option<serde_json::Value>) {
+    let (lines, yaml) = split_frontmatter(input);
+    (lines, yaml.and_then(|yaml| yaml.get("config").cloned()))
+}
+
+/// Split a leading `---` fenced YAML block off `input`, returning the
+/// remaining lines and the parsed block.
+fn split_frontmatter(input: &str) -> (Vec<&str>, Option<serde_json::Value>) {
     let lines: Vec<&str> = input.lines().collect();
     let mut first_content_idx = 0;
     while first_content_idx < lines.len() && lines[first_content_idx].trim().is_empty() {
@@ -104,8 +192,8 @@ fn split_leading_frontmatter(input: &str) -> (Vec<&str>, Option<serde_json::Valu
         let trimmed = lines[end_idx].trim();
         if trimmed == "---" || trimmed == "..." {
             let frontmatter = lines[first_content_idx + 1..end_idx].join("\n");
-            let config = extract_frontmatter_config(&frontmatter);
-            return (lines[end_idx + 1..].to_vec(), config);
+            let yaml = serde_yaml::from_str::<serde_json::Value>(&frontmatter).ok();
+            return (lines[end_idx + 1..].to_vec(), yaml);
         }
         end_idx += 1;
     }
@@ -205,11 +293,26 @@ fn detect_diagram_kind(input: &str) -> DiagramKind {
 }
 
 fn preprocess_input(input: &str) -> Result<(Vec<String>, Option<serde_json::Value>)> {
+    let (lines, init_config) = preprocess_input_numbered(input)?;
+    Ok((
+        lines.into_iter().map(|(_, line)| line).collect(),
+        init_config,
+    ))
+}
+
+/// [`preprocess_input`] that also keeps the 0-based index of the source line
+/// each statement line came from, for error locations.
+fn preprocess_input_numbered(
+    input: &str,
+) -> Result<(Vec<(usize, String)>, Option<serde_json::Value>)> {
     let (source_lines, frontmatter_config) = split_leading_frontmatter(input);
+    // Frontmatter is only ever stripped from the front, so the remaining
+    // lines are a suffix of the input.
+    let first_line = input.lines().count() - source_lines.len();
     let mut init_config: Option<serde_json::Value> = frontmatter_config;
     let mut lines = Vec::new();
 
-    for raw_line in source_lines {
+    for (idx, raw_line) in source_lines.into_iter().enumerate() {
         let trimmed_line = raw_line.trim();
         if trimmed_line.is_empty() {
             continue;
@@ -231,7 +334,7 @@ fn preprocess_input(input: &str) -> Result<(Vec<String>, Option<serde_json::Valu
         if without_comment.is_empty() {
             continue;
         }
-        lines.push(without_comment.to_string());
+        lines.push((first_line + idx, without_comment.to_string()));
     }
 
     Ok((lines, init_config))
@@ -270,14 +373,31 @@ fn preprocess_input_keep_indent(input: &str) -> Result<(Vec<String>, Option<serd
     Ok((lines, init_config))
 }
 
+/// 1-based line and column of byte `offset` within `statement`, which was
+/// read from the 0-based source line `line_idx`, along with that line's text.
+/// The column falls back to the start of the line when the statement was
+/// rewritten during preprocessing.
+fn locate_in_input(
+    input: &str,
+    line_idx: usize,
+    statement: &str,
+    offset: usize,
+) -> (usize, usize, String) {
+    let text = input.lines().nth(line_idx).unwrap_or("");
+    let column = text
+        .find(statement)
+        .map_or(1, |start| text[..start + offset].chars().count() + 1);
+    (line_idx + 1, column, text.trim_end().to_string())
+}
+
 fn parse_flowchart(input: &str) -> Result<ParseOutput> {
     let mut graph = Graph::new();
     graph.kind = DiagramKind::Flowchart;
     let mut subgraph_stack: Vec<usize> = Vec::new();
 
-    let (lines, init_config) = preprocess_input(input)?;
+    let (lines, init_config) = preprocess_input_numbered(input)?;
 
-    for raw_line in lines {
+    for (line_idx, raw_line) in lines {
         for line in split_statements(&raw_line) {
             if line.is_empty() {
                 continue;
@@ -306,9 +426,9 @@ fn parse_flowchart(input: &str) -> Result<ParseOutput> {
                     icon: None,
                 });
                 subgraph_stack.push(graph.subgraphs.len() - 1);
-                if let Some(id) = id {
-                    apply_subgraph_classes(&mut graph, &id, &classes);
-                }
+                let key =
+                    id.unwrap_or_else(|| graph.subgraphs[graph.subgraphs.len() - 1].label.clone());
+                apply_subgraph_classes(&mut graph, &key, &classes);
                 continue;
             }
 
@@ -355,6 +475,30 @@ fn parse_flowchart(input: &str) -> Result<ParseOutput> {
                 continue;
             }
 
+            if let (base, Some(_)) = split_node_metadata(&line)
+                && !base.trim().is_empty()
+                && !base.trim().contains(char::is_whitespace)
+                && parse_edge_line(&line).is_none()
+            {
+                add_flowchart_node_ref(&line, &mut graph, &subgraph_stack);
+                continue;
+            }
+
+            if let Some(arrow) = DANGLING_ARROW_RE
+                .captures(&line)
+                .and_then(|caps| caps.name("arrow"))
+            {
+                let (line_no, column, snippet) =
+                    locate_in_input(input, line_idx, &line, arrow.start());
+                return Err(ParseError {
+                    message: format!("link `{}` has no target node", arrow.as_str()),
+                    line: line_no,
+                    column,
+                    snippet,
+                }
+                .into());
+            }
+
             if let Some(chain_lines) = split_edge_chain(&line) {
                 let mut added = false;
                 for edge_line in chain_lines {
@@ -377,7 +521,12 @@ fn parse_flowchart(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn add_flowchart_edge(line: &str, graph: &mut Graph, subgraph_stack: &[usize]) -> bool {
@@ -388,25 +537,14 @@ fn add_flowchart_edge(line: &str, graph: &mut Graph, subgraph_stack: &[usize]) -
     let sources = split_on_ampersand(&left);
     let targets = split_on_ampersand(&right);
 
-    let mut source_ids = Vec::new();
-    for source in sources {
-        let (left_id, left_label, left_shape, left_classes) = parse_node_token(source);
-        let left_explicit = left_label.is_some() || left_shape.is_some();
-        graph.ensure_node(&left_id, left_label, left_shape);
-        apply_node_classes(graph, &left_id, &left_classes);
-        update_node_subgraph_membership(graph, subgraph_stack, &left_id, left_explicit);
-        source_ids.push(left_id);
-    }
-
-    let mut target_ids = Vec::new();
-    for target in targets {
-        let (right_id, right_label, right_shape, right_classes) = parse_node_token(target);
-        let right_explicit = right_label.is_some() || right_shape.is_some();
-        graph.ensure_node(&right_id, right_label, right_shape);
-        apply_node_classes(graph, &right_id, &right_classes);
-        update_node_subgraph_membership(graph, subgraph_stack, &right_id, right_explicit);
-        target_ids.push(right_id);
-    }
+    let source_ids: Vec<String> = sources
+        .into_iter()
+        .map(|source| add_flowchart_node_ref(source, graph, subgraph_stack))
+        .collect();
+    let target_ids: Vec<String> = targets
+        .into_iter()
+        .map(|target| add_flowchart_node_ref(target, graph, subgraph_stack))
+        .collect();
 
     for left_id in &source_ids {
         for right_id in &target_ids {
@@ -431,6 +569,44 @@ fn add_flowchart_edge(line: &str, graph: &mut Graph, subgraph_stack: &[usize]) -
     true
 }
 
+/// Registers one node reference of a flowchart statement, including any
+/// trailing `@{ ... }` metadata block, and returns its id.
+fn add_flowchart_node_ref(token: &str, graph: &mut Graph, subgraph_stack: &[usize]) -> String {
+    let (base, meta) = split_node_metadata(token);
+    let (id, label, shape, classes) = parse_node_token(&base);
+    let explicit = label.is_some() || shape.is_some() || meta.is_some();
+    graph.ensure_node(&id, label, shape);
+    if let Some(meta) = meta {
+        apply_node_metadata(graph, &id, meta);
+    }
+    apply_node_classes(graph, &id, &classes);
+    update_node_subgraph_membership(graph, subgraph_stack, &id, explicit);
+    id
+}
+
+/// Splits `id@{ ... }` (optionally followed by `:::class`) into the node
+/// token without the block and the block's contents.
+fn split_node_metadata(token: &str) -> (String, Option<&str>) {
+    let trimmed = token.trim();
+    let Some(open) = trimmed.find("@{") else {
+        return (trimmed.to_string(), None);
+    };
+    let body = &trimmed[open + 2..];
+    let mut quote: Option<char> = None;
+    for (idx, ch) in body.char_indices() {
+        match (quote, ch) {
+            (Some(q), _) if ch == q => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '}') => {
+                let base = format!("{}{}", &trimmed[..open], &body[idx + 1..]);
+                return (base, Some(&body[..idx]));
+            }
+            _ => {}
+        }
+    }
+    (trimmed.to_string(), None)
+}
+
 fn split_trailing_quoted(input: &str) -> Option<(&str, &str)> {
     let trimmed = input.trim_end();
     let quote = trimmed.chars().last()?;
@@ -660,6 +836,18 @@ fn normalize_class_method_signature(entry: &str) -> String {
     format!("{} : {}", sig, rest)
 }
 
+/// Split a `<<stereotype>>` prefix off a class line, returning the stereotype
+/// and whatever follows it (the class id for the standalone form).
+fn parse_class_stereotype(line: &str) -> Option<(String, &str)> {
+    let rest = line.trim().strip_prefix("<<")?;
+    let end = rest.find(">>")?;
+    let stereotype = rest[..end].trim();
+    if stereotype.is_empty() {
+        return None;
+    }
+    Some((stereotype.to_string(), rest[end + 2..].trim()))
+}
+
 fn parse_class_member_line(line: &str) -> Option<(String, String)> {
     let (left, right) = line.split_once(':')?;
     let id = left.trim();
@@ -876,6 +1064,16 @@ fn normalize_state_token(
         };
         return (id, shape, Some(String::new()));
     }
+    // History pseudostates: `[H]` is shallow, `[H*]` deep. One of each per scope.
+    if trimmed == "[H]" || trimmed == "[H*]" {
+        let (kind, label) = if trimmed == "[H*]" {
+            ("deep_history", "H*")
+        } else {
+            ("history", "H")
+        };
+        let id = format!("__{kind}_{scope}__");
+        return (id, crate::ir::NodeShape::Circle, Some(label.to_string()));
+    }
     (strip_quotes(trimmed), crate::ir::NodeShape::RoundRect, None)
 }
 
@@ -1065,51 +1263,106 @@ fn ensure_sequence_node(
     }
 }
 
-fn parse_sequence_message(
-    line: &str,
-) -> Option<(
-    String,
-    String,
-    Option<String>,
-    crate::ir::EdgeStyle,
-    Option<crate::ir::SequenceActivationKind>,
-)> {
+struct SequenceMessage {
+    from: String,
+    to: String,
+    label: Option<String>,
+    style: crate::ir::EdgeStyle,
+    activation: Option<crate::ir::SequenceActivationKind>,
+    arrow_end: bool,
+    arrow_end_kind: Option<crate::ir::EdgeArrowhead>,
+    end_decoration: Option<crate::ir::EdgeDecoration>,
+}
+
+fn parse_sequence_message(line: &str) -> Option<SequenceMessage> {
     let tokens = [
-        "-->>+", "->>+", "-->+", "->+", "-->>-", "->>-", "-->-", "->-", "<--+", "<-+", "<--", "<-",
-        "-->>", "->>", "-->", "->",
+        "-->>+", "->>+", "--)+", "-)+", "--x+", "-x+", "-->+", "->+", "-->>-", "->>-", "--)-",
+        "-)-", "--x-", "-x-", "-->-", "->-", "<--+", "<-+", "<--", "<-", "-->>", "->>", "--)",
+        "-)", "--x", "-x", "-->", "->",
     ];
-    for token in tokens {
-        if let Some(pos) = line.find(token) {
-            let left = line[..pos].trim();
-            let right_part = line[pos + token.len()..].trim();
-            if left.is_empty() || right_part.is_empty() {
-                continue;
-            }
-            let (right, label) = split_label(right_part);
-            let mut from = left.to_string();
-            let mut to = right.to_string();
-            if token.starts_with('<') {
-                std::mem::swap(&mut from, &mut to);
-            }
-            let trimmed = token.trim_start_matches('<').trim_end_matches(['+', '-']);
-            let style = if trimmed.starts_with("--") {
-                crate::ir::EdgeStyle::Dotted
-            } else {
-                crate::ir::EdgeStyle::Solid
-            };
-            let activation = if token.ends_with('+') {
-                Some(crate::ir::SequenceActivationKind::Activate)
-            } else if token.ends_with('-') {
-                Some(crate::ir::SequenceActivationKind::Deactivate)
-            } else {
-                None
-            };
-            return Some((from, to, label, style, activation));
+    // Only the text before the label can hold the operator. Arrows with a
+    // `>`/`<` head win over `-x`/`-)`, which also occur inside hyphenated ids
+    // (`web-xhr`, `api-x`). A weak `-x` that runs straight into more id
+    // characters ranks after one on an identifier boundary; within a rank the
+    // leftmost and longest match wins.
+    let head = line.find(':').map_or(line, |idx| &line[..idx]);
+    let mut matches: Vec<(usize, &str)> = tokens
+        .iter()
+        .flat_map(|token| head.match_indices(token))
+        .filter(|(pos, token)| {
+            is_strong_sequence_arrow(token)
+                || !matches!(head[pos + token.len()..].chars().next(), Some('>' | '-'))
+        })
+        .collect();
+    matches.sort_by_key(|(pos, token)| {
+        let splits_word = token.ends_with('x')
+            && head[pos + token.len()..]
+                .chars()
+                .next()
+                .is_some_and(|ch| ch.is_alphanumeric() || ch == '_');
+        (
+            !is_strong_sequence_arrow(token),
+            splits_word,
+            *pos,
+            std::cmp::Reverse(token.len()),
+        )
+    });
+    for (pos, token) in matches {
+        let left = line[..pos].trim();
+        let right_part = line[pos + token.len()..].trim();
+        if left.is_empty() || right_part.is_empty() {
+            continue;
         }
+        let (right, label) = split_label(right_part);
+        let mut from = left.to_string();
+        let mut to = right.to_string();
+        if token.starts_with('<') {
+            std::mem::swap(&mut from, &mut to);
+        }
+        let trimmed = token.trim_start_matches('<').trim_end_matches(['+', '-']);
+        let style = if trimmed.starts_with("--") {
+            crate::ir::EdgeStyle::Dotted
+        } else {
+            crate::ir::EdgeStyle::Solid
+        };
+        let activation = if token.ends_with('+') {
+            Some(crate::ir::SequenceActivationKind::Activate)
+        } else if token.ends_with('-') {
+            Some(crate::ir::SequenceActivationKind::Deactivate)
+        } else {
+            None
+        };
+        // `->>` draws a filled head, `-)` an open async head, `-x` a cross
+        // and a bare `->` none at all.
+        let (arrow_end, arrow_end_kind, end_decoration) = if token.starts_with('<') {
+            (true, None, None)
+        } else if trimmed.ends_with(">>") {
+            (true, None, None)
+        } else if trimmed.ends_with(')') {
+            (true, Some(crate::ir::EdgeArrowhead::SequenceAsync), None)
+        } else if trimmed.ends_with('x') {
+            (false, None, Some(crate::ir::EdgeDecoration::Cross))
+        } else {
+            (false, None, None)
+        };
+        return Some(SequenceMessage {
+            from,
+            to,
+            label,
+            style,
+            activation,
+            arrow_end,
+            arrow_end_kind,
+            end_decoration,
+        });
     }
     None
 }
 
+fn is_strong_sequence_arrow(token: &str) -> bool {
+    token.starts_with('<') || token.contains('>')
+}
+
 fn parse_sequence_note(
     line: &str,
 ) -> Option<(crate::ir::SequenceNotePosition, Vec<String>, String)> {
@@ -1167,6 +1420,7 @@ fn parse_class_diagram(input: &str) -> Result<ParseOutput> {
 
     let mut members: HashMap<String, Vec<String>> = HashMap::new();
     let mut labels: HashMap<String, String> = HashMap::new();
+    let mut stereotypes: HashMap<String, String> = HashMap::new();
     let mut current_class: Option<String> = None;
 
     for raw_line in lines {
@@ -1273,18 +1527,44 @@ fn parse_class_diagram(input: &str) -> Result<ParseOutput> {
             }
         }
 
+        if let Some((stereotype, id)) = parse_class_stereotype(line)
+            && !id.is_empty()
+        {
+            let (id, _) = normalize_class_id(id);
+            graph.ensure_node(
+                &id,
+                labels.get(&id).cloned(),
+                Some(crate::ir::NodeShape::Rectangle),
+            );
+            stereotypes.insert(id, stereotype);
+            continue;
+        }
+
         if let Some((id, member)) = parse_class_member_line(line) {
             members.entry(id).or_default().push(member);
             continue;
         }
     }
 
+    for (id, items) in members.iter_mut() {
+        items.retain(|entry| match parse_class_stereotype(entry) {
+            Some((stereotype, "")) => {
+                stereotypes.insert(id.clone(), stereotype);
+                false
+            }
+            _ => true,
+        });
+    }
+
     for (id, node) in graph.nodes.iter_mut() {
         let class_name = labels
             .get(id)
             .cloned()
             .unwrap_or_else(|| node.label.clone());
         let mut lines = Vec::new();
+        if let Some(stereotype) = stereotypes.get(id) {
+            lines.push(format!("«{stereotype}»"));
+        }
         lines.push(class_name.clone());
         if let Some(items) = members.get(id)
             && !items.is_empty()
@@ -1313,7 +1593,12 @@ fn parse_class_diagram(input: &str) -> Result<ParseOutput> {
         node.label = lines.join("\n");
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn is_er_card_char(ch: char) -> bool {
@@ -1567,7 +1852,12 @@ fn parse_er_diagram(input: &str) -> Result<ParseOutput> {
         node.label = lines.join("\n");
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_pie_diagram(input: &str) -> Result<ParseOutput> {
@@ -1613,7 +1903,12 @@ fn parse_pie_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_pie_slice_line(line: &str) -> Option<(String, f32)> {
@@ -1758,7 +2053,12 @@ fn parse_mindmap_diagram(input: &str) -> Result<ParseOutput> {
         stack.push(id);
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_mindmap_node_token(
@@ -1922,7 +2222,12 @@ fn parse_journey_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_journey_task_line(line: &str) -> Option<(String, Option<f32>, Vec<String>)> {
@@ -2036,7 +2341,12 @@ fn parse_timeline_diagram(input: &str) -> Result<ParseOutput> {
         &current_section,
     );
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_gantt_diagram(input: &str) -> Result<ParseOutput> {
@@ -2112,7 +2422,8 @@ fn parse_gantt_diagram(input: &str) -> Result<ParseOutput> {
                 label: label.to_string(),
                 start,
                 duration,
-                after: after.clone(),
+                after: after.first().cloned(),
+                after_all: after.clone(),
                 section: current_section_name.clone(),
                 status,
             });
@@ -2128,10 +2439,10 @@ fn parse_gantt_diagram(input: &str) -> Result<ParseOutput> {
                 subgraph.nodes.push(node_id.clone());
             }
 
-            if let Some(after_id) = after {
-                graph.ensure_node(&after_id, None, Some(crate::ir::NodeShape::Rectangle));
+            for after_id in &after {
+                graph.ensure_node(after_id, None, Some(crate::ir::NodeShape::Rectangle));
                 graph.edges.push(crate::ir::Edge {
-                    from: after_id,
+                    from: after_id.clone(),
                     to: node_id.clone(),
                     label: None,
                     start_label: None,
@@ -2145,7 +2456,10 @@ fn parse_gantt_diagram(input: &str) -> Result<ParseOutput> {
                     end_decoration: None,
                     style: crate::ir::EdgeStyle::Solid,
                 });
-            } else if let Some(prev) = last_task.take() {
+            }
+            if after.is_empty()
+                && let Some(prev) = last_task.take()
+            {
                 graph.edges.push(crate::ir::Edge {
                     from: prev,
                     to: node_id.clone(),
@@ -2167,7 +2481,12 @@ fn parse_gantt_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_gantt_task_meta(
@@ -2175,12 +2494,12 @@ fn parse_gantt_task_meta(
 ) -> (
     Option<String>,
     Vec<String>,
-    Option<String>,
+    Vec<String>,
     Option<crate::ir::GanttStatus>,
 ) {
     let mut id: Option<String> = None;
     let mut details: Vec<String> = Vec::new();
-    let mut after: Option<String> = None;
+    let mut after: Vec<String> = Vec::new();
     let mut status: Option<crate::ir::GanttStatus> = None;
 
     for raw_token in meta.split(',') {
@@ -2189,11 +2508,12 @@ fn parse_gantt_task_meta(
             continue;
         }
         let lower = token.to_ascii_lowercase();
-        if let Some(rest) = lower.strip_prefix("after ") {
-            let dep = rest.trim().to_string();
-            if !dep.is_empty() {
-                after = Some(dep);
-            }
+        if lower.starts_with("after ") {
+            after.extend(
+                token["after ".len()..]
+                    .split_whitespace()
+                    .map(str::to_string),
+            );
             continue;
         }
         if let Some(token_status) = gantt_status_from_token(&lower) {
@@ -2404,17 +2724,20 @@ fn parse_requirement_diagram(input: &str) -> Result<ParseOutput> {
     }
 
     for (id, node) in graph.nodes.iter_mut() {
-        if let Some(attrs) = attributes.get(id)
-            && !attrs.is_empty()
-        {
-            let mut lines = Vec::new();
-            lines.push(node.label.clone());
-            lines.extend(attrs.iter().map(|attr| normalize_requirement_attr(attr)));
-            node.label = lines.join("\n");
+        if let Some(attrs) = attributes.get(id) {
+            node.fields = attrs
+                .iter()
+                .map(|attr| normalize_requirement_attr(attr))
+                .collect();
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_requirement_relation_line(line: &str) -> Option<(String, String, String)> {
@@ -2590,7 +2913,12 @@ fn parse_gitgraph_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_gitgraph_direction(line: &str) -> Option<Direction> {
@@ -2814,7 +3142,12 @@ fn parse_c4_diagram(input: &str) -> Result<ParseOutput> {
         process_c4_line(line, &mut graph.c4, &mut boundary_stack);
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn process_c4_line(line: &str, c4: &mut crate::ir::C4Data, boundary_stack: &mut Vec<String>) {
@@ -3336,7 +3669,12 @@ fn parse_sankey_diagram(input: &str) -> Result<ParseOutput> {
         });
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_quadrant_diagram(input: &str) -> Result<ParseOutput> {
@@ -3410,7 +3748,12 @@ fn parse_quadrant_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_quadrant_point_coords(line: &str) -> Option<(String, f32, f32)> {
@@ -3473,7 +3816,12 @@ fn parse_zenuml_diagram(input: &str) -> Result<ParseOutput> {
 
     graph.sequence_participants = order;
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_zenuml_message_line(
@@ -3635,7 +3983,12 @@ fn parse_block_diagram(input: &str) -> Result<ParseOutput> {
     // The layout stage infers an implicit grid from graph topology in that case.
     graph.block = Some(block);
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_packet_diagram(input: &str) -> Result<ParseOutput> {
@@ -3692,7 +4045,12 @@ fn parse_packet_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_kanban_diagram(input: &str) -> Result<ParseOutput> {
@@ -3752,7 +4110,12 @@ fn parse_kanban_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_architecture_diagram(input: &str) -> Result<ParseOutput> {
@@ -3820,7 +4183,12 @@ fn parse_architecture_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_architecture_node(
@@ -3941,7 +4309,12 @@ fn parse_radar_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_radar_curve(line: &str) -> Option<(String, Vec<String>)> {
@@ -4033,7 +4406,12 @@ fn parse_treemap_diagram(input: &str) -> Result<ParseOutput> {
         stack.push(node_id);
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_treemap_item(line: &str) -> (String, Option<String>) {
@@ -4133,7 +4511,12 @@ fn parse_xy_chart_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_xy_series_line_v2(
@@ -4299,6 +4682,7 @@ fn parse_state_diagram(input: &str) -> Result<ParseOutput> {
                         stroke_width: Some(0.0),
                         stroke_dasharray: None,
                         line_color: None,
+                        opacity: None,
                     },
                 );
             }
@@ -4570,7 +4954,12 @@ fn parse_state_diagram(input: &str) -> Result<ParseOutput> {
         }
     }
 
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings: Vec::new(),
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
@@ -4581,6 +4970,8 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
 
     let mut labels: HashMap<String, String> = HashMap::new();
     let mut order: Vec<String> = Vec::new();
+    let mut declared: HashSet<String> = HashSet::new();
+    let mut warnings: Vec<String> = Vec::new();
     let mut open_frames: Vec<crate::ir::SequenceFrame> = Vec::new();
     let mut frames: Vec<crate::ir::SequenceFrame> = Vec::new();
     let mut open_boxes: Vec<crate::ir::SequenceBox> = Vec::new();
@@ -4598,6 +4989,13 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
             if !order.contains(&id) {
                 order.push(id.clone());
             }
+            // A repeated declaration keeps the first alias and shape.
+            if !declared.insert(id.clone()) {
+                warnings.push(format!(
+                    "participant '{id}' is declared more than once; keeping the first declaration"
+                ));
+                continue;
+            }
             if let Some(label) = label.clone() {
                 labels.insert(id.clone(), label);
             }
@@ -4804,12 +5202,18 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
         }
         if lower.starts_with("autonumber") {
             let parts = line.split_whitespace().collect::<Vec<_>>();
+            graph.sequence_autonumber_step = 1;
             if parts.len() >= 2 {
                 let token = parts[1].to_ascii_lowercase();
                 if token == "off" || token == "stop" || token == "disable" {
                     graph.sequence_autonumber = None;
                 } else if let Ok(start) = parts[1].parse::<usize>() {
                     graph.sequence_autonumber = Some(start);
+                    graph.sequence_autonumber_step = parts
+                        .get(2)
+                        .and_then(|step| step.parse::<usize>().ok())
+                        .filter(|step| *step > 0)
+                        .unwrap_or(1);
                 } else {
                     graph.sequence_autonumber = Some(1);
                 }
@@ -4819,7 +5223,17 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
             continue;
         }
 
-        if let Some((from, to, label, style, activation)) = parse_sequence_message(line) {
+        if let Some(SequenceMessage {
+            from,
+            to,
+            label,
+            style,
+            activation,
+            arrow_end,
+            arrow_end_kind,
+            end_decoration,
+        }) = parse_sequence_message(line)
+        {
             if !order.contains(&from) {
                 order.push(from.clone());
             }
@@ -4836,11 +5250,11 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
                 end_label: None,
                 directed: true,
                 arrow_start: false,
-                arrow_end: true,
+                arrow_end,
                 arrow_start_kind: None,
-                arrow_end_kind: None,
+                arrow_end_kind,
                 start_decoration: None,
-                end_decoration: None,
+                end_decoration,
                 style,
             });
             if let Some(kind) = activation
@@ -4872,7 +5286,12 @@ fn parse_sequence_diagram(input: &str) -> Result<ParseOutput> {
 
     graph.sequence_participants = order;
     graph.sequence_frames = frames;
-    Ok(ParseOutput { graph, init_config })
+    Ok(ParseOutput {
+        graph,
+        init_config,
+        warnings,
+        config_override: ConfigOverride::default(),
+    })
 }
 
 fn add_node_to_subgraph(graph: &mut Graph, idx: usize, node_id: &str) {
@@ -5216,14 +5635,6 @@ fn split_on_ampersand(input: &str) -> Vec<&str> {
 
 fn split_edge_chain(line: &str) -> Option<Vec<String>> {
     let masked = mask_bracket_content(line);
-    if PIPE_LABEL_RE.is_match(&masked)
-        || QUOTED_LABEL_ARROW_RE.is_match(line)
-        || LABEL_ARROW_RE.is_match(&masked)
-        || COMPACT_DOTTED_LABEL_ARROW_RE.is_match(&masked)
-    {
-        return None;
-    }
-
     let matches: Vec<regex::Match> = ARROW_TOKEN_RE.find_iter(&masked).collect();
     if matches.len() < 2 {
         return None;
@@ -5233,14 +5644,55 @@ fn split_edge_chain(line: &str) -> Option<Vec<String>> {
     let mut arrows: Vec<String> = Vec::with_capacity(matches.len());
     let mut last_idx = 0usize;
 
-    for m in matches {
+    let mut idx = 0usize;
+    while idx < matches.len() {
+        let m = matches[idx];
         nodes.push(line[last_idx..m.start()].trim().to_string());
-        arrows.push(line[m.start()..m.end()].trim().to_string());
-        last_idx = m.end();
+        // `-- text -->`, `== text ==>`, `-. text .->`: the opener and the
+        // closing link form one labeled arrow. Lone dashes inside the text
+        // are not links. The token regex stops `-.` short of its dot.
+        let (token, text_start) = match m.as_str().trim() {
+            token @ ("-" | "<-") if masked[m.end()..].starts_with('.') => {
+                (format!("{token}."), m.end() + 1)
+            }
+            token => (token.to_string(), m.end()),
+        };
+        let closer = if MID_TEXT_OPENERS.contains(&token.as_str()) {
+            matches[idx + 1..]
+                .iter()
+                .position(|next| next.as_str().trim().len() >= 2)
+                .map(|offset| idx + 1 + offset)
+        } else {
+            None
+        };
+        if let Some(close_idx) = closer {
+            let close = matches[close_idx];
+            arrows.push(format!(
+                "{} {} {}",
+                token,
+                line[text_start..close.start()].trim(),
+                close.as_str().trim()
+            ));
+            last_idx = close.end();
+            idx = close_idx + 1;
+        } else {
+            arrows.push(line[m.start()..m.end()].trim().to_string());
+            last_idx = m.end();
+            idx += 1;
+        }
+        // Dashes inside a following `|label|` are label text.
+        if let Some(rest) = masked[last_idx..].trim_start().strip_prefix('|')
+            && let Some(end) = rest.find('|')
+        {
+            let label_end = masked.len() - rest.len() + end + 1;
+            while idx < matches.len() && matches[idx].start() < label_end {
+                idx += 1;
+            }
+        }
     }
     nodes.push(line[last_idx..].trim().to_string());
 
-    if nodes.len() != arrows.len() + 1 {
+    if arrows.len() < 2 || nodes.len() != arrows.len() + 1 {
         return None;
     }
 
@@ -5642,6 +6094,51 @@ fn tokenize_quoted(input: &str) -> Vec<String> {
     tokens
 }
 
+/// Applies the `key: value` pairs of an `id@{ ... }` block. Only `status`
+/// is understood; other keys are ignored.
+fn apply_node_metadata(graph: &mut Graph, id: &str, meta: &str) {
+    for entry in split_unquoted_commas(meta) {
+        let Some((key, value)) = entry.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|ch| ch == '"' || ch == '\'');
+        match key.trim() {
+            "status" => {
+                if let Some(status) = crate::ir::NodeStatus::parse(value) {
+                    graph.node_statuses.insert(id.to_string(), status);
+                }
+            }
+            "shape" => {
+                if let Some(shape) = crate::ir::NodeShape::from_v11_name(value) {
+                    graph.ensure_node(id, None, Some(shape));
+                }
+            }
+            "label" => graph.ensure_node(id, Some(value.to_string()), None),
+            _ => {}
+        }
+    }
+}
+
+/// Splits on commas that are not inside a `"..."` or `'...'` string.
+fn split_unquoted_commas(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (idx, ch) in input.char_indices() {
+        match (quote, ch) {
+            (Some(q), _) if ch == q => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, ',') => {
+                parts.push(&input[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
 fn parse_click_line(line: &str) -> Option<(String, crate::ir::NodeLink)> {
     let trimmed = line.trim();
     let lower = trimmed.to_ascii_lowercase();
@@ -5706,6 +6203,7 @@ fn parse_node_style(input: &str) -> crate::ir::NodeStyle {
             }
             "stroke-dasharray" => style.stroke_dasharray = Some(value.to_string()),
             "color" => style.text_color = Some(value.to_string()),
+            "opacity" => style.opacity = parse_opacity(value),
             _ => {}
         }
     }
@@ -5728,12 +6226,21 @@ fn parse_edge_style(input: &str) -> crate::ir::EdgeStyleOverride {
             }
             "stroke-dasharray" => style.dasharray = Some(value.to_string()),
             "color" => style.label_color = Some(value.to_string()),
+            "opacity" => style.opacity = parse_opacity(value),
+            "marker-mid" => {
+                style.midpoint_marker = Some(value.trim_end_matches(';').to_ascii_lowercase());
+            }
             _ => {}
         }
     }
     style
 }
 
+fn parse_opacity(value: &str) -> Option<f32> {
+    let opacity = value.trim_end_matches(';').trim().parse::<f32>().ok()?;
+    opacity.is_finite().then(|| opacity.clamp(0.0, 1.0))
+}
+
 fn parse_node_token(
     token: &str,
 ) -> (
@@ -5952,6 +6459,31 @@ mod tests {
     use crate::ir::DiagramKind;
     use std::path::Path;
 
+    #[test]
+    fn dangling_flowchart_arrow_reports_line_and_column() {
+        let err = parse_mermaid("flowchart LR\n A -->").unwrap_err();
+        let parse_err = err.downcast_ref::<ParseError>().expect("ParseError");
+        assert_eq!(parse_err.line, 2);
+        assert_eq!(parse_err.column, 4);
+        assert_eq!(parse_err.snippet, " A -->");
+        let message = err.to_string();
+        assert!(message.starts_with("line 2, column 4:"));
+        assert!(message.ends_with(" A -->"));
+
+        assert!(parse_mermaid("flowchart LR\n A --> B").is_ok());
+        assert!(parse_mermaid("flowchart LR\n A[\"x -->\"]").is_ok());
+    }
+
+    #[test]
+    fn dangling_arrow_location_uses_its_own_source_line() {
+        let err =
+            parse_mermaid("---\ntitle: t\n---\nflowchart LR\nA --> B\n%% note\nA -->").unwrap_err();
+        let parse_err = err.downcast_ref::<ParseError>().expect("ParseError");
+        assert_eq!(parse_err.line, 7);
+        assert_eq!(parse_err.column, 3);
+        assert_eq!(parse_err.snippet, "A -->");
+    }
+
     fn read_fixture(rel: &str) -> String {
         std::fs::read_to_string(
             Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -6122,6 +6654,62 @@ mod tests {
         assert_eq!(parsed.graph.edges[1].label.as_deref(), Some("ship it"));
     }
 
+    #[test]
+    fn parse_undirected_edge_with_mid_label() {
+        let parsed = parse_mermaid("flowchart LR\nA -- mid --- B").unwrap();
+        assert_eq!(parsed.graph.edges.len(), 1);
+        let edge = &parsed.graph.edges[0];
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("A", "B"));
+        assert_eq!(edge.label.as_deref(), Some("mid"));
+        assert_eq!(edge.style, crate::ir::EdgeStyle::Solid);
+        assert!(!edge.directed && !edge.arrow_end && !edge.arrow_start);
+        assert_eq!(parsed.graph.nodes.len(), 2);
+    }
+
+    #[test]
+    fn parse_thick_edge_with_mid_label() {
+        let parsed = parse_mermaid("flowchart LR\nA == mid ==> C").unwrap();
+        assert_eq!(parsed.graph.edges.len(), 1);
+        let edge = &parsed.graph.edges[0];
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("A", "C"));
+        assert_eq!(edge.label.as_deref(), Some("mid"));
+        assert_eq!(edge.style, crate::ir::EdgeStyle::Thick);
+        assert!(edge.directed && edge.arrow_end);
+        assert_eq!(parsed.graph.nodes.len(), 2);
+    }
+
+    #[test]
+    fn parse_edge_chain_with_mid_labels() {
+        let parsed = parse_mermaid("flowchart LR\nA --- B -- y --- C == z ==> D --> E").unwrap();
+        let edges: Vec<_> = parsed
+            .graph
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.from.as_str(),
+                    edge.to.as_str(),
+                    edge.label.as_deref(),
+                    edge.style,
+                )
+            })
+            .collect();
+        use crate::ir::EdgeStyle::{Solid, Thick};
+        assert_eq!(
+            edges,
+            vec![
+                ("A", "B", None, Solid),
+                ("B", "C", Some("y"), Solid),
+                ("C", "D", Some("z"), Thick),
+                ("D", "E", None, Solid),
+            ]
+        );
+        let parsed = parse_mermaid("flowchart LR\nA -->|high-risk| B --> C").unwrap();
+        assert_eq!(parsed.graph.edges.len(), 2);
+        assert_eq!(parsed.graph.edges[0].label.as_deref(), Some("high-risk"));
+        assert_eq!(parsed.graph.nodes.len(), 3);
+    }
+
     #[test]
     fn parse_compact_dotted_edge_label_without_spaces() {
         let input = "flowchart LR\nN01 -.audit.-> N16";
@@ -6188,6 +6776,43 @@ mod tests {
         assert!(parsed.graph.nodes.contains_key("C"));
     }
 
+    #[test]
+    fn parse_flowchart_v11_shape_metadata() {
+        let parsed = parse_mermaid(
+            "flowchart LR\nA@{ shape: doc, label: \"Report\" }\nB@{ shape: lightning-bolt }\nC@{ shape: cloudy-thing }\nA --> B",
+        )
+        .unwrap();
+        let nodes = &parsed.graph.nodes;
+        assert_eq!(nodes["A"].shape, crate::ir::NodeShape::Document);
+        assert_eq!(nodes["A"].label, "Report");
+        assert_eq!(nodes["B"].shape, crate::ir::NodeShape::Bolt);
+        assert_eq!(nodes["C"].shape, crate::ir::NodeShape::Rectangle);
+    }
+
+    #[test]
+    fn parse_flowchart_inline_shape_metadata_in_edges() {
+        let parsed = parse_mermaid(
+            "flowchart LR\nA@{ shape: doc } --> B@{ shape: bolt, label: \"Zap, now\" }:::hot\nC@{shape: doc}-->D",
+        )
+        .unwrap();
+        let nodes = &parsed.graph.nodes;
+        assert_eq!(parsed.graph.edges.len(), 2);
+        assert_eq!(nodes["A"].shape, crate::ir::NodeShape::Document);
+        assert_eq!(nodes["A"].label, "A");
+        assert_eq!(nodes["C"].shape, crate::ir::NodeShape::Document);
+        assert_eq!(nodes["B"].shape, crate::ir::NodeShape::Bolt);
+        assert_eq!(nodes["B"].label, "Zap, now");
+        assert!(parsed.graph.node_classes["B"].contains(&"hot".to_string()));
+    }
+
+    #[test]
+    fn parse_flowchart_metadata_label_keeps_quoted_commas() {
+        let parsed = parse_mermaid("flowchart LR\nA@{ label: \"Zap, now\", shape: doc }").unwrap();
+        let node = &parsed.graph.nodes["A"];
+        assert_eq!(node.label, "Zap, now");
+        assert_eq!(node.shape, crate::ir::NodeShape::Document);
+    }
+
     #[test]
     fn parse_multi_source_edges() {
         let input = "flowchart LR\nA & B --> C";
@@ -6267,6 +6892,52 @@ mod tests {
         assert!(label.contains("name"));
     }
 
+    #[test]
+    fn parse_class_stereotypes() {
+        let input = "classDiagram\nclass Shape {\n<<interface>>\n+area() float\n}\nclass Animal\n<<abstract>> Animal";
+        let parsed = parse_mermaid(input).unwrap();
+        assert_eq!(
+            parsed.graph.nodes["Shape"].label,
+            "«interface»\nShape\n---\n+area() : float"
+        );
+        assert_eq!(parsed.graph.nodes["Animal"].label, "«abstract»\nAnimal");
+    }
+
+    #[test]
+    fn parse_frontmatter_title_and_config_override() {
+        let input = "---\ntitle: Order flow\nconfig:\n  theme: default\n  flowchart:\n    nodeSpacing: 80\n    unknownKey: 1\n  mystery: true\n---\nflowchart LR\nA --> B";
+        let parsed = parse_mermaid(input).unwrap();
+        assert_eq!(parsed.graph.title.as_deref(), Some("Order flow"));
+        assert_eq!(parsed.graph.nodes.len(), 2);
+        assert_eq!(
+            parsed.config_override,
+            ConfigOverride {
+                theme: Some("default".to_string()),
+                node_spacing: Some(80.0),
+                ..ConfigOverride::default()
+            }
+        );
+
+        let plain = parse_mermaid("flowchart LR\nA --> B").unwrap();
+        assert_eq!(plain.graph.title, None);
+        assert_eq!(plain.config_override, ConfigOverride::default());
+    }
+
+    #[test]
+    fn parse_init_directive_theme_variables() {
+        let input = "%%{init: {'theme':'forest','themeVariables':{'primaryColor':'#f00','lineColor':'#0f0','fontFamily':'Mono'}}}%%\nflowchart LR\nA --> B";
+        let parsed = parse_mermaid(input).unwrap();
+        let config_override = &parsed.config_override;
+        assert_eq!(config_override.theme.as_deref(), Some("forest"));
+        assert_eq!(config_override.primary_color.as_deref(), Some("#f00"));
+        assert_eq!(config_override.line_color.as_deref(), Some("#0f0"));
+        assert_eq!(config_override.font_family.as_deref(), Some("Mono"));
+
+        let malformed = parse_mermaid("%%{init: {'theme': }}%%\nflowchart LR\nA --> B").unwrap();
+        assert_eq!(malformed.graph.nodes.len(), 2);
+        assert_eq!(malformed.config_override, ConfigOverride::default());
+    }
+
     #[test]
     fn parse_class_relation_multiplicity() {
         let input = "classDiagram\nClass01 \"1\" *-- \"many\" Class02 : contains";
@@ -6431,6 +7102,18 @@ mod tests {
         assert_eq!(parsed.graph.edges.len(), 1);
     }
 
+    #[test]
+    fn parse_flowchart_node_status_metadata() {
+        let parsed =
+            parse_mermaid("flowchart LR\nA --> B\nA@{ status: error }\nB@{ status: \"ok\" }\nC@{ status: unknown }")
+                .unwrap();
+        let statuses = &parsed.graph.node_statuses;
+        assert_eq!(statuses.get("A"), Some(&crate::ir::NodeStatus::Error));
+        assert_eq!(statuses.get("B"), Some(&crate::ir::NodeStatus::Ok));
+        assert!(!statuses.contains_key("C"));
+        assert!(parsed.graph.nodes.contains_key("C"));
+    }
+
     #[test]
     fn parse_kanban_basic() {
         let input = read_fixture("kanban/basic.mmd");
@@ -6477,6 +7160,24 @@ mod tests {
         assert_eq!(xychart.series.len(), 1);
     }
 
+    #[test]
+    fn parse_state_history_pseudostates() {
+        let input = "stateDiagram-v2\nstate Active {\n[*] --> Idle\nIdle --> [H]\nIdle --> [H*]\n}";
+        let parsed = parse_mermaid(input).unwrap();
+        let shallow = parsed.graph.nodes.get("__history_Active__").unwrap();
+        assert_eq!(shallow.label, "H");
+        assert_eq!(shallow.shape, crate::ir::NodeShape::Circle);
+        let deep = parsed.graph.nodes.get("__deep_history_Active__").unwrap();
+        assert_eq!(deep.label, "H*");
+        let active = parsed
+            .graph
+            .subgraphs
+            .iter()
+            .find(|sub| sub.id.as_deref() == Some("Active"))
+            .unwrap();
+        assert!(active.nodes.contains(&shallow.id));
+    }
+
     #[test]
     fn parse_state_diagram_basic() {
         let input = read_fixture("state/basic.mmd");
@@ -6548,6 +7249,67 @@ mod tests {
         assert_eq!(parsed.graph.edges[1].style, crate::ir::EdgeStyle::Dotted);
     }
 
+    #[test]
+    fn parse_sequence_message_arrowheads() {
+        let input =
+            "sequenceDiagram\nA->>B: sync\nA-)B: async\nA->B: plain\nA--xB: lost\nB-->>A: reply";
+        let parsed = parse_mermaid(input).unwrap();
+        let edges = &parsed.graph.edges;
+        assert_eq!(edges.len(), 5);
+        assert!(edges[0].arrow_end && edges[0].arrow_end_kind.is_none());
+        assert!(edges[1].arrow_end);
+        assert_eq!(
+            edges[1].arrow_end_kind,
+            Some(crate::ir::EdgeArrowhead::SequenceAsync)
+        );
+        assert!(!edges[2].arrow_end && edges[2].end_decoration.is_none());
+        assert!(!edges[3].arrow_end);
+        assert_eq!(edges[3].style, crate::ir::EdgeStyle::Dotted);
+        assert_eq!(
+            edges[3].end_decoration,
+            Some(crate::ir::EdgeDecoration::Cross)
+        );
+        assert!(edges[4].arrow_end);
+        assert_eq!(edges[4].from, "B");
+    }
+
+    #[test]
+    fn parse_sequence_message_keeps_hyphenated_ids() {
+        let parsed = parse_mermaid("sequenceDiagram\nweb-xhr->>db: load").unwrap();
+        let edge = &parsed.graph.edges[0];
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("web-xhr", "db"));
+        assert!(edge.arrow_end);
+
+        let input = "sequenceDiagram\nparticipant api-x\napi-x->>db: query\ndb-)api-x: done";
+        let parsed = parse_mermaid(input).unwrap();
+        let edges = &parsed.graph.edges;
+        assert_eq!(
+            (edges[0].from.as_str(), edges[0].to.as_str()),
+            ("api-x", "db")
+        );
+        assert_eq!(
+            (edges[1].from.as_str(), edges[1].to.as_str()),
+            ("db", "api-x")
+        );
+        assert_eq!(
+            edges[1].arrow_end_kind,
+            Some(crate::ir::EdgeArrowhead::SequenceAsync)
+        );
+        assert!(!parsed.graph.nodes.contains_key("api"));
+
+        let parsed = parse_mermaid("sequenceDiagram\nweb-xhr-)db: hi").unwrap();
+        let edge = &parsed.graph.edges[0];
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("web-xhr", "db"));
+        assert_eq!(
+            edge.arrow_end_kind,
+            Some(crate::ir::EdgeArrowhead::SequenceAsync)
+        );
+
+        let parsed = parse_mermaid("sequenceDiagram\nAlice-xBob: drop").unwrap();
+        let edge = &parsed.graph.edges[0];
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("Alice", "Bob"));
+    }
+
     #[test]
     fn parse_sequence_database_participant() {
         let input = "sequenceDiagram\ndatabase DB\nDB->>DB: ping";
@@ -6556,6 +7318,16 @@ mod tests {
         assert_eq!(node.shape, crate::ir::NodeShape::Cylinder);
     }
 
+    #[test]
+    fn parse_sequence_autonumber_start_and_step() {
+        let parsed = parse_mermaid("sequenceDiagram\nautonumber 10 5\nA->>B: ping").unwrap();
+        assert_eq!(parsed.graph.sequence_autonumber, Some(10));
+        assert_eq!(parsed.graph.sequence_autonumber_step, 5);
+        let parsed = parse_mermaid("sequenceDiagram\nautonumber 5\nA->>B: ping").unwrap();
+        assert_eq!(parsed.graph.sequence_autonumber, Some(5));
+        assert_eq!(parsed.graph.sequence_autonumber_step, 1);
+    }
+
     #[test]
     fn parse_sequence_autonumber_off() {
         let input = "sequenceDiagram\nautonumber off\nA->>B: ping";
@@ -6690,6 +7462,17 @@ mod tests {
         assert!(link.target.is_none());
     }
 
+    #[test]
+    fn parses_click_directive_with_tooltip_and_target() {
+        let input =
+            "flowchart LR\nA-->B\nclick B href \"https://example.com\" \"Open docs\" _blank";
+        let parsed = parse_mermaid(input).unwrap();
+        let link = parsed.graph.node_links.get("B").unwrap();
+        assert_eq!(link.url, "https://example.com");
+        assert_eq!(link.title.as_deref(), Some("Open docs"));
+        assert_eq!(link.target.as_deref(), Some("_blank"));
+    }
+
     #[test]
     fn strips_inline_comments() {
         let input = "flowchart LR\nA-->B %% comment\nB-->C";
@@ -1,20 +1,23 @@
-use crate::config::LayoutConfig;
-#[cfg(feature = "png")]
-use crate::config::RenderConfig;
+use crate::config::{ActivationZ, FrameSpec, LabelAlign, LabelShape, LayoutConfig, RenderConfig};
 use crate::layout::label_placement::{
     edge_endpoint_label_position, edge_label_padding, endpoint_label_padding,
 };
 use crate::layout::{
     C4BoundaryLayout, C4Layout, C4RelLayout, C4ShapeLayout, DiagramData, ErrorLayout,
     GitGraphLayout, JourneyLayout, Layout, PieData, SankeyLayout, TextBlock,
+    edge_label_anchor_from_points,
 };
 use crate::text_metrics;
 use crate::theme::{Theme, adjust_color, parse_color_to_hsl};
 use anyhow::Result;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
 use std::path::Path;
 
+const DEBUG_COLOR: &str = "#D6336C";
+
 fn fit_dimensions_to_preferred_ratio(
     width: f32,
     height: f32,
@@ -44,15 +47,283 @@ fn edge_dom_id(edge_idx: usize) -> String {
     format!("edge-{edge_idx}")
 }
 
+/// `text-rendering` attribute for the root `<svg>`; every text element
+/// inherits it from there.
+fn text_rendering_attr(render_config: &RenderConfig) -> String {
+    render_config
+        .text_rendering
+        .map(|hint| format!(" text-rendering=\"{}\"", hint.as_str()))
+        .unwrap_or_default()
+}
+
+/// Prefix for the ids a document defines for its own references (the
+/// description, viewBox and page clips), so several SVGs inlined into one
+/// page don't collide. Uses `render_config.id_prefix` when set, otherwise a
+/// hash of the diagram's content.
+fn document_id_prefix(layout: &Layout, render_config: &RenderConfig) -> String {
+    if let Some(prefix) = &render_config.id_prefix {
+        return escape_xml(prefix);
+    }
+    let mut hasher = DefaultHasher::new();
+    layout.kind.display_name().hash(&mut hasher);
+    layout.accessibility.title.hash(&mut hasher);
+    layout.accessibility.description.hash(&mut hasher);
+    layout.width.to_bits().hash(&mut hasher);
+    layout.height.to_bits().hash(&mut hasher);
+    for node in layout.nodes.values() {
+        node.id.hash(&mut hasher);
+        node.label.lines.hash(&mut hasher);
+        node.x.to_bits().hash(&mut hasher);
+        node.y.to_bits().hash(&mut hasher);
+    }
+    for edge in &layout.edges {
+        edge.from.hash(&mut hasher);
+        edge.to.hash(&mut hasher);
+        if let Some(label) = &edge.label {
+            label.lines.hash(&mut hasher);
+        }
+    }
+    format!("mmd-{:08x}", hasher.finish() as u32)
+}
+
 pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> String {
+    render_svg_with_config(layout, theme, config, &RenderConfig::default())
+}
+
+/// Render a laid-out diagram to SVG, applying output options from `render_config`.
+pub fn render_svg_with_config(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+) -> String {
+    render_svg_with_source(layout, theme, config, render_config, None)
+}
+
+/// [`render_svg_with_config`] for a layout parsed from `source`, which is
+/// embedded as a comment when [`RenderConfig::embed_source`] is set.
+pub(crate) fn render_svg_with_source(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+    source: Option<&str>,
+) -> String {
     let mut svg = String::new();
+    write_svg_document(layout, theme, config, render_config, source, &mut svg);
+    if let Some(axis) = render_config.mirror {
+        svg = mirror_svg(svg, axis);
+    }
+    if render_config.clip_to_viewbox {
+        svg = clip_svg_to_viewbox(svg, &document_id_prefix(layout, render_config));
+    }
+    #[cfg(feature = "serde")]
+    if render_config.embed_layout_json {
+        svg = embed_layout_metadata(svg, layout, &document_id_prefix(layout, render_config));
+    }
+    svg
+}
+
+/// Render a laid-out diagram straight into `out` instead of building the
+/// whole document in memory. The `mirror`, `clip_to_viewbox` and
+/// `embed_layout_json` options rewrite the finished document, so they still
+/// render to a `String` first.
+pub fn render_svg_to_writer<W: Write>(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+    out: &mut W,
+) -> std::io::Result<()> {
+    render_svg_to_writer_with_source(layout, theme, config, render_config, None, out)
+}
+
+/// [`render_svg_to_writer`] for a layout parsed from `source`; see
+/// [`render_svg_with_source`].
+pub(crate) fn render_svg_to_writer_with_source<W: Write>(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+    source: Option<&str>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    if render_config.mirror.is_some()
+        || render_config.clip_to_viewbox
+        || render_config.embed_layout_json
+    {
+        let svg = render_svg_with_source(layout, theme, config, render_config, source);
+        return out.write_all(svg.as_bytes());
+    }
+    let mut sink = WriterSink { out, error: None };
+    write_svg_document(layout, theme, config, render_config, source, &mut sink);
+    match sink.error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+const CSS_NODE_FILL: &str = "--mmd-node-fill";
+const CSS_LINE_COLOR: &str = "--mmd-line-color";
+
+/// Copy of `theme` whose node fill and line color go through CSS custom
+/// properties, keeping the original colors as `var()` fallbacks.
+fn css_variable_theme(theme: &Theme) -> Theme {
+    let mut themed = theme.clone();
+    themed.primary_color = format!("var({CSS_NODE_FILL}, {})", theme.primary_color);
+    themed.line_color = format!("var({CSS_LINE_COLOR}, {})", theme.line_color);
+    themed
+}
+
+/// Default values for the custom properties, scoped to this document's root
+/// by id. `:where()` keeps the rule at zero specificity, so a host page
+/// overrides it with any selector, e.g. `.dark svg { --mmd-node-fill: #222; }`.
+fn css_variables_style_block(theme: &Theme, id_prefix: &str) -> String {
+    format!(
+        "<style>:where(#{id_prefix}) {{ {CSS_NODE_FILL}: {}; {CSS_LINE_COLOR}: {}; }}</style>",
+        escape_xml(&theme.primary_color),
+        escape_xml(&theme.line_color)
+    )
+}
+
+/// Destination for SVG markup: a `String` or a streaming writer.
+trait SvgSink {
+    fn push_str(&mut self, chunk: &str);
+}
+
+impl SvgSink for String {
+    fn push_str(&mut self, chunk: &str) {
+        String::push_str(self, chunk);
+    }
+}
+
+/// Forwards markup to a writer, keeping the first I/O error and dropping
+/// everything after it.
+struct WriterSink<'a, W: Write> {
+    out: &'a mut W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> SvgSink for WriterSink<'_, W> {
+    fn push_str(&mut self, chunk: &str) {
+        if self.error.is_none()
+            && let Err(err) = self.out.write_all(chunk.as_bytes())
+        {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Splits a rendered document into its opening `<svg>` tag, the content
+/// before `</svg>`, and the four viewBox fields.
+fn svg_root_parts(svg: &str) -> Option<(&str, &str, [&str; 4])> {
+    let open_end = svg.find('>')? + 1;
+    let close_start = svg.rfind("</svg>")?;
+    let open_tag = &svg[..open_end];
+    let (_, rest) = open_tag.split_once("viewBox=\"")?;
+    let (viewbox, _) = rest.split_once('"')?;
+    let parts: Vec<&str> = viewbox.split_whitespace().collect();
+    let viewbox: [&str; 4] = parts.try_into().ok()?;
+    Some((open_tag, &svg[open_end..close_start], viewbox))
+}
+
+/// Splits the leading source comment, `<title>` and `<desc>` off a document
+/// body, returning `(preamble, rest)`.
+fn split_svg_preamble(body: &str) -> (&str, &str) {
+    let mut rest = body;
+    for (open, close) in [
+        ("<!--", "-->"),
+        ("<title>", "</title>"),
+        ("<desc ", "</desc>"),
+    ] {
+        if rest.starts_with(open)
+            && let Some(end) = rest.find(close)
+        {
+            rest = &rest[end + close.len()..];
+        }
+    }
+    body.split_at(body.len() - rest.len())
+}
+
+/// Wrap the drawing inside the root `<svg>` in a group clipped to its viewBox;
+/// `<title>` and `<desc>` stay direct children of the root.
+fn clip_svg_to_viewbox(svg: String, id_prefix: &str) -> String {
+    let Some((open_tag, body, [x, y, width, height])) = svg_root_parts(&svg) else {
+        return svg;
+    };
+    let (preamble, body) = split_svg_preamble(body);
+    format!(
+        "{open_tag}{preamble}<clipPath id=\"{id_prefix}-viewbox-clip\"><rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\"/></clipPath><g clip-path=\"url(#{id_prefix}-viewbox-clip)\">{body}</g></svg>"
+    )
+}
+
+/// Wrap the drawing inside the root `<svg>` in a group reflected across the
+/// middle of the viewBox, so the image stays in place but reads backwards.
+/// `<title>` and `<desc>` stay direct children of the root.
+fn mirror_svg(svg: String, axis: crate::config::Axis) -> String {
+    let Some((open_tag, body, viewbox)) = svg_root_parts(&svg) else {
+        return svg;
+    };
+    let (preamble, body) = split_svg_preamble(body);
+    let values: Option<Vec<f32>> = viewbox.iter().map(|v| v.parse().ok()).collect();
+    let Some(&[x, y, width, height]) = values.as_deref() else {
+        return svg;
+    };
+    let transform = match axis {
+        crate::config::Axis::Horizontal => {
+            format!("translate({:.2} 0) scale(-1 1)", 2.0 * x + width)
+        }
+        crate::config::Axis::Vertical => {
+            format!("translate(0 {:.2}) scale(1 -1)", 2.0 * y + height)
+        }
+    };
+    format!("{open_tag}{preamble}<g class=\"mirror\" transform=\"{transform}\">{body}</g></svg>")
+}
+
+fn write_svg_document<S: SvgSink>(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+    source: Option<&str>,
+    svg: &mut S,
+) {
+    let id_prefix = document_id_prefix(layout, render_config);
+    let css_style = render_config
+        .css_variables
+        .then(|| css_variables_style_block(theme, &id_prefix));
+    let css_theme;
+    let theme = if render_config.css_variables {
+        css_theme = css_variable_theme(theme);
+        &css_theme
+    } else {
+        theme
+    };
     let state_font_size = if layout.kind == crate::ir::DiagramKind::State {
         theme.font_size * 0.85
     } else {
         theme.font_size
     };
+    // Only kinds whose drawing is fully described by nodes, edges and
+    // subgraphs (or sequence data) can be fit by `tight_viewbox`.
+    let tight_fits = matches!(
+        layout.kind,
+        crate::ir::DiagramKind::Flowchart
+            | crate::ir::DiagramKind::Class
+            | crate::ir::DiagramKind::State
+            | crate::ir::DiagramKind::Er
+            | crate::ir::DiagramKind::Sequence
+    ) && matches!(
+        layout.diagram,
+        DiagramData::Graph { .. } | DiagramData::Sequence(_)
+    );
     let (width, height, viewbox_x, viewbox_y, viewbox_width, viewbox_height) =
-        if let DiagramData::Error(error) = &layout.diagram {
+        if render_config.tight && tight_fits {
+            let (x, y, width, height) = layout.tight_viewbox();
+            let width = width.max(1.0);
+            let height = height.max(1.0);
+            (width, height, x, y, width, height)
+        } else if let DiagramData::Error(error) = &layout.diagram {
             (
                 error.render_width,
                 error.render_height,
@@ -123,6 +394,73 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             let height = layout.height.max(1.0);
             (width, height, 0.0, 0.0, width, height)
         };
+    let edge_legend = config
+        .flowchart
+        .edge_legend
+        .as_deref()
+        .filter(|entries| !entries.is_empty() && layout.kind == crate::ir::DiagramKind::Flowchart)
+        .map(|entries| {
+            let size = edge_legend_size(entries, theme);
+            let x = viewbox_x + (viewbox_width - size.0 - EDGE_LEGEND_MARGIN).max(0.0);
+            let y = viewbox_y + viewbox_height;
+            (entries, x, y)
+        });
+    // Reserve the legend's space before the size limits so `min_size` and
+    // `max_size` apply to the canvas as drawn, legend included.
+    let (width, height, viewbox_width, viewbox_height) = match edge_legend {
+        Some((entries, x, _)) => {
+            let (legend_width, legend_height) = edge_legend_size(entries, theme);
+            let grown_width = viewbox_width.max(x - viewbox_x + legend_width + EDGE_LEGEND_MARGIN);
+            let grown_height = viewbox_height + legend_height + EDGE_LEGEND_MARGIN;
+            (
+                width * grown_width / viewbox_width,
+                height * grown_height / viewbox_height,
+                grown_width,
+                grown_height,
+            )
+        }
+        None => (width, height, viewbox_width, viewbox_height),
+    };
+    let frame = render_config
+        .frame
+        .as_ref()
+        .map(|spec| (spec, frame_title_height(spec, theme)));
+    let (width, height, viewbox_x, viewbox_y, viewbox_width, viewbox_height) = match frame {
+        Some((spec, title_height)) => {
+            let inset = spec.padding.max(0.0) + spec.stroke_width;
+            let grown_width = viewbox_width + inset * 2.0;
+            let grown_height = viewbox_height + inset * 2.0 + title_height;
+            (
+                width * grown_width / viewbox_width,
+                height * grown_height / viewbox_height,
+                viewbox_x - inset,
+                viewbox_y - inset - title_height,
+                grown_width,
+                grown_height,
+            )
+        }
+        None => (
+            width,
+            height,
+            viewbox_x,
+            viewbox_y,
+            viewbox_width,
+            viewbox_height,
+        ),
+    };
+    let frame_rect = (viewbox_x, viewbox_y, viewbox_width, viewbox_height);
+    let (width, height, viewbox_x, viewbox_y, mut viewbox_width, mut viewbox_height) =
+        apply_size_limits(
+            (
+                width,
+                height,
+                viewbox_x,
+                viewbox_y,
+                viewbox_width,
+                viewbox_height,
+            ),
+            render_config,
+        );
     let seq_data = if let DiagramData::Sequence(s) = &layout.diagram {
         Some(s)
     } else {
@@ -142,8 +480,16 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
     let preferred_ratio = config
         .preferred_aspect_ratio
         .filter(|ratio| ratio.is_finite() && *ratio > 0.0);
-    let (target_width, target_height) =
+    let (mut target_width, mut target_height) =
         fit_dimensions_to_preferred_ratio(width, height, preferred_ratio);
+    if render_config.integer_dimensions {
+        let rounded_width = target_width.ceil();
+        let rounded_height = target_height.ceil();
+        viewbox_width *= rounded_width / target_width;
+        viewbox_height *= rounded_height / target_height;
+        target_width = rounded_width;
+        target_height = rounded_height;
+    }
 
     let mut width_attr = target_width.to_string();
     let mut height_attr = target_height.to_string();
@@ -190,8 +536,20 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
     } else if !preferred_ratio_style.is_empty() {
         style_attr = format!(" style=\"{preferred_ratio_style}\"");
     }
+    let accessibility = &layout.accessibility;
+    let describedby = if accessibility.description.is_some() {
+        format!(" aria-describedby=\"{id_prefix}-desc\"")
+    } else {
+        String::new()
+    };
+    let text_rendering_attr = text_rendering_attr(render_config);
+    let root_id = if css_style.is_some() {
+        format!(" id=\"{id_prefix}\"")
+    } else {
+        String::new()
+    };
     svg.push_str(&format!(
-        "<svg xmlns=\"http://www.w3.org/2000/svg\"{} width=\"{width_attr}\"{} viewBox=\"{viewbox_x} {viewbox_y} {viewbox_width} {viewbox_height}\"{style_attr}>",
+        "<svg xmlns=\"http://www.w3.org/2000/svg\"{root_id}{} width=\"{width_attr}\"{} viewBox=\"{viewbox_x} {viewbox_y} {viewbox_width} {viewbox_height}\"{style_attr}{text_rendering_attr} role=\"img\"{describedby}>",
         if has_links {
             " xmlns:xlink=\"http://www.w3.org/1999/xlink\""
         } else {
@@ -203,20 +561,53 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             format!(" height=\"{height_attr}\"")
         }
     ));
+    if render_config.embed_source
+        && let Some(source) = source
+    {
+        svg.push_str(&source_comment(source));
+    }
+
+    let title = accessibility
+        .title
+        .as_deref()
+        .unwrap_or(layout.kind.display_name());
+    svg.push_str(&format!("<title>{}</title>", escape_xml(title)));
+    if let Some(description) = &accessibility.description {
+        svg.push_str(&format!(
+            "<desc id=\"{id_prefix}-desc\">{}</desc>",
+            escape_xml(description)
+        ));
+    }
+
+    if let Some(style) = &css_style {
+        svg.push_str(style);
+    }
 
     if matches!(layout.diagram, DiagramData::Error(_)) {
         svg.push_str(&error_style_block(theme));
     }
 
-    svg.push_str(&format!(
-        "<rect x=\"{viewbox_x}\" y=\"{viewbox_y}\" width=\"{viewbox_width}\" height=\"{viewbox_height}\" fill=\"{}\"/>",
-        theme.background
-    ));
+    // Only the canvas takes a solid background color; node and slice fills
+    // keep deriving from the theme's.
+    let canvas_fill = render_config.background_color(theme).map(escape_xml);
+    if let Some((spec, title_height)) = frame {
+        svg.push_str(&frame_svg(
+            spec,
+            title_height,
+            frame_rect,
+            theme,
+            canvas_fill.as_deref(),
+        ));
+    } else if let Some(fill) = canvas_fill {
+        svg.push_str(&format!(
+            "<rect x=\"{viewbox_x}\" y=\"{viewbox_y}\" width=\"{viewbox_width}\" height=\"{viewbox_height}\" fill=\"{fill}\"/>"
+        ));
+    }
 
     if let DiagramData::C4(ref c4) = layout.diagram {
         svg.push_str(&render_c4(c4, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     let mut colors = Vec::new();
@@ -233,126 +624,98 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
         color_ids.insert(color.clone(), idx);
     }
 
+    let mut marker_variants: Vec<(usize, f32)> = (0..colors.len()).map(|idx| (idx, 1.0)).collect();
+    for edge in &layout.edges {
+        let scale = marker_scale(edge, layout.kind);
+        let idx = edge_marker_color_idx(edge, &color_ids);
+        if scale > 1.0 && !marker_variants.contains(&(idx, scale)) {
+            marker_variants.push((idx, scale));
+        }
+    }
+
     svg.push_str("<defs>");
-    for color in &colors {
-        let idx = color_ids.get(color).copied().unwrap_or(0);
-        svg.push_str(&format!(
-            "<marker id=\"arrow-{idx}\" viewBox=\"0 0 10 10\" refX=\"5\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-            color, color
-        ));
-        svg.push_str(&format!(
-            "<marker id=\"arrow-start-{idx}\" viewBox=\"0 0 10 10\" refX=\"4.5\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M 0 5 L 10 10 L 10 0 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-            color, color
+    for &(idx, scale) in &marker_variants {
+        svg.push_str(&arrow_marker_defs(
+            &marker_id(idx, scale),
+            &colors[idx],
+            scale,
+            is_sequence,
+            is_state,
+            is_class,
         ));
-        if is_sequence {
-            svg.push_str(&format!(
-                "<marker id=\"arrow-seq-{idx}\" viewBox=\"-1 0 12 10\" refX=\"7.9\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"12\" markerHeight=\"12\" orient=\"auto-start-reverse\"><path d=\"M -1 0 L 10 5 L 0 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color,
-                color
-            ));
-            svg.push_str(&format!(
-                "<marker id=\"arrow-start-seq-{idx}\" viewBox=\"-1 0 12 10\" refX=\"2.1\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"12\" markerHeight=\"12\" orient=\"auto\"><path d=\"M 11 0 L 0 5 L 11 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color,
-                color
-            ));
-        }
-        if is_state {
-            svg.push_str(&format!(
-                "<marker id=\"arrow-state-{idx}\" viewBox=\"0 0 20 14\" refX=\"19\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"20\" markerHeight=\"14\" orient=\"auto\"><path d=\"M 19 7 L 9 13 L 14 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color, color
-            ));
-        }
-        if is_class {
-            svg.push_str(&format!(
-                "<marker id=\"arrow-class-open-{idx}\" viewBox=\"0 0 20 14\" refX=\"1\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"20\" markerHeight=\"14\" orient=\"auto\"><path d=\"M 1 7 L 18 13 V 1 Z\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color
-            ));
-            svg.push_str(&format!(
-                "<marker id=\"arrow-class-open-start-{idx}\" viewBox=\"0 0 20 14\" refX=\"18\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"20\" markerHeight=\"14\" orient=\"auto\"><path d=\"M 1 7 L 18 13 V 1 Z\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color
-            ));
-            svg.push_str(&format!(
-                "<marker id=\"arrow-class-dep-{idx}\" viewBox=\"0 0 20 14\" refX=\"13\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"20\" markerHeight=\"14\" orient=\"auto\"><path d=\"M 18 7 L 9 13 L 14 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color, color
-            ));
-            svg.push_str(&format!(
-                "<marker id=\"arrow-class-dep-start-{idx}\" viewBox=\"0 0 20 14\" refX=\"6\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"20\" markerHeight=\"14\" orient=\"auto\"><path d=\"M 5 7 L 9 13 L 1 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
-                color, color
-            ));
-        }
     }
     svg.push_str("</defs>");
 
     if let DiagramData::Error(ref error) = layout.diagram {
         svg.push_str(&render_error(error, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Sankey(ref sankey) = layout.diagram {
         svg.push_str(&render_sankey(sankey, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if layout.kind == crate::ir::DiagramKind::Architecture {
         svg.push_str(&render_architecture(layout, theme, config, &color_ids));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if layout.kind == crate::ir::DiagramKind::Radar {
         svg.push_str(&render_radar(layout, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if layout.kind == crate::ir::DiagramKind::Requirement {
         svg.push_str(&render_requirement(layout, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Pie(ref pie) = layout.diagram {
         svg.push_str(&render_pie(pie, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Quadrant(ref quadrant) = layout.diagram {
         svg.push_str(&render_quadrant(quadrant, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Gantt(ref gantt) = layout.diagram {
         svg.push_str(&render_gantt(gantt, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::XYChart(ref xychart) = layout.diagram {
         svg.push_str(&render_xychart(xychart, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Timeline(ref timeline) = layout.diagram {
         svg.push_str(&render_timeline(timeline, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::Journey(ref journey) = layout.diagram {
         svg.push_str(&render_journey(journey, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     if let DiagramData::GitGraph(ref gitgraph) = layout.diagram {
         svg.push_str(&render_gitgraph(gitgraph, theme, config));
         svg.push_str("</svg>");
-        return svg;
+        return;
     }
 
     for subgraph in &layout.subgraphs {
@@ -502,7 +865,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
     }
 
     let overlay_flowchart = layout.kind == crate::ir::DiagramKind::Flowchart;
-    let mut overlay_arrows: Vec<(bool, (f32, f32), f32, String, f32)> = Vec::new();
+    let mut overlay_arrows: Vec<(bool, (f32, f32), f32, String, f32, Option<String>)> = Vec::new();
 
     if let Some(seq) = seq_data {
         for seq_box in &seq.boxes {
@@ -596,12 +959,13 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
         ));
     }
 
+    let mut activations_svg = String::new();
     for activation in seq_data
         .map(|s| s.activations.as_slice())
         .unwrap_or_default()
     {
-        svg.push_str(&format!(
-            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
+        activations_svg.push_str(&format!(
+            "<rect class=\"activation\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
             activation.x,
             activation.y,
             activation.width,
@@ -610,6 +974,10 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             theme.sequence_activation_border
         ));
     }
+    let activations_above_notes = config.sequence.activation_z == ActivationZ::AboveNotes;
+    if !activations_above_notes {
+        svg.push_str(&activations_svg);
+    }
 
     for note in seq_data.map(|s| s.notes.as_slice()).unwrap_or_default() {
         let fill = theme.sequence_note_fill.as_str();
@@ -641,6 +1009,9 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             Some(theme.primary_text_color.as_str()),
         ));
     }
+    if activations_above_notes {
+        svg.push_str(&activations_svg);
+    }
 
     if let DiagramData::Graph { state_notes } = &layout.diagram {
         for note in state_notes {
@@ -682,6 +1053,10 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             let d = points_to_path(&edge.points);
             let mut stroke = theme.line_color.clone();
             let edge_id = edge_dom_id(edge_idx);
+            let edge_group = edge_group_open(&edge_id, edge, render_config, "edge");
+            if let Some(open) = edge_group.as_deref() {
+                svg.push_str(open);
+            }
             if let Some(color) = &edge.override_style.stroke {
                 stroke = color.clone();
             }
@@ -689,11 +1064,16 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             let edge_label_stroke = theme.primary_border_color.as_str();
             let (center_pad_x, center_pad_y) = edge_label_padding(layout.kind, config);
             let (endpoint_pad_x, endpoint_pad_y) = endpoint_label_padding(layout.kind);
-            let marker_id = color_ids.get(&stroke).copied().unwrap_or(0);
-            let marker_end = if edge.arrow_end {
-                format!("marker-end=\"url(#arrow-seq-{marker_id})\"")
-            } else {
-                String::new()
+            let marker_id = marker_id(
+                color_ids.get(&stroke).copied().unwrap_or(0),
+                marker_scale(edge, layout.kind),
+            );
+            let marker_end = match (edge.arrow_end, edge.arrow_end_kind) {
+                (true, Some(crate::ir::EdgeArrowhead::SequenceAsync)) => {
+                    format!("marker-end=\"url(#arrow-seq-open-{marker_id})\"")
+                }
+                (true, _) => format!("marker-end=\"url(#arrow-seq-{marker_id})\""),
+                (false, _) => String::new(),
             };
             let marker_start = if edge.arrow_start {
                 format!("marker-start=\"url(#arrow-start-seq-{marker_id})\"")
@@ -708,7 +1088,10 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if let Some(dash_override) = &edge.override_style.dasharray {
                 dash = format!("stroke-dasharray=\"{}\"", dash_override);
             }
-            let stroke_width = edge.override_style.stroke_width.unwrap_or(1.5);
+            let stroke_width = edge
+                .override_style
+                .stroke_width
+                .unwrap_or(base_edge_width(layout.kind));
             svg.push_str(&format!(
                 "<path id=\"{edge_id}\" class=\"edgePath\" data-edge-id=\"{edge_id}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" {} {} {} stroke-linecap=\"round\" stroke-linejoin=\"round\" />",
                 d, stroke, stroke_width, marker_end, marker_start, dash
@@ -901,6 +1284,9 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 ));
                 svg.push_str("</g>");
             }
+            if edge_group.is_some() {
+                svg.push_str("</g>");
+            }
         }
 
         for number in seq_data.map(|s| s.numbers.as_slice()).unwrap_or_default() {
@@ -924,28 +1310,37 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             ));
         }
     } else {
-        let base_edge_width = match layout.kind {
-            crate::ir::DiagramKind::Class
-            | crate::ir::DiagramKind::State
-            | crate::ir::DiagramKind::Er => 1.0,
-            _ => 2.0,
-        };
+        let base_edge_width = base_edge_width(layout.kind);
+        let curve_tension = config.flowchart.routing.curve_tension;
         for (edge_idx, edge) in layout.edges.iter().enumerate() {
-            let d = points_to_path(&edge.points);
+            let (d, start_angle, end_angle) = match config.flowchart.edge_curve {
+                crate::config::EdgeCurve::Basis => basis_path_with_angles(&edge.points),
+                crate::config::EdgeCurve::Orthogonal if curve_tension < 1.0 => (
+                    points_to_curve_path(&edge.points, curve_tension),
+                    edge_endpoint_angle(&edge.points, true),
+                    edge_endpoint_angle(&edge.points, false),
+                ),
+                _ => (
+                    points_to_path(&edge.points),
+                    edge_endpoint_angle(&edge.points, true),
+                    edge_endpoint_angle(&edge.points, false),
+                ),
+            };
             let mut stroke = theme.line_color.clone();
             let edge_id = edge_dom_id(edge_idx);
-            let (mut dash, mut stroke_width) = match edge.style {
-                crate::ir::EdgeStyle::Solid => (String::new(), base_edge_width),
-                crate::ir::EdgeStyle::Dotted => {
-                    ("stroke-dasharray=\"2\"".to_string(), base_edge_width)
-                }
-                crate::ir::EdgeStyle::Thick => (String::new(), 3.5),
-            };
+            let edge_group = edge_group_open(&edge_id, edge, render_config, "edge");
+            if let Some(open) = edge_group.as_deref() {
+                svg.push_str(open);
+            }
+            let (mut dash, mut stroke_width) = edge_style_stroke(edge.style, base_edge_width);
 
             if let Some(color) = &edge.override_style.stroke {
                 stroke = color.clone();
             }
-            let marker_id = color_ids.get(&stroke).copied().unwrap_or(0);
+            let marker_id = marker_id(
+                color_ids.get(&stroke).copied().unwrap_or(0),
+                marker_scale(edge, layout.kind),
+            );
             let marker_end = if edge.arrow_end && !overlay_flowchart {
                 match layout.kind {
                     crate::ir::DiagramKind::State => {
@@ -958,7 +1353,9 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                         Some(crate::ir::EdgeArrowhead::ClassDependency) => {
                             format!("marker-end=\"url(#arrow-class-dep-{marker_id})\"")
                         }
-                        None => format!("marker-end=\"url(#arrow-{marker_id})\""),
+                        Some(crate::ir::EdgeArrowhead::SequenceAsync) | None => {
+                            format!("marker-end=\"url(#arrow-{marker_id})\"")
+                        }
                     },
                     _ => format!("marker-end=\"url(#arrow-{marker_id})\""),
                 }
@@ -977,7 +1374,9 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                         Some(crate::ir::EdgeArrowhead::ClassDependency) => {
                             format!("marker-start=\"url(#arrow-class-dep-start-{marker_id})\"")
                         }
-                        None => format!("marker-start=\"url(#arrow-start-{marker_id})\""),
+                        Some(crate::ir::EdgeArrowhead::SequenceAsync) | None => {
+                            format!("marker-start=\"url(#arrow-start-{marker_id})\"")
+                        }
                     },
                     _ => format!("marker-start=\"url(#arrow-start-{marker_id})\""),
                 }
@@ -991,29 +1390,50 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 dash = format!("stroke-dasharray=\"{}\"", dash_override);
             }
             svg.push_str(&format!(
-                "<path id=\"{edge_id}\" class=\"edgePath\" data-edge-id=\"{edge_id}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" {} {} {} stroke-linecap=\"round\" stroke-linejoin=\"round\" />",
-                d, stroke, stroke_width, marker_end, marker_start, dash
+                "<path id=\"{edge_id}\" class=\"edgePath\" data-edge-id=\"{edge_id}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" {} {} {} stroke-linecap=\"{}\" stroke-linejoin=\"{}\" />",
+                d,
+                stroke,
+                stroke_width,
+                marker_end,
+                marker_start,
+                dash,
+                config.flowchart.edge_linecap.as_str(),
+                config.flowchart.edge_linejoin.as_str()
             ));
 
             if overlay_flowchart {
                 if edge.arrow_start
                     && let Some(point) = edge.points.first().copied()
                 {
-                    let angle = edge_endpoint_angle(&edge.points, true);
-                    overlay_arrows.push((true, point, angle, stroke.clone(), stroke_width));
+                    let angle = start_angle;
+                    overlay_arrows.push((
+                        true,
+                        point,
+                        angle,
+                        stroke.clone(),
+                        stroke_width,
+                        edge_group_open(&edge_id, edge, render_config, "edgeArrow"),
+                    ));
                 }
                 if edge.arrow_end
                     && let Some(point) = edge.points.last().copied()
                 {
-                    let angle = edge_endpoint_angle(&edge.points, false);
-                    overlay_arrows.push((false, point, angle, stroke.clone(), stroke_width));
+                    let angle = end_angle;
+                    overlay_arrows.push((
+                        false,
+                        point,
+                        angle,
+                        stroke.clone(),
+                        stroke_width,
+                        edge_group_open(&edge_id, edge, render_config, "edgeArrow"),
+                    ));
                 }
             }
 
             if let Some(point) = edge.points.first().copied()
                 && let Some(decoration) = edge.start_decoration
             {
-                let angle = edge_endpoint_angle(&edge.points, true);
+                let angle = start_angle;
                 svg.push_str(&edge_decoration_svg(
                     point,
                     angle,
@@ -1026,7 +1446,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if let Some(point) = edge.points.last().copied()
                 && let Some(decoration) = edge.end_decoration
             {
-                let angle = edge_endpoint_angle(&edge.points, false);
+                let angle = end_angle;
                 svg.push_str(&edge_decoration_svg(
                     point,
                     angle,
@@ -1036,6 +1456,16 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                     false,
                 ));
             }
+            if let Some(marker) = edge.override_style.midpoint_marker.as_deref()
+                && let Some(point) = edge_label_anchor_from_points(&edge.points)
+            {
+                svg.push_str(&edge_midpoint_marker_svg(
+                    point,
+                    marker,
+                    &stroke,
+                    stroke_width,
+                ));
+            }
 
             if let Some(label) = edge.label.as_ref()
                 && let Some((x, y)) = edge.label_anchor
@@ -1054,8 +1484,36 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 let label_w = label.width * label_scale;
                 let label_h = label.height * label_scale;
                 let rect = LabelRect::from_center(x, y, label_w, label_h, pad_x, pad_y);
+                let rotate_attr = if layout.kind == crate::ir::DiagramKind::Flowchart
+                    && config.flowchart.rotate_edge_labels
+                    && let Some(angle) = edge_label_rotation(&edge.points, (x, y))
+                {
+                    format!(" transform=\"rotate({angle:.2} {x:.2} {y:.2})\"")
+                } else {
+                    String::new()
+                };
+                let label_shape = if layout.kind == crate::ir::DiagramKind::Flowchart {
+                    config.flowchart.edge_label_shape
+                } else {
+                    LabelShape::Rect
+                };
                 let label_fill = theme.edge_label_background.as_str();
-                if label_fill != "none" {
+                let mut label_color = edge.override_style.label_color.clone();
+                if label_shape == LabelShape::Pill {
+                    let pill_fill = theme.primary_border_color.as_str();
+                    let pill_w = rect.width + rect.height / 2.0;
+                    let radius = rect.height / 2.0;
+                    svg.push_str(&format!(
+                        "<rect class=\"edgeLabelPill\" data-edge-id=\"{edge_id}\" data-label-kind=\"center\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"{radius:.2}\" ry=\"{radius:.2}\" fill=\"{}\" stroke=\"none\"{rotate_attr}/>",
+                        x - pill_w / 2.0,
+                        rect.y,
+                        pill_w,
+                        rect.height,
+                        pill_fill
+                    ));
+                    label_color
+                        .get_or_insert_with(|| contrast_text_color(pill_fill, theme).to_string());
+                } else if label_shape == LabelShape::Rect && label_fill != "none" {
                     let visible = edge_label_background_visible(
                         layout.kind,
                         EdgeLabelKind::Center,
@@ -1065,7 +1523,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                     let fill_opacity = if visible { fill_opacity } else { 0.0 };
                     let stroke_opacity = if visible { stroke_opacity } else { 0.0 };
                     svg.push_str(&format!(
-                        "<rect data-edge-id=\"{edge_id}\" data-label-kind=\"center\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"2\" ry=\"2\" fill=\"{}\" fill-opacity=\"{:.2}\" stroke=\"{}\" stroke-opacity=\"{:.2}\" stroke-width=\"0.8\"/>",
+                        "<rect data-edge-id=\"{edge_id}\" data-label-kind=\"center\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"2\" ry=\"2\" fill=\"{}\" fill-opacity=\"{:.2}\" stroke=\"{}\" stroke-opacity=\"{:.2}\" stroke-width=\"0.8\"{rotate_attr}/>",
                         rect.x,
                         rect.y,
                         rect.width,
@@ -1094,7 +1552,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                     svg.push_str("</g>");
                 } else {
                     svg.push_str(&format!(
-                        "<g class=\"edgeLabel\" data-edge-id=\"{edge_id}\" data-label-kind=\"center\">"
+                        "<g class=\"edgeLabel\" data-edge-id=\"{edge_id}\" data-label-kind=\"center\"{rotate_attr}>"
                     ));
                     svg.push_str(&text_block_svg(
                         x,
@@ -1103,7 +1561,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                         theme,
                         config,
                         true,
-                        edge.override_style.label_color.as_deref(),
+                        label_color.as_deref(),
                     ));
                     svg.push_str("</g>");
                 }
@@ -1255,6 +1713,9 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                     svg.push_str("</g>");
                 }
             }
+            if edge_group.is_some() {
+                svg.push_str("</g>");
+            }
         }
     }
 
@@ -1279,6 +1740,10 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if node.anchor_subgraph.is_some() {
                 continue;
             }
+            let node_group = node_group_open(node, render_config);
+            if let Some(open) = node_group.as_deref() {
+                svg.push_str(open);
+            }
             if let Some(link) = node.link.as_ref() {
                 svg.push_str(&format!("<a {}>", link_attrs(link)));
                 if let Some(title) = link.title.as_deref() {
@@ -1290,6 +1755,12 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 if node.link.is_some() {
                     svg.push_str("</a>");
                 }
+                if config.debug.show_node_ids {
+                    svg.push_str(&node_id_debug_svg(node, theme));
+                }
+                if node_group.is_some() {
+                    svg.push_str("</g>");
+                }
                 continue;
             }
             svg.push_str(&shape_svg(node, theme, config));
@@ -1302,14 +1773,17 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 svg.push_str(&divider_lines_svg(node, theme, divider_line_height));
             }
             let center_x = node.x + node.width / 2.0;
-            let center_y = node.y + node.height / 2.0;
+            let center_y = node.y + node.height / 2.0 + render_config.text_baseline_offset;
             let hide_label = node.label.lines.iter().all(|line| line.trim().is_empty())
                 || node.id.starts_with("__start_")
                 || node.id.starts_with("__end_");
             if !hide_label {
                 let label_svg = if layout.kind == crate::ir::DiagramKind::Treemap {
                     let label_x = node.x + config.treemap.label_padding_x;
-                    let label_y = node.y + config.treemap.label_padding_y + node.label.height / 2.0;
+                    let label_y = node.y
+                        + config.treemap.label_padding_y
+                        + node.label.height / 2.0
+                        + render_config.text_baseline_offset;
                     text_block_svg_anchor(
                         label_x,
                         label_y,
@@ -1327,6 +1801,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                                 theme,
                                 config,
                                 node.style.text_color.as_deref(),
+                                false,
                             )
                         } else {
                             text_block_svg(
@@ -1340,28 +1815,56 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                             )
                         }
                     })
-                } else if node.label.lines.iter().any(|line| is_divider_line(line)) {
-                    text_block_svg_class(node, theme, config, node.style.text_color.as_deref())
+                } else if node.label.lines.iter().any(|line| is_divider_line(line))
+                    || (layout.kind == crate::ir::DiagramKind::Class
+                        && node
+                            .label
+                            .lines
+                            .first()
+                            .is_some_and(|line| is_stereotype_line(line)))
+                {
+                    text_block_svg_class(
+                        node,
+                        theme,
+                        config,
+                        node.style.text_color.as_deref(),
+                        layout.kind == crate::ir::DiagramKind::Class,
+                    )
                 } else if layout.kind == crate::ir::DiagramKind::State {
+                    let (label_x, anchor) = aligned_label_anchor(center_x, &node.label, config);
                     text_block_svg_with_font_size(
-                        center_x,
+                        label_x,
                         center_y,
                         &node.label,
                         theme,
                         config,
-                        state_font_size,
-                        "middle",
+                        state_font_size * config.labels.node_label_scale,
+                        anchor,
                         node.style.text_color.as_deref(),
                         false,
                     )
-                } else {
-                    text_block_svg(
-                        center_x,
+                } else if layout.kind == crate::ir::DiagramKind::Flowchart {
+                    let (label_x, anchor) = aligned_label_anchor(center_x, &node.label, config);
+                    text_block_svg_with_font_size(
+                        label_x,
                         center_y,
                         &node.label,
                         theme,
                         config,
+                        theme.font_size * config.labels.node_label_scale,
+                        anchor,
+                        node.style.text_color.as_deref(),
                         false,
+                    )
+                } else {
+                    let (label_x, anchor) = aligned_label_anchor(center_x, &node.label, config);
+                    text_block_svg_anchor(
+                        label_x,
+                        center_y,
+                        &node.label,
+                        theme,
+                        config,
+                        anchor,
                         node.style.text_color.as_deref(),
                     )
                 };
@@ -1370,17 +1873,29 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if node.link.is_some() {
                 svg.push_str("</a>");
             }
+            if config.debug.show_node_ids {
+                svg.push_str(&node_id_debug_svg(node, theme));
+            }
+            if node_group.is_some() {
+                svg.push_str("</g>");
+            }
         }
 
         if overlay_flowchart && !overlay_arrows.is_empty() {
-            for (is_start, point, angle, stroke, stroke_width) in overlay_arrows {
+            for (is_start, point, angle, stroke, stroke_width, group) in overlay_arrows {
                 let final_angle = if is_start { angle + 180.0 } else { angle };
+                if let Some(open) = group.as_deref() {
+                    svg.push_str(open);
+                }
                 svg.push_str(&arrowhead_svg(
                     point,
                     final_angle,
                     stroke.as_str(),
                     stroke_width,
                 ));
+                if group.is_some() {
+                    svg.push_str("</g>");
+                }
             }
         }
 
@@ -1395,7 +1910,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             let divider_line_height = theme.font_size * config.label_line_height;
             svg.push_str(&divider_lines_svg(footbox, theme, divider_line_height));
             let center_x = footbox.x + footbox.width / 2.0;
-            let center_y = footbox.y + footbox.height / 2.0;
+            let center_y = footbox.y + footbox.height / 2.0 + render_config.text_baseline_offset;
             let hide_label = footbox
                 .label
                 .lines
@@ -1410,6 +1925,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                         theme,
                         config,
                         footbox.style.text_color.as_deref(),
+                        false,
                     )
                 } else {
                     text_block_svg(
@@ -1436,6 +1952,10 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if node.anchor_subgraph.is_some() {
                 continue;
             }
+            let node_group = node_group_open(node, render_config);
+            if let Some(open) = node_group.as_deref() {
+                svg.push_str(open);
+            }
             if let Some(link) = node.link.as_ref() {
                 svg.push_str(&format!("<a {}>", link_attrs(link)));
                 if let Some(title) = link.title.as_deref() {
@@ -1452,7 +1972,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 theme.sequence_actor_border
             ));
             let center_x = node.x + node.width / 2.0;
-            let center_y = node.y + node.height / 2.0;
+            let center_y = node.y + node.height / 2.0 + render_config.text_baseline_offset;
             let hide_label = node.label.lines.iter().all(|line| line.trim().is_empty())
                 || node.id.starts_with("__start_")
                 || node.id.starts_with("__end_");
@@ -1470,6 +1990,12 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
             if node.link.is_some() {
                 svg.push_str("</a>");
             }
+            if config.debug.show_node_ids {
+                svg.push_str(&node_id_debug_svg(node, theme));
+            }
+            if node_group.is_some() {
+                svg.push_str("</g>");
+            }
         }
         for footbox in seq_data.map(|s| s.footboxes.as_slice()).unwrap_or_default() {
             if let Some(link) = footbox.link.as_ref() {
@@ -1488,7 +2014,7 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
                 theme.sequence_actor_border
             ));
             let center_x = footbox.x + footbox.width / 2.0;
-            let center_y = footbox.y + footbox.height / 2.0;
+            let center_y = footbox.y + footbox.height / 2.0 + render_config.text_baseline_offset;
             let hide_label = footbox
                 .label
                 .lines
@@ -1513,10 +2039,379 @@ pub fn render_svg(layout: &Layout, theme: &Theme, config: &LayoutConfig) -> Stri
         }
     }
 
+    if let Some((entries, x, y)) = edge_legend {
+        svg.push_str(&render_edge_legend(entries, x, y, theme));
+    }
+
     svg.push_str("</svg>");
+}
+
+/// Render a scaled-down overview of a layout for use as a navigation minimap.
+///
+/// Each visible node becomes a plain rectangle and each edge a thin polyline;
+/// labels, markers and styling are left out. `scale` multiplies the layout's
+/// coordinates, so `0.1` yields an overview a tenth of the full size.
+pub fn render_minimap(layout: &Layout, scale: f32) -> String {
+    let scale = if scale > 0.0 { scale } else { 1.0 };
+    let width = (layout.width * scale).max(1.0);
+    let height = (layout.height * scale).max(1.0);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"minimap\" width=\"{width:.2}\" height=\"{height:.2}\" viewBox=\"0 0 {width:.2} {height:.2}\">"
+    );
+    svg.push_str("<g fill=\"none\" stroke=\"#b0b0b0\" stroke-width=\"0.5\">");
+    for edge in &layout.edges {
+        if edge.points.len() < 2 {
+            continue;
+        }
+        let points = edge
+            .points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", x * scale, y * scale))
+            .collect::<Vec<_>>()
+            .join(" ");
+        svg.push_str(&format!("<polyline points=\"{points}\"/>"));
+    }
+    svg.push_str("</g><g fill=\"#6b7280\">");
+    for node in layout.nodes.values().filter(|node| !node.hidden) {
+        svg.push_str(&format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+            node.x * scale,
+            node.y * scale,
+            node.width * scale,
+            node.height * scale
+        ));
+    }
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// Render a laid-out diagram as a series of page-sized SVGs.
+///
+/// Sequence diagrams taller than `page_height` are sliced into pages that each
+/// repeat the participant headers above their portion of the lifelines. A cut
+/// that would split a message is moved up to just above it. Other diagram
+/// kinds, or pages too short to fit anything below the headers, produce a
+/// single SVG.
+///
+/// Pages are cut in layout coordinates, so options that move or resize the
+/// canvas (`tight`, `min_size`, `max_size`, `mirror`, `clip_to_viewbox`,
+/// `integer_dimensions` and `frame`) are ignored when the diagram is split.
+pub fn render_svg_paginated(
+    layout: &Layout,
+    theme: &Theme,
+    config: &LayoutConfig,
+    render_config: &RenderConfig,
+    page_height: f32,
+) -> Vec<String> {
+    if !matches!(layout.diagram, DiagramData::Sequence(_)) {
+        return vec![render_svg_with_config(layout, theme, config, render_config)];
+    }
+    let header_height = layout
+        .nodes
+        .values()
+        .filter(|node| !node.hidden)
+        .map(|node| node.y + node.height)
+        .fold(0.0f32, f32::max);
+    let total_height = layout.height.max(1.0);
+    let body_capacity = page_height - header_height;
+    if !page_height.is_finite() || body_capacity <= 0.0 || total_height <= page_height {
+        return vec![render_svg_with_config(layout, theme, config, render_config)];
+    }
+
+    let margin = theme.font_size * 0.5;
+    let label_gap = (theme.font_size * 0.6).max(8.0);
+    let spans: Vec<(f32, f32)> = layout
+        .edges
+        .iter()
+        .filter(|edge| !edge.points.is_empty())
+        .map(|edge| {
+            let top = edge.points.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+            let bottom = edge.points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+            let label_top = edge
+                .label
+                .as_ref()
+                .map(|label| match edge.label_anchor {
+                    Some((_, y)) => y - label.height / 2.0,
+                    None => top - label_gap - label.height,
+                })
+                .unwrap_or(top);
+            (top.min(label_top) - margin, bottom + margin)
+        })
+        .collect();
+
+    let mut cuts = vec![header_height];
+    let mut start = header_height;
+    while start + body_capacity < total_height {
+        let mut cut = start + body_capacity;
+        while let Some(top) = spans
+            .iter()
+            .filter(|(top, bottom)| *top < cut && *bottom > cut && *top > start)
+            .map(|(top, _)| *top)
+            .reduce(f32::min)
+        {
+            cut = top;
+        }
+        cuts.push(cut);
+        start = cut;
+    }
+    cuts.push(total_height);
+
+    let page_config = RenderConfig {
+        tight: false,
+        min_size: None,
+        max_size: None,
+        mirror: None,
+        clip_to_viewbox: false,
+        integer_dimensions: false,
+        frame: None,
+        ..render_config.clone()
+    };
+    let full = render_svg_with_config(layout, theme, config, &page_config);
+    let Some((open_tag, body, _)) = svg_root_parts(&full) else {
+        return vec![full];
+    };
+    // Each page is a document of its own: it keeps the accessibility
+    // attributes and the title and description as direct root children.
+    let root_attrs: String = ["role", "aria-describedby", "text-rendering"]
+        .iter()
+        .filter_map(|name| svg_tag_attr(open_tag, name).map(|value| format!(" {name}=\"{value}\"")))
+        .collect();
+    let (preamble, inner) = split_svg_preamble(body);
+    let width = layout.width.max(1.0);
+    let id_prefix = document_id_prefix(layout, render_config);
+    cuts.windows(2)
+        .enumerate()
+        .map(|(page, pair)| {
+            let page_id = format!("{id_prefix}-page{page}");
+            let body_height = pair[1] - pair[0];
+            let height = header_height + body_height;
+            let offset = header_height - pair[0];
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{width}\" height=\"{height:.2}\" viewBox=\"0 0 {width} {height:.2}\"{root_attrs}>{preamble}<defs><g id=\"{page_id}-content\">{inner}</g><clipPath id=\"{page_id}-header-clip\"><rect x=\"0\" y=\"0\" width=\"{width}\" height=\"{header_height:.2}\"/></clipPath><clipPath id=\"{page_id}-body-clip\"><rect x=\"0\" y=\"{header_height:.2}\" width=\"{width}\" height=\"{body_height:.2}\"/></clipPath></defs><use href=\"#{page_id}-content\" clip-path=\"url(#{page_id}-header-clip)\"/><g clip-path=\"url(#{page_id}-body-clip)\"><use href=\"#{page_id}-content\" transform=\"translate(0 {offset:.2})\"/></g></svg>"
+            )
+        })
+        .collect()
+}
+
+/// Value of attribute `name` in an opening tag.
+fn svg_tag_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let (_, rest) = tag.split_once(&format!(" {name}=\""))?;
+    rest.split_once('"').map(|(value, _)| value)
+}
+
+/// Small annotation with the node's source id, drawn inside its top-left corner.
+fn node_id_debug_svg(node: &crate::layout::NodeLayout, theme: &Theme) -> String {
+    let font_size = (theme.font_size * 0.55).max(8.0);
+    format!(
+        "<text class=\"node-id-debug\" x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"start\" font-family=\"{}\" font-size=\"{}\" fill=\"{DEBUG_COLOR}\">{}</text>",
+        node.x + 2.0,
+        node.y + font_size,
+        normalize_font_family(&theme.font_family),
+        font_size,
+        escape_xml(&node.id)
+    )
+}
+
+/// `stroke-dasharray` attribute and stroke width for an edge style.
+fn edge_style_stroke(style: crate::ir::EdgeStyle, base_width: f32) -> (String, f32) {
+    match style {
+        crate::ir::EdgeStyle::Solid => (String::new(), base_width),
+        crate::ir::EdgeStyle::Dotted => ("stroke-dasharray=\"2\"".to_string(), base_width),
+        crate::ir::EdgeStyle::Thick => (String::new(), 3.5),
+    }
+}
+
+/// Height of the frame's title bar; 0 without a title.
+fn frame_title_height(spec: &FrameSpec, theme: &Theme) -> f32 {
+    if spec.title.is_some() {
+        theme.font_size * 2.0
+    } else {
+        0.0
+    }
+}
+
+/// Rounded border filling `rect` with the canvas fill (`None` leaves it
+/// unfilled), plus the title bar and its text when the frame has a title.
+fn frame_svg(
+    spec: &FrameSpec,
+    title_height: f32,
+    rect: (f32, f32, f32, f32),
+    theme: &Theme,
+    canvas_fill: Option<&str>,
+) -> String {
+    let half = spec.stroke_width / 2.0;
+    let (x, y) = (rect.0 + half, rect.1 + half);
+    let (w, h) = (rect.2 - spec.stroke_width, rect.3 - spec.stroke_width);
+    let r = spec.corner_radius.clamp(0.0, w.min(h) / 2.0);
+    let color = escape_xml(&spec.color);
+    let fill = canvas_fill.unwrap_or("none");
+    let mut svg = format!(
+        "<g class=\"diagram-frame\"><rect class=\"frame-border\" x=\"{x:.2}\" y=\"{y:.2}\" width=\"{w:.2}\" height=\"{h:.2}\" rx=\"{r:.2}\" ry=\"{r:.2}\" fill=\"{fill}\" stroke=\"{color}\" stroke-width=\"{}\"/>",
+        spec.stroke_width
+    );
+    if let Some(title) = spec.title.as_deref() {
+        let bar_bottom = y + title_height - half;
+        let r = r.min(title_height - half);
+        svg.push_str(&format!(
+            "<path class=\"frame-title-bar\" d=\"M{x:.2} {bar_bottom:.2} V{:.2} Q{x:.2} {y:.2} {:.2} {y:.2} H{:.2} Q{:.2} {y:.2} {:.2} {:.2} V{bar_bottom:.2} Z\" fill=\"{color}\"/>",
+            y + r,
+            x + r,
+            x + w - r,
+            x + w,
+            x + w,
+            y + r
+        ));
+        svg.push_str(&format!(
+            "<text class=\"frame-title\" x=\"{:.2}\" y=\"{:.2}\" dominant-baseline=\"middle\" font-family=\"{}\" font-size=\"{}\" font-weight=\"600\" fill=\"{}\">{}</text>",
+            x + theme.font_size * 0.75,
+            y + (title_height - half) / 2.0,
+            escape_xml(&theme.font_family),
+            theme.font_size,
+            escape_xml(&theme.text_color),
+            escape_xml(title)
+        ));
+    }
+    svg.push_str("</g>");
+    svg
+}
+
+const EDGE_LEGEND_MARGIN: f32 = 8.0;
+const EDGE_LEGEND_PADDING: f32 = 8.0;
+const EDGE_LEGEND_SAMPLE: f32 = 32.0;
+
+fn edge_legend_font_size(theme: &Theme) -> f32 {
+    theme.font_size * 0.85
+}
+
+/// Outer `(width, height)` of the edge style legend box.
+fn edge_legend_size(entries: &[(crate::ir::EdgeStyle, String)], theme: &Theme) -> (f32, f32) {
+    let font_size = edge_legend_font_size(theme);
+    let text_width = entries
+        .iter()
+        .map(|(_, text)| {
+            text_metrics::measure_text_width(text, font_size, &theme.font_family)
+                .unwrap_or(text.chars().count() as f32 * font_size * 0.6)
+        })
+        .fold(0.0, f32::max);
+    let width = EDGE_LEGEND_PADDING * 3.0 + EDGE_LEGEND_SAMPLE + text_width;
+    let height = EDGE_LEGEND_PADDING * 2.0 + entries.len() as f32 * font_size * 1.6;
+    (width, height)
+}
+
+/// A boxed list of sample edge segments, each followed by its description,
+/// with the box's top-left corner at `(x, y)`.
+fn render_edge_legend(
+    entries: &[(crate::ir::EdgeStyle, String)],
+    x: f32,
+    y: f32,
+    theme: &Theme,
+) -> String {
+    let font_size = edge_legend_font_size(theme);
+    let row_height = font_size * 1.6;
+    let (width, height) = edge_legend_size(entries, theme);
+    let mut svg = String::from("<g class=\"edge-legend\">");
+    svg.push_str(&format!(
+        "<rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" rx=\"4\" ry=\"4\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
+        escape_xml(&theme.edge_label_background),
+        escape_xml(&theme.primary_border_color)
+    ));
+    for (idx, (style, text)) in entries.iter().enumerate() {
+        let center_y = y + EDGE_LEGEND_PADDING + row_height * (idx as f32 + 0.5);
+        let line_x = x + EDGE_LEGEND_PADDING;
+        let (dash, stroke_width) = edge_style_stroke(*style, 2.0);
+        svg.push_str(&format!(
+            "<line x1=\"{line_x:.2}\" y1=\"{center_y:.2}\" x2=\"{:.2}\" y2=\"{center_y:.2}\" stroke=\"{}\" stroke-width=\"{stroke_width}\" {dash}/>",
+            line_x + EDGE_LEGEND_SAMPLE,
+            escape_xml(&theme.line_color)
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{center_y:.2}\" dominant-baseline=\"middle\" font-family=\"{}\" font-size=\"{font_size}\" fill=\"{}\">{}</text>",
+            line_x + EDGE_LEGEND_SAMPLE + EDGE_LEGEND_PADDING,
+            normalize_font_family(&theme.font_family),
+            escape_xml(&theme.primary_text_color),
+            escape_xml(text)
+        ));
+    }
+    svg.push_str("</g>");
     svg
 }
 
+/// Grow the canvas to `min_size` by widening the viewBox evenly around the
+/// content, then scale the output dimensions down to fit `max_size`.
+fn apply_size_limits(
+    dims: (f32, f32, f32, f32, f32, f32),
+    render_config: &RenderConfig,
+) -> (f32, f32, f32, f32, f32, f32) {
+    let (mut width, mut height, mut vb_x, mut vb_y, mut vb_width, mut vb_height) = dims;
+    if let Some((min_width, min_height)) = render_config.min_size {
+        if width < min_width {
+            let extra = vb_width * (min_width / width - 1.0);
+            vb_x -= extra / 2.0;
+            vb_width += extra;
+            width = min_width;
+        }
+        if height < min_height {
+            let extra = vb_height * (min_height / height - 1.0);
+            vb_y -= extra / 2.0;
+            vb_height += extra;
+            height = min_height;
+        }
+    }
+    if let Some((max_width, max_height)) = render_config.max_size {
+        let scale = (max_width / width).min(max_height / height);
+        if scale.is_finite() && scale > 0.0 && scale < 1.0 {
+            width *= scale;
+            height *= scale;
+        }
+    }
+    (width, height, vb_x, vb_y, vb_width, vb_height)
+}
+
+/// Opening `<g>` tag wrapping a node, carrying its opacity and, in interactive
+/// mode, the `node` class and id hooks used by hover styling.
+fn node_group_open(
+    node: &crate::layout::NodeLayout,
+    render_config: &RenderConfig,
+) -> Option<String> {
+    let mut attrs = String::new();
+    if render_config.interactive {
+        attrs.push_str(&format!(
+            " class=\"node\" data-node-id=\"{}\"",
+            escape_xml(&node.id)
+        ));
+    }
+    if let Some(opacity) = node.style.opacity {
+        attrs.push_str(&format!(" opacity=\"{opacity}\""));
+    }
+    match node.tooltip.as_deref() {
+        Some(tooltip) => Some(format!("<g{attrs}><title>{}</title>", escape_xml(tooltip))),
+        None => (!attrs.is_empty()).then(|| format!("<g{attrs}>")),
+    }
+}
+
+/// Opening `<g>` tag wrapping an edge (or its overlaid arrowheads). In
+/// interactive mode it carries `data-source`/`data-target` so scripts can find
+/// the connected nodes.
+fn edge_group_open(
+    edge_id: &str,
+    edge: &crate::layout::EdgeLayout,
+    render_config: &RenderConfig,
+    class: &str,
+) -> Option<String> {
+    let mut attrs = String::new();
+    if render_config.interactive {
+        attrs.push_str(&format!(
+            " class=\"{class}\" data-edge-id=\"{edge_id}\" data-source=\"{}\" data-target=\"{}\"",
+            escape_xml(&edge.from),
+            escape_xml(&edge.to)
+        ));
+    }
+    if let Some(opacity) = edge.override_style.opacity {
+        attrs.push_str(&format!(" opacity=\"{opacity}\""));
+    }
+    (!attrs.is_empty()).then(|| format!("<g{attrs}>"))
+}
+
 fn points_to_path(points: &[(f32, f32)]) -> String {
     if points.is_empty() {
         return String::new();
@@ -1532,6 +2427,82 @@ fn points_to_path(points: &[(f32, f32)]) -> String {
     d
 }
 
+type CubicSegment = [(f32, f32); 4];
+
+/// Cardinal spline segments through already deduped `points`: one
+/// `[start, c1, c2, end]` cubic per waypoint pair.
+fn cardinal_segments(points: &[(f32, f32)], tension: f32) -> Vec<CubicSegment> {
+    let scale = (1.0 - tension.clamp(0.0, 1.0)) / 6.0;
+    let last = points.len().saturating_sub(1);
+    (0..last)
+        .map(|i| {
+            let p0 = points[i.saturating_sub(1)];
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let p3 = points[(i + 2).min(last)];
+            let c1 = (p1.0 + (p2.0 - p0.0) * scale, p1.1 + (p2.1 - p0.1) * scale);
+            let c2 = (p2.0 - (p3.0 - p1.0) * scale, p2.1 - (p3.1 - p1.1) * scale);
+            [p1, c1, c2, p2]
+        })
+        .collect()
+}
+
+fn cubic_path(segments: &[CubicSegment]) -> String {
+    let Some(first) = segments.first() else {
+        return String::new();
+    };
+    let mut d = format!("M {:.3},{:.3}", first[0].0, first[0].1);
+    for [_, c1, c2, p2] in segments {
+        d.push_str(&format!(
+            " C {:.3},{:.3} {:.3},{:.3} {:.3},{:.3}",
+            c1.0, c1.1, c2.0, c2.1, p2.0, p2.1
+        ));
+    }
+    d
+}
+
+/// Cardinal spline through `points` as cubic Béziers. `tension` 0 gives a
+/// Catmull-Rom curve; 1 collapses the control points onto the waypoints.
+fn points_to_curve_path(points: &[(f32, f32)], tension: f32) -> String {
+    let deduped = dedupe_points(points);
+    if deduped.len() < 3 {
+        return points_to_path(&deduped);
+    }
+    cubic_path(&cardinal_segments(&deduped, tension))
+}
+
+/// Catmull-Rom path through `points` plus the start and end arrow angles,
+/// taken from the curve tangents rather than the first and last waypoint
+/// segments.
+fn basis_path_with_angles(points: &[(f32, f32)]) -> (String, f32, f32) {
+    let deduped = dedupe_points(points);
+    if deduped.len() < 3 {
+        return (
+            points_to_path(&deduped),
+            edge_endpoint_angle(&deduped, true),
+            edge_endpoint_angle(&deduped, false),
+        );
+    }
+    let segments = cardinal_segments(&deduped, 0.0);
+    let [start, c1, c2_first, first_end] = segments[0];
+    let [last_start, c1_last, c2, end] = segments[segments.len() - 1];
+    // A control point sitting on its endpoint leaves the tangent to the
+    // next one along the segment.
+    let start_toward = [c1, c2_first, first_end]
+        .into_iter()
+        .find(|p| (p.0 - start.0).hypot(p.1 - start.1) > 1e-3)
+        .unwrap_or(first_end);
+    let end_from = [c2, c1_last, last_start]
+        .into_iter()
+        .find(|p| (p.0 - end.0).hypot(p.1 - end.1) > 1e-3)
+        .unwrap_or(last_start);
+    (
+        cubic_path(&segments),
+        edge_endpoint_angle(&[start, start_toward], true),
+        edge_endpoint_angle(&[end_from, end], false),
+    )
+}
+
 fn dedupe_points(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
     let mut out = Vec::with_capacity(points.len());
     for point in points.iter().copied() {
@@ -1584,6 +2555,14 @@ enum EdgeLabelKind {
     End,
 }
 
+/// Dark theme text on light fills, white on dark ones.
+fn contrast_text_color<'a>(fill: &str, theme: &'a Theme) -> &'a str {
+    match parse_color_to_hsl(fill) {
+        Some((_, _, lightness)) if lightness < 60.0 => "#ffffff",
+        _ => theme.primary_text_color.as_str(),
+    }
+}
+
 fn edge_label_background_visible(
     diagram_kind: crate::ir::DiagramKind,
     label_kind: EdgeLabelKind,
@@ -2192,7 +3171,20 @@ fn render_requirement(layout: &Layout, theme: &Theme, config: &LayoutConfig) ->
             ));
             let mut body_y = divider_y + req.label_padding_y;
             for line in body_lines {
-                svg.push_str(&render_line(header_x, body_y, line, label_color, false));
+                if is_divider_line(line) {
+                    let y = body_y + theme.font_size / 2.0;
+                    svg.push_str(&format!(
+                        "<line class=\"requirement-compartment\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                        node.x,
+                        y,
+                        node.x + node.width,
+                        y,
+                        req.divider_color,
+                        req.divider_width
+                    ));
+                } else {
+                    svg.push_str(&render_line(header_x, body_y, line, label_color, false));
+                }
                 body_y += line_height;
             }
         }
@@ -2552,8 +3544,14 @@ fn render_architecture(
             .stroke
             .as_ref()
             .unwrap_or(&theme.line_color);
-        let stroke_width = edge.override_style.stroke_width.unwrap_or(3.0);
-        let marker_idx = color_ids.get(stroke).copied().unwrap_or(default_marker_idx);
+        let stroke_width = edge
+            .override_style
+            .stroke_width
+            .unwrap_or(base_edge_width(layout.kind));
+        let marker_idx = marker_id(
+            color_ids.get(stroke).copied().unwrap_or(default_marker_idx),
+            marker_scale(edge, layout.kind),
+        );
         let dash_attr = edge
             .override_style
             .dasharray
@@ -2685,6 +3683,9 @@ fn render_pie(pie: &PieData, theme: &Theme, config: &LayoutConfig) -> String {
 
     let pie_cfg = &config.pie;
     let mut total: f32 = pie.legend.iter().map(|s| s.value.max(0.0)).sum();
+    if let Some(more) = &pie.legend_more {
+        total += more.value.max(0.0);
+    }
     if total <= 0.0 {
         total = pie.slices.iter().map(|s| s.value.max(0.0)).sum();
     }
@@ -2748,7 +3749,8 @@ fn render_pie(pie: &PieData, theme: &Theme, config: &LayoutConfig) -> String {
     }
 
     let mut labels: Vec<PieLabel> = Vec::new();
-    let suppress_outside_labels = pie.legend.len() >= 4;
+    let legend_count = pie.legend.len() + pie.legend_more.as_ref().map_or(0, |more| more.hidden);
+    let suppress_outside_labels = legend_count >= 4;
     for slice in &pie.slices {
         let span = (slice.end_angle - slice.start_angle).abs();
         if span <= 0.0001 || total <= 0.0 {
@@ -2940,6 +3942,19 @@ fn render_pie(pie: &PieData, theme: &Theme, config: &LayoutConfig) -> String {
             true,
         ));
     }
+    if let Some(more) = &pie.legend_more {
+        svg.push_str(&text_block_svg_with_font_size(
+            more.x,
+            more.y + pie_cfg.legend_rect_size / 2.0,
+            &more.label,
+            theme,
+            config,
+            theme.pie_legend_text_size,
+            "start",
+            Some(theme.pie_legend_text_color.as_str()),
+            true,
+        ));
+    }
 
     if let Some(title) = &pie.title {
         svg.push_str(&text_block_svg_with_font_size(
@@ -2948,7 +3963,7 @@ fn render_pie(pie: &PieData, theme: &Theme, config: &LayoutConfig) -> String {
             &title.text,
             theme,
             config,
-            theme.pie_title_text_size,
+            theme.pie_title_text_size * config.labels.title_scale,
             "middle",
             Some(theme.pie_title_text_color.as_str()),
             true,
@@ -3038,14 +4053,16 @@ fn render_quadrant(
 
     // Title
     if let Some(ref title) = layout.title {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             grid_x + half_w,
             layout.title_y,
             title,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.title_scale,
+            "middle",
             Some(theme.primary_text_color.as_str()),
+            false,
         ));
     }
 
@@ -3073,49 +4090,55 @@ fn render_quadrant(
 
     // Axis labels
     if let Some(ref x_left) = layout.x_axis_left {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             grid_x + half_w / 2.0,
             grid_y + h + 20.0,
             x_left,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.axis_label_scale,
+            "middle",
             Some("#131300"),
+            false,
         ));
     }
     if let Some(ref x_right) = layout.x_axis_right {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             grid_x + half_w + half_w / 2.0,
             grid_y + h + 20.0,
             x_right,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.axis_label_scale,
+            "middle",
             Some("#131300"),
+            false,
         ));
     }
     if let Some(ref y_bottom) = layout.y_axis_bottom {
-        let axis_x = grid_x - theme.font_size * 2.2;
-        let axis_y = grid_y + half_h + half_h / 2.0;
-        svg.push_str(&format!(
-            "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\" dominant-baseline=\"middle\" font-family=\"{}\" font-size=\"{}\" fill=\"#131300\"><tspan>{}</tspan></text>",
-            axis_x,
-            axis_y,
-            normalize_font_family(&theme.font_family),
-            theme.font_size,
-            y_bottom.lines.first().map(|s| s.as_str()).unwrap_or("")
+        svg.push_str(&text_block_svg_with_font_size(
+            grid_x - theme.font_size * 2.2,
+            grid_y + half_h + half_h / 2.0,
+            y_bottom,
+            theme,
+            config,
+            theme.font_size * config.labels.axis_label_scale,
+            "end",
+            Some("#131300"),
+            false,
         ));
     }
     if let Some(ref y_top) = layout.y_axis_top {
-        let axis_x = grid_x - theme.font_size * 2.2;
-        let axis_y = grid_y + half_h / 2.0;
-        svg.push_str(&format!(
-            "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\" dominant-baseline=\"middle\" font-family=\"{}\" font-size=\"{}\" fill=\"#131300\"><tspan>{}</tspan></text>",
-            axis_x,
-            axis_y,
-            normalize_font_family(&theme.font_family),
-            theme.font_size,
-            y_top.lines.first().map(|s| s.as_str()).unwrap_or("")
+        svg.push_str(&text_block_svg_with_font_size(
+            grid_x - theme.font_size * 2.2,
+            grid_y + half_h / 2.0,
+            y_top,
+            theme,
+            config,
+            theme.font_size * config.labels.axis_label_scale,
+            "end",
+            Some("#131300"),
+            false,
         ));
     }
 
@@ -3148,20 +4171,25 @@ fn render_gantt(
     let chart_left = layout.chart_x;
     let chart_right = layout.chart_x + layout.chart_width;
     let full_width = chart_right + layout.label_x;
-    let bar_height = (layout.row_height * 0.82)
-        .min(layout.row_height - 4.0)
-        .max(theme.font_size * 1.1);
+    let bar_height = config.gantt.bar_height.unwrap_or_else(|| {
+        (layout.row_height * 0.82)
+            .min(layout.row_height - 4.0)
+            .max(theme.font_size * 1.1)
+    });
+    let bar_radius = config.gantt.bar_corner_radius.max(0.0);
 
     // Title
     if let Some(ref title) = layout.title {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             layout.chart_x + layout.chart_width / 2.0,
             layout.title_y,
             title,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.title_scale,
+            "middle",
             Some(theme.primary_text_color.as_str()),
+            false,
         ));
     }
 
@@ -3302,11 +4330,12 @@ fn render_gantt(
             ));
         } else {
             svg.push_str(&format!(
-                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"3\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
                 task.x,
                 bar_y,
                 task.width,
                 bar_height,
+                bar_radius,
                 task.color,
                 theme.primary_border_color
             ));
@@ -3358,6 +4387,58 @@ fn render_gantt(
         }
     }
 
+    // Dependency arrows, from the end of each prerequisite to the start of
+    // the task that waits on it.
+    let stub = (theme.font_size * 0.5).max(6.0);
+    let head = (theme.font_size * 0.4).max(5.0);
+    let milestone_half = |task: &crate::layout::GanttTaskLayout| {
+        if matches!(task.status, Some(crate::ir::GanttStatus::Milestone)) {
+            Some(bar_height * 0.6)
+        } else {
+            None
+        }
+    };
+    for &(from_idx, to_idx) in &layout.dependencies {
+        let (Some(from), Some(to)) = (layout.tasks.get(from_idx), layout.tasks.get(to_idx)) else {
+            continue;
+        };
+        let end_x = milestone_half(from).map_or(from.x + from.width, |half| from.x + half);
+        let start_x = milestone_half(to).map_or(to.x, |half| to.x - half);
+        let from_y = from.y + layout.row_height / 2.0;
+        let to_y = to.y + layout.row_height / 2.0;
+        let path = if start_x - end_x >= stub * 2.0 {
+            let mid_x = (end_x + start_x) / 2.0;
+            format!("M {end_x:.2},{from_y:.2} H {mid_x:.2} V {to_y:.2} H {start_x:.2}")
+        } else {
+            // The dependent starts where (or before) the prerequisite ends:
+            // step out, run along the row boundary, and come back in.
+            let lane_y = if to_y >= from_y {
+                to.y
+            } else {
+                to.y + layout.row_height
+            };
+            format!(
+                "M {end_x:.2},{from_y:.2} H {:.2} V {lane_y:.2} H {:.2} V {to_y:.2} H {start_x:.2}",
+                end_x + stub,
+                start_x - stub
+            )
+        };
+        svg.push_str(&format!(
+            "<path class=\"gantt-dependency\" d=\"{path}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.2\"/>",
+            theme.line_color
+        ));
+        svg.push_str(&format!(
+            "<polygon points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\" fill=\"{}\"/>",
+            start_x,
+            to_y,
+            start_x - head,
+            to_y - head * 0.6,
+            start_x - head,
+            to_y + head * 0.6,
+            theme.line_color
+        ));
+    }
+
     svg
 }
 
@@ -3368,22 +4449,18 @@ fn render_xychart(
 ) -> String {
     let mut svg = String::new();
 
-    // Background
-    svg.push_str(&format!(
-        "<rect x=\"0\" y=\"0\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
-        layout.width, layout.height, theme.background
-    ));
-
     // Title
     if let Some(ref title) = layout.title {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             layout.width / 2.0,
             layout.title_y,
             title,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.title_scale,
+            "middle",
             Some(theme.primary_text_color.as_str()),
+            false,
         ));
     }
 
@@ -3395,10 +4472,16 @@ fn render_xychart(
 
     // Y-axis ticks and labels
     for (label, y) in &layout.y_axis_ticks {
-        // Tick line
+        if config.xychart.show_gridlines {
+            svg.push_str(&format!(
+                "<line class=\"gridline\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"2,2\"/>",
+                layout.plot_x, y, layout.plot_x + layout.plot_width, y, "#ccc"
+            ));
+        }
+        // Tick mark
         svg.push_str(&format!(
-            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"2,2\"/>",
-            layout.plot_x, y, layout.plot_x + layout.plot_width, y, "#ccc"
+            "<line class=\"tick\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"1\"/>",
+            layout.plot_x - 4.0, y, layout.plot_x, y, theme.line_color
         ));
         // Label
         svg.push_str(&format!(
@@ -3419,15 +4502,38 @@ fn render_xychart(
         ));
     }
 
-    // Y-axis label
+    // Axis titles, one tspan per fitted line
+    let axis_font_size = theme.font_size * config.labels.axis_label_scale;
+    if let Some(ref x_label) = layout.x_axis_label {
+        svg.push_str(&text_block_svg_with_font_size(
+            layout.plot_x + layout.plot_width / 2.0,
+            layout.x_axis_label_y,
+            x_label,
+            theme,
+            config,
+            axis_font_size,
+            "middle",
+            Some(theme.primary_text_color.as_str()),
+            true,
+        ));
+    }
     if let Some(ref y_label) = layout.y_axis_label {
+        let center_y = layout.plot_y + layout.plot_height / 2.0;
         svg.push_str(&format!(
-            "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" font-family=\"{}\" font-size=\"{:.1}\" fill=\"{}\" transform=\"rotate(-90, {:.2}, {:.2})\">{}</text>",
-            layout.y_axis_label_x, layout.plot_y + layout.plot_height / 2.0,
-            normalize_font_family(&theme.font_family), theme.font_size,
-            theme.primary_text_color,
-            layout.y_axis_label_x, layout.plot_y + layout.plot_height / 2.0,
-            escape_xml(&y_label.lines.join(" "))
+            "<g transform=\"rotate(-90, {:.2}, {:.2})\">{}</g>",
+            layout.y_axis_label_x,
+            center_y,
+            text_block_svg_with_font_size(
+                layout.y_axis_label_x,
+                center_y,
+                y_label,
+                theme,
+                config,
+                axis_font_size,
+                "middle",
+                Some(theme.primary_text_color.as_str()),
+                false,
+            )
         ));
     }
 
@@ -3478,22 +4584,18 @@ fn render_timeline(
 ) -> String {
     let mut svg = String::new();
 
-    // Background
-    svg.push_str(&format!(
-        "<rect x=\"0\" y=\"0\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
-        layout.width, layout.height, theme.background
-    ));
-
     // Title
     if let Some(ref title) = layout.title {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             layout.width / 2.0,
             layout.title_y,
             title,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.title_scale,
+            "middle",
             Some(theme.primary_text_color.as_str()),
+            false,
         ));
     }
 
@@ -3559,20 +4661,25 @@ fn render_journey(layout: &JourneyLayout, theme: &Theme, config: &LayoutConfig)
     let mut svg = String::new();
 
     if let Some(ref title) = layout.title {
-        svg.push_str(&text_block_svg(
+        svg.push_str(&text_block_svg_with_font_size(
             layout.width / 2.0,
             layout.title_y,
             title,
             theme,
             config,
-            false,
+            theme.font_size * config.labels.title_scale,
+            "middle",
             Some(theme.primary_text_color.as_str()),
+            false,
         ));
     }
 
     let mut actor_colors: HashMap<String, String> = HashMap::new();
     for actor in &layout.actors {
         actor_colors.insert(actor.name.clone(), actor.color.clone());
+        if !config.journey.show_legend {
+            continue;
+        }
         svg.push_str(&format!(
             "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\"/>",
             actor.x,
@@ -4074,11 +5181,11 @@ fn text_block_svg_with_font_size(
     override_color: Option<&str>,
     baseline: bool,
 ) -> String {
-    let total_height = label.lines.len() as f32 * font_size * config.label_line_height;
+    let total_height = label.scaled_line_count() * font_size * config.label_line_height;
     let start_y = if baseline {
         y
     } else {
-        y - total_height / 2.0 + font_size
+        y - total_height / 2.0 + font_size * label.line_scale(0)
     };
     let mut text = String::new();
     let default_fill = theme.primary_text_color.as_str();
@@ -4093,12 +5200,12 @@ fn text_block_svg_with_font_size(
 
     let line_height = font_size * config.label_line_height;
     for (idx, line) in label.lines.iter().enumerate() {
-        let dy = if idx == 0 { 0.0 } else { line_height };
-        let rendered = if is_divider_line(line) {
-            String::new()
+        let dy = if idx == 0 {
+            0.0
         } else {
-            escape_xml(line)
+            line_height * label.line_scale(idx)
         };
+        let rendered = text_block_line_svg(label, idx, line, font_size);
         text.push_str(&format!(
             "<tspan x=\"{x:.2}\" dy=\"{dy:.2}\">{}</tspan>",
             rendered
@@ -4121,11 +5228,11 @@ fn text_block_svg_with_font_size_weight(
     font_weight: Option<&str>,
     baseline: bool,
 ) -> String {
-    let total_height = label.lines.len() as f32 * font_size * config.label_line_height;
+    let total_height = label.scaled_line_count() * font_size * config.label_line_height;
     let start_y = if baseline {
         y
     } else {
-        y - total_height / 2.0 + font_size
+        y - total_height / 2.0 + font_size * label.line_scale(0)
     };
     let mut text = String::new();
     let default_fill = theme.primary_text_color.as_str();
@@ -4144,12 +5251,12 @@ fn text_block_svg_with_font_size_weight(
 
     let line_height = font_size * config.label_line_height;
     for (idx, line) in label.lines.iter().enumerate() {
-        let dy = if idx == 0 { 0.0 } else { line_height };
-        let rendered = if is_divider_line(line) {
-            String::new()
+        let dy = if idx == 0 {
+            0.0
         } else {
-            escape_xml(line)
+            line_height * label.line_scale(idx)
         };
+        let rendered = text_block_line_svg(label, idx, line, font_size);
         text.push_str(&format!(
             "<tspan x=\"{x:.2}\" dy=\"{dy:.2}\">{}</tspan>",
             rendered
@@ -4160,6 +5267,48 @@ fn text_block_svg_with_font_size_weight(
     text
 }
 
+/// Escaped content for line `idx` of `label`, split into bold, italic and
+/// `<small>` runs when the label carries markdown spans that still match
+/// the line.
+fn text_block_line_svg(label: &TextBlock, idx: usize, line: &str, font_size: f32) -> String {
+    if is_divider_line(line) {
+        return String::new();
+    }
+    let spans = label.spans.get(idx).filter(|spans| {
+        spans
+            .iter()
+            .map(|span| span.text.as_str())
+            .collect::<String>()
+            == line
+    });
+    let Some(spans) = spans else {
+        return escape_xml(line);
+    };
+    let mut out = String::new();
+    for span in spans {
+        let text = escape_xml(&span.text);
+        let mut attrs = String::new();
+        if span.bold {
+            attrs.push_str(" font-weight=\"bold\"");
+        }
+        if span.italic {
+            attrs.push_str(" font-style=\"italic\"");
+        }
+        if span.small {
+            attrs.push_str(&format!(
+                " font-size=\"{:.1}\"",
+                font_size * crate::layout::SMALL_TEXT_SCALE
+            ));
+        }
+        if attrs.is_empty() {
+            out.push_str(&text);
+        } else {
+            out.push_str(&format!("<tspan{attrs}>{text}</tspan>"));
+        }
+    }
+    out
+}
+
 fn text_line_svg_with_font_size(
     x: f32,
     y: f32,
@@ -4727,17 +5876,41 @@ fn c4_shape_font_weight(conf: &crate::config::C4Config, kind: crate::ir::C4Shape
     }
 }
 
+/// Anchor x and `text-anchor` for a label block centered on `center_x`,
+/// justified per [`LayoutConfig::label_align`]. Only the text moves; the
+/// block keeps its measured footprint.
+fn aligned_label_anchor(
+    center_x: f32,
+    label: &TextBlock,
+    config: &LayoutConfig,
+) -> (f32, &'static str) {
+    match config.label_align {
+        Some(LabelAlign::Left) => (center_x - label.width / 2.0, "start"),
+        Some(LabelAlign::Right) => (center_x + label.width / 2.0, "end"),
+        Some(LabelAlign::Center) | None => (center_x, "middle"),
+    }
+}
+
+/// Label split into a centered title and left-aligned member sections at its
+/// `---` divider lines. `class_header` styles leading `«…»` title lines as
+/// class stereotypes.
 fn text_block_svg_class(
     node: &crate::layout::NodeLayout,
     theme: &Theme,
     config: &LayoutConfig,
     override_color: Option<&str>,
+    class_header: bool,
 ) -> String {
     let line_height = theme.font_size * config.class_label_line_height();
     let total_height = node.label.lines.len() as f32 * line_height;
     let start_y = node.y + node.height / 2.0 - total_height / 2.0 + theme.font_size;
     let center_x = node.x + node.width / 2.0;
-    let left_x = node.x + config.node_padding_x.max(10.0);
+    let member_inset = config.node_padding_x.max(10.0);
+    let (member_x, member_anchor) = match config.label_align {
+        Some(LabelAlign::Center) => (center_x, "middle"),
+        Some(LabelAlign::Right) => (node.x + node.width - member_inset, "end"),
+        Some(LabelAlign::Left) | None => (node.x + member_inset, "start"),
+    };
     let fill = override_color.unwrap_or(theme.primary_text_color.as_str());
 
     let Some(divider_idx) = node
@@ -4753,15 +5926,17 @@ fn text_block_svg_class(
             .enumerate()
             .map(|(idx, line)| (idx, line.as_str()))
             .collect();
+        let (label_x, anchor) = aligned_label_anchor(center_x, &node.label, config);
         return text_lines_svg(
             &lines,
-            center_x,
+            label_x,
             start_y,
             line_height,
-            "middle",
+            anchor,
             theme,
             fill,
             false,
+            class_header,
         );
     };
 
@@ -4789,18 +5964,20 @@ fn text_block_svg_class(
             theme,
             fill,
             true,
+            class_header,
         ));
     }
     if !member_lines.is_empty() {
         svg.push_str(&text_lines_svg(
             &member_lines,
-            left_x,
+            member_x,
             start_y,
             line_height,
-            "start",
+            member_anchor,
             theme,
             fill,
             false,
+            false,
         ));
     }
     svg
@@ -4865,6 +6042,7 @@ fn render_er_node_label(
             theme,
             fill,
             true,
+            false,
         ));
         svg.push_str(&format!(
             "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"1\" stroke-opacity=\"0.35\"/>",
@@ -4964,6 +6142,7 @@ fn render_er_node_label(
                 theme,
                 fill,
                 false,
+                false,
             ));
         }
     }
@@ -4980,6 +6159,7 @@ fn text_lines_svg(
     theme: &Theme,
     fill: &str,
     bold_first: bool,
+    stereotypes: bool,
 ) -> String {
     let Some((first_idx, _)) = lines.first() else {
         return String::new();
@@ -4993,6 +6173,19 @@ fn text_lines_svg(
         fill
     ));
 
+    // Class stereotypes (`«interface»`) sit above the name in italics; the
+    // name of an abstract class is italicized as well.
+    let name_pos = if stereotypes {
+        lines
+            .iter()
+            .position(|(_, line)| !is_stereotype_line(line))
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let is_abstract = lines[..name_pos]
+        .iter()
+        .any(|(_, line)| line.trim().eq_ignore_ascii_case("«abstract»"));
     let mut prev_idx = *first_idx;
     for (pos, (idx, line)) in lines.iter().enumerate() {
         let dy = if pos == 0 {
@@ -5000,13 +6193,18 @@ fn text_lines_svg(
         } else {
             (*idx - prev_idx) as f32 * line_height
         };
-        let weight = if pos == 0 && bold_first {
+        let weight = if pos == name_pos && bold_first {
             " font-weight=\"600\""
         } else {
             ""
         };
+        let style = if pos < name_pos || (pos == name_pos && is_abstract) {
+            " font-style=\"italic\""
+        } else {
+            ""
+        };
         text.push_str(&format!(
-            "<tspan x=\"{x:.2}\" dy=\"{dy:.2}\"{weight}>{}</tspan>",
+            "<tspan x=\"{x:.2}\" dy=\"{dy:.2}\"{weight}{style}>{}</tspan>",
             escape_xml(line)
         ));
         prev_idx = *idx;
@@ -5015,6 +6213,11 @@ fn text_lines_svg(
     text
 }
 
+fn is_stereotype_line(line: &str) -> bool {
+    let line = line.trim();
+    line.len() > "«»".len() && line.starts_with('«') && line.ends_with('»')
+}
+
 fn is_divider_line(line: &str) -> bool {
     line.trim() == "---"
 }
@@ -5216,6 +6419,7 @@ fn render_er_node(
         lines: vec![title.clone()],
         width: 0.0,
         height: 0.0,
+        spans: Vec::new(),
     };
     let header_y = y + header_height / 2.0;
     svg.push_str(&text_block_svg_anchor(
@@ -5354,6 +6558,7 @@ fn render_er_node(
             lines: vec![attr.name.clone()],
             width: 0.0,
             height: 0.0,
+            spans: Vec::new(),
         };
         svg.push_str(&text_block_svg_anchor(
             cursor_x,
@@ -5370,6 +6575,7 @@ fn render_er_node(
                 lines: vec![attr.data_type.clone()],
                 width: 0.0,
                 height: 0.0,
+                spans: Vec::new(),
             };
             svg.push_str(&text_block_svg_anchor(
                 x + w - pad_x,
@@ -5418,7 +6624,7 @@ pub fn write_output_png(
     let size = tree.size().to_int_size();
     let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
         .ok_or_else(|| anyhow::anyhow!("Failed to allocate pixmap"))?;
-    if let Some(color) = parse_hex_color(&theme.background) {
+    if let Some(color) = render_cfg.background_color(theme).and_then(parse_hex_color) {
         pixmap.fill(color);
     }
 
@@ -5432,6 +6638,56 @@ pub fn write_output_png(
     Ok(())
 }
 
+/// Insert `source` as an XML comment right after the opening `<svg>` tag,
+/// together with the crate version, as the `render*` functions do when
+/// [`RenderConfig::embed_source`] is set.
+pub fn embed_source_comment(svg: &str, source: &str) -> String {
+    let comment = source_comment(source);
+    match svg
+        .find("<svg")
+        .and_then(|start| svg[start..].find('>').map(|end| start + end + 1))
+    {
+        Some(pos) => format!("{}{comment}{}", &svg[..pos], &svg[pos..]),
+        None => format!("{comment}{svg}"),
+    }
+}
+
+/// `source` and the crate version as an XML comment. Runs of `-` are split
+/// with spaces since XML comments may not contain `--`.
+fn source_comment(source: &str) -> String {
+    let mut body = String::with_capacity(source.len() + 32);
+    body.push_str(concat!(
+        "mermaid-rs-renderer ",
+        env!("CARGO_PKG_VERSION"),
+        "\n"
+    ));
+    let mut prev_dash = false;
+    for ch in source.trim_end().chars() {
+        if ch == '-' && prev_dash {
+            body.push(' ');
+        }
+        prev_dash = ch == '-';
+        body.push(ch);
+    }
+    format!("<!--\n{body}\n-->")
+}
+
+/// Insert the layout as compact JSON in a `<metadata id="{id_prefix}-layout">`
+/// element right after the root's `<title>` and `<desc>`.
+#[cfg(feature = "serde")]
+fn embed_layout_metadata(svg: String, layout: &Layout, id_prefix: &str) -> String {
+    let Some((open_tag, body, _)) = svg_root_parts(&svg) else {
+        return svg;
+    };
+    let json = serde_json::to_string(layout).expect("layout geometry always serializes");
+    let json = json
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let (preamble, body) = split_svg_preamble(body);
+    format!("{open_tag}{preamble}<metadata id=\"{id_prefix}-layout\">{json}</metadata>{body}</svg>")
+}
+
 fn escape_xml(input: &str) -> String {
     input
         .replace('&', "&amp;")
@@ -5493,9 +6749,11 @@ fn edge_decoration_svg(
 ) -> String {
     let (x, y) = point;
     let mut angle = angle_deg;
-    if matches!(
+    // Diamonds and crow's-foot glyphs are drawn pointing away from the
+    // node along +x; the end angle points into the node, so turn it round.
+    if !matches!(
         decoration,
-        crate::ir::EdgeDecoration::Diamond | crate::ir::EdgeDecoration::DiamondFilled
+        crate::ir::EdgeDecoration::Circle | crate::ir::EdgeDecoration::Cross
     ) && !at_start
     {
         angle += 180.0;
@@ -5524,29 +6782,166 @@ fn edge_decoration_svg(
                 points, stroke, stroke, stroke_width
             )
         }
-        // Crow's foot notation for ER diagrams
+        // Crow's foot notation for ER diagrams: the foot's toes touch the
+        // entity, and the tick (one) or circle (zero) sits beyond it.
         crate::ir::EdgeDecoration::CrowsFootOne => format!(
-            "<path d=\"M 0 -6 L 0 6 M 5 -6 L 5 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/>",
+            "<path d=\"M 6 -6 L 6 6 M 11 -6 L 11 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/>",
             stroke, stroke_width
         ),
         crate::ir::EdgeDecoration::CrowsFootZeroOne => format!(
-            "<g><circle cx=\"-4\" cy=\"0\" r=\"4\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/><path d=\"M 4 -6 L 4 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/></g>",
+            "<g><path d=\"M 6 -6 L 6 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/><circle cx=\"15\" cy=\"0\" r=\"4\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/></g>",
             stroke, stroke_width, stroke, stroke_width
         ),
         crate::ir::EdgeDecoration::CrowsFootMany => format!(
-            "<path d=\"M 0 -6 L 0 6 M 0 0 L 8 -6 M 0 0 L 8 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/>",
+            "<path d=\"M 0 -6 L 12 0 L 0 6 M 15 -6 L 15 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/>",
             stroke, stroke_width
         ),
         crate::ir::EdgeDecoration::CrowsFootZeroMany => format!(
-            "<g><circle cx=\"-4\" cy=\"0\" r=\"4\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/><path d=\"M 4 0 L 12 -6 M 4 0 L 12 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/></g>",
+            "<g><path d=\"M 0 -6 L 12 0 L 0 6\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{join}/><circle cx=\"17\" cy=\"0\" r=\"4\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/></g>",
             stroke, stroke_width, stroke, stroke_width
         ),
     };
     format!("<g transform=\"translate({x:.2} {y:.2}) rotate({angle:.2})\">{shape}</g>")
 }
 
+fn edge_midpoint_marker_svg(
+    point: (f32, f32),
+    marker: &str,
+    stroke: &str,
+    stroke_width: f32,
+) -> String {
+    let (x, y) = point;
+    let join = " stroke-linejoin=\"round\" stroke-linecap=\"round\"";
+    let shape = match marker {
+        "square" => format!(
+            "<rect x=\"-4\" y=\"-4\" width=\"8\" height=\"8\" fill=\"{stroke}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"/>"
+        ),
+        "circle" => format!(
+            "<circle cx=\"0\" cy=\"0\" r=\"4\" fill=\"{stroke}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"/>"
+        ),
+        "diamond" => format!(
+            "<polygon points=\"0,-5 5,0 0,5 -5,0\" fill=\"{stroke}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"{join}/>"
+        ),
+        "cross" => format!(
+            "<path d=\"M -4 -4 L 4 4 M -4 4 L 4 -4\" fill=\"none\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"{join}/>"
+        ),
+        _ => return String::new(),
+    };
+    format!("<g class=\"edgeMidpointMarker\" transform=\"translate({x:.2} {y:.2})\">{shape}</g>")
+}
+
+/// Stroke width edges of `kind` are drawn with when no `linkStyle`
+/// overrides it.
+fn base_edge_width(kind: crate::ir::DiagramKind) -> f32 {
+    match kind {
+        crate::ir::DiagramKind::Sequence | crate::ir::DiagramKind::ZenUML => 1.5,
+        crate::ir::DiagramKind::Architecture => 3.0,
+        crate::ir::DiagramKind::Class
+        | crate::ir::DiagramKind::State
+        | crate::ir::DiagramKind::Er => 1.0,
+        _ => 2.0,
+    }
+}
+
+/// How much to enlarge `edge`'s arrowhead markers: proportional to a
+/// stroke width thicker than the kind's default, never shrinking them.
+fn marker_scale(edge: &crate::layout::EdgeLayout, kind: crate::ir::DiagramKind) -> f32 {
+    let Some(width) = edge.override_style.stroke_width else {
+        return 1.0;
+    };
+    let scale = (width / base_edge_width(kind)).max(1.0);
+    (scale * 100.0).round() / 100.0
+}
+
+fn edge_marker_color_idx(
+    edge: &crate::layout::EdgeLayout,
+    color_ids: &HashMap<String, usize>,
+) -> usize {
+    edge.override_style
+        .stroke
+        .as_ref()
+        .and_then(|color| color_ids.get(color))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Suffix of the marker ids for a color index and scale; unscaled markers
+/// keep the plain color index.
+fn marker_id(color_idx: usize, scale: f32) -> String {
+    if scale > 1.0 {
+        format!("{color_idx}-x{}", (scale * 100.0).round() as u32)
+    } else {
+        color_idx.to_string()
+    }
+}
+
+/// Arrowhead `<marker>` defs for one edge color, enlarged by `scale`.
+/// Every marker id ends in `id`.
+fn arrow_marker_defs(
+    id: &str,
+    color: &str,
+    scale: f32,
+    is_sequence: bool,
+    is_state: bool,
+    is_class: bool,
+) -> String {
+    let dim = |size: f32| (size * scale * 100.0).round() / 100.0;
+    let (size_8, size_12, size_14, size_20) = (dim(8.0), dim(12.0), dim(14.0), dim(20.0));
+    let idx = id;
+    let mut defs = String::new();
+    defs.push_str(&format!(
+        "<marker id=\"arrow-{idx}\" viewBox=\"0 0 10 10\" refX=\"5\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_8}\" markerHeight=\"{size_8}\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+        color, color
+    ));
+    defs.push_str(&format!(
+        "<marker id=\"arrow-start-{idx}\" viewBox=\"0 0 10 10\" refX=\"4.5\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_8}\" markerHeight=\"{size_8}\" orient=\"auto\"><path d=\"M 0 5 L 10 10 L 10 0 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+        color, color
+    ));
+    if is_sequence {
+        defs.push_str(&format!(
+            "<marker id=\"arrow-seq-{idx}\" viewBox=\"-1 0 12 10\" refX=\"7.9\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_12}\" markerHeight=\"{size_12}\" orient=\"auto-start-reverse\"><path d=\"M -1 0 L 10 5 L 0 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color,
+            color
+        ));
+        defs.push_str(&format!(
+            "<marker id=\"arrow-seq-open-{idx}\" viewBox=\"-1 0 12 10\" refX=\"9\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_12}\" markerHeight=\"{size_12}\" orient=\"auto-start-reverse\"><path d=\"M 1 0.5 L 10 5 L 1 9.5\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" stroke-dasharray=\"1,0\"/></marker>",
+            color
+        ));
+        defs.push_str(&format!(
+            "<marker id=\"arrow-start-seq-{idx}\" viewBox=\"-1 0 12 10\" refX=\"2.1\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_12}\" markerHeight=\"{size_12}\" orient=\"auto\"><path d=\"M 11 0 L 0 5 L 11 10 z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color,
+            color
+        ));
+    }
+    if is_state {
+        defs.push_str(&format!(
+            "<marker id=\"arrow-state-{idx}\" viewBox=\"0 0 20 14\" refX=\"19\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_20}\" markerHeight=\"{size_14}\" orient=\"auto\"><path d=\"M 19 7 L 9 13 L 14 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color, color
+        ));
+    }
+    if is_class {
+        defs.push_str(&format!(
+            "<marker id=\"arrow-class-open-{idx}\" viewBox=\"0 0 20 14\" refX=\"1\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_20}\" markerHeight=\"{size_14}\" orient=\"auto\"><path d=\"M 1 7 L 18 13 V 1 Z\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color
+        ));
+        defs.push_str(&format!(
+            "<marker id=\"arrow-class-open-start-{idx}\" viewBox=\"0 0 20 14\" refX=\"18\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_20}\" markerHeight=\"{size_14}\" orient=\"auto\"><path d=\"M 1 7 L 18 13 V 1 Z\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color
+        ));
+        defs.push_str(&format!(
+            "<marker id=\"arrow-class-dep-{idx}\" viewBox=\"0 0 20 14\" refX=\"13\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_20}\" markerHeight=\"{size_14}\" orient=\"auto\"><path d=\"M 18 7 L 9 13 L 14 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color, color
+        ));
+        defs.push_str(&format!(
+            "<marker id=\"arrow-class-dep-start-{idx}\" viewBox=\"0 0 20 14\" refX=\"6\" refY=\"7\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{size_20}\" markerHeight=\"{size_14}\" orient=\"auto\"><path d=\"M 5 7 L 9 13 L 1 7 L 9 1 Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-dasharray=\"1,0\"/></marker>",
+            color, color
+        ));
+    }
+    defs
+}
+
 fn arrowhead_svg(point: (f32, f32), angle_deg: f32, stroke: &str, stroke_width: f32) -> String {
-    let size = (stroke_width * 2.2 + 6.0).clamp(6.0, 14.0);
+    let size = (stroke_width * 2.2 + 6.0).max(6.0);
     let half = size * 0.6;
     let (x, y) = point;
     let join = " stroke-linejoin=\"round\" stroke-linecap=\"round\"";
@@ -5558,6 +6953,35 @@ fn arrowhead_svg(point: (f32, f32), angle_deg: f32, stroke: &str, stroke_width:
     )
 }
 
+/// Angle of the path segment closest to `anchor`, folded into `(-90, 90]` so
+/// rotated text never reads upside down. `None` for (near) horizontal runs.
+fn edge_label_rotation(points: &[(f32, f32)], anchor: (f32, f32)) -> Option<f32> {
+    let mut best: Option<(f32, f32)> = None;
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let dx = b.0 - a.0;
+        let dy = b.1 - a.1;
+        let len_sq = dx * dx + dy * dy;
+        if len_sq <= 1e-6 {
+            continue;
+        }
+        let t = (((anchor.0 - a.0) * dx + (anchor.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0);
+        let px = a.0 + dx * t - anchor.0;
+        let py = a.1 + dy * t - anchor.1;
+        let dist = px * px + py * py;
+        if best.is_none_or(|(best_dist, _)| dist < best_dist) {
+            best = Some((dist, dy.atan2(dx).to_degrees()));
+        }
+    }
+    let (_, mut angle) = best?;
+    if angle > 90.0 {
+        angle -= 180.0;
+    } else if angle <= -90.0 {
+        angle += 180.0;
+    }
+    (angle.abs() > 0.5).then_some(angle)
+}
+
 fn edge_endpoint_angle(points: &[(f32, f32)], start: bool) -> f32 {
     if points.len() < 2 {
         return 0.0;
@@ -5899,6 +7323,117 @@ fn shape_svg(node: &crate::layout::NodeLayout, theme: &Theme, config: &LayoutCon
                 node.style.stroke_width.unwrap_or(1.0)
             )
         }
+        crate::ir::NodeShape::Document | crate::ir::NodeShape::LinedDocument => {
+            let amp = h * 0.08;
+            let base = y + h - amp;
+            let path = format!(
+                "M{:.2} {:.2} H{:.2} V{:.2} Q{:.2} {:.2} {:.2} {:.2} Q{:.2} {:.2} {:.2} {:.2} Z",
+                x,
+                y,
+                x + w,
+                base,
+                x + w * 0.75,
+                base - amp * 2.0,
+                x + w * 0.5,
+                base,
+                x + w * 0.25,
+                base + amp * 2.0,
+                x,
+                base
+            );
+            let stroke_width = node.style.stroke_width.unwrap_or(1.0);
+            let mut svg = format!(
+                "<path d=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{dash}{join}/>",
+                path, fill, stroke, stroke_width
+            );
+            if node.shape == crate::ir::NodeShape::LinedDocument {
+                let line_x = x + (w * 0.08).min(10.0);
+                svg.push_str(&format!(
+                    "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    line_x,
+                    y,
+                    line_x,
+                    base + amp,
+                    stroke,
+                    stroke_width
+                ));
+            }
+            svg
+        }
+        crate::ir::NodeShape::Brace | crate::ir::NodeShape::Braces => {
+            // Only the braces are stroked; the body stays open like Mermaid's comment shape.
+            let depth = (w * 0.08).clamp(4.0, 12.0);
+            let curl = (h * 0.12).min(depth * 1.5);
+            let brace = |edge: f32, dir: f32| {
+                let spine = edge + dir * depth * 0.5;
+                let cy = y + h / 2.0;
+                format!(
+                    "M{:.2} {:.2} Q{:.2} {:.2} {:.2} {:.2} L{:.2} {:.2} Q{:.2} {:.2} {:.2} {:.2} Q{:.2} {:.2} {:.2} {:.2} L{:.2} {:.2} Q{:.2} {:.2} {:.2} {:.2}",
+                    edge + dir * depth,
+                    y,
+                    spine,
+                    y,
+                    spine,
+                    y + curl,
+                    spine,
+                    cy - curl,
+                    spine,
+                    cy,
+                    edge,
+                    cy,
+                    spine,
+                    cy,
+                    spine,
+                    cy + curl,
+                    spine,
+                    y + h - curl,
+                    spine,
+                    y + h,
+                    edge + dir * depth,
+                    y + h
+                )
+            };
+            let mut path = brace(x, 1.0);
+            if node.shape == crate::ir::NodeShape::Braces {
+                path.push(' ');
+                path.push_str(&brace(x + w, -1.0));
+            }
+            format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"none\"/><path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{dash}{join}/>",
+                x,
+                y,
+                w,
+                h,
+                fill,
+                path,
+                stroke,
+                node.style.stroke_width.unwrap_or(1.0)
+            )
+        }
+        crate::ir::NodeShape::Bolt => {
+            const BOLT: [(f32, f32); 7] = [
+                (0.62, 0.0),
+                (0.18, 0.56),
+                (0.46, 0.56),
+                (0.34, 1.0),
+                (0.84, 0.40),
+                (0.56, 0.40),
+                (0.72, 0.0),
+            ];
+            let mut path = String::new();
+            for (idx, (px, py)) in BOLT.iter().enumerate() {
+                let cmd = if idx == 0 { 'M' } else { 'L' };
+                path.push_str(&format!("{cmd}{:.2} {:.2} ", x + w * px, y + h * py));
+            }
+            path.push('Z');
+            format!(
+                "<path d=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{dash}{join}/>",
+                path,
+                fill,
+                stroke,
+                node.style.stroke_width.unwrap_or(1.0)
+            )
+        }
         crate::ir::NodeShape::MindmapDefault => {
             let rd = config
                 .mindmap
@@ -5951,7 +7486,7 @@ fn shape_svg(node: &crate::layout::NodeLayout, theme: &Theme, config: &LayoutCon
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::LayoutConfig;
+    use crate::config::{BackgroundMode, LayoutConfig};
     use crate::ir::{Direction, Graph};
     use crate::layout::compute_layout;
 
@@ -5993,6 +7528,1212 @@ mod tests {
         assert!(svg.contains("data-label-kind=\"center\""));
     }
 
+    #[test]
+    fn render_node_and_edge_opacity() {
+        let input = "flowchart LR\nA --> B\nstyle A opacity:0.3\nlinkStyle 0 opacity:0.5";
+        let parsed = crate::parser::parse_mermaid(input).unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let svg = render_svg(&layout, &Theme::modern(), &LayoutConfig::default());
+        assert!(svg.contains("<g opacity=\"0.3\">"));
+        assert!(svg.contains("<g opacity=\"0.5\">"));
+        assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+    }
+
+    #[test]
+    fn render_interactive_edge_groups_reference_endpoints() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA --> B").unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let render_config = RenderConfig {
+            interactive: true,
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(
+            &layout,
+            &Theme::modern(),
+            &LayoutConfig::default(),
+            &render_config,
+        );
+        assert!(svg.contains(
+            "<g class=\"edge\" data-edge-id=\"edge-0\" data-source=\"A\" data-target=\"B\">"
+        ));
+        assert!(svg.contains("<g class=\"node\" data-node-id=\"A\">"));
+        assert!(svg.contains("<g class=\"node\" data-node-id=\"B\">"));
+
+        let plain = render_svg(&layout, &Theme::modern(), &LayoutConfig::default());
+        assert!(!plain.contains("data-source="));
+    }
+
+    fn viewbox(svg: &str) -> Vec<f32> {
+        let start = svg.find("viewBox=\"").unwrap() + "viewBox=\"".len();
+        let end = svg[start..].find('"').unwrap() + start;
+        svg[start..end]
+            .split_whitespace()
+            .map(|part| part.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn render_clip_to_viewbox_wraps_content() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA --> B").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let mut layout = compute_layout(&parsed.graph, &theme, &config);
+        let overflow = (layout.width + 200.0, layout.height + 200.0);
+        layout.edges[0].points.push(overflow);
+
+        let plain = render_svg(&layout, &theme, &config);
+        assert!(!plain.contains("clip-path="));
+
+        let render_config = RenderConfig {
+            clip_to_viewbox: true,
+            id_prefix: Some("doc".to_string()),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let vb = viewbox(&svg);
+        let clip = format!(
+            "<clipPath id=\"doc-viewbox-clip\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath><g clip-path=\"url(#doc-viewbox-clip)\">",
+            vb[0], vb[1], vb[2], vb[3]
+        );
+        let open_end = svg.find('>').unwrap() + 1;
+        assert!(svg[open_end..].starts_with(&format!("<title>Flowchart</title>{clip}")));
+        assert!(svg.ends_with("</g></svg>"));
+        assert!(svg.contains(&format!("{:.3},{:.3}", overflow.0, overflow.1)));
+    }
+
+    #[test]
+    fn render_class_stereotype_above_name() {
+        let parsed = crate::parser::parse_mermaid(
+            "classDiagram\nclass Shape {\n<<interface>>\n+area() float\n}",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let stereotype = svg
+            .find("font-style=\"italic\">«interface»</tspan>")
+            .expect("italic stereotype");
+        let name = svg.find("font-weight=\"600\">Shape</tspan>").unwrap();
+        assert!(stereotype < name);
+        let name_dy = &svg[..name];
+        let name_dy = &name_dy[name_dy.rfind("dy=\"").unwrap() + 4..];
+        let name_dy: f32 = name_dy[..name_dy.find('"').unwrap()].parse().unwrap();
+        assert!(name_dy > 0.0);
+
+        // Only a class's own stereotype is italic, not `«…»` text elsewhere.
+        for input in [
+            "classDiagram\nclass Shape {\n«note»\n+area() float\n}",
+            "flowchart LR\nA[\"«quoted»<br>---<br>body\"]",
+        ] {
+            let parsed = crate::parser::parse_mermaid(input).unwrap();
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let svg = render_svg(&layout, &theme, &config);
+            assert!(!svg.contains("font-style=\"italic\""), "{input}");
+        }
+    }
+
+    #[test]
+    fn render_rotated_edge_labels_follow_steep_edges() {
+        let parsed = crate::parser::parse_mermaid("flowchart TD\nA -->|a long label| B").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(!svg.contains("data-label-kind=\"center\" transform="));
+
+        config.flowchart.rotate_edge_labels = true;
+        let svg = render_svg(&layout, &theme, &config);
+        let (x, y) = layout.edges[0].label_anchor.unwrap();
+        let group = format!(
+            "<g class=\"edgeLabel\" data-edge-id=\"{}\" data-label-kind=\"center\" transform=\"rotate(90.00 {x:.2} {y:.2})\">",
+            edge_dom_id(0)
+        );
+        assert!(svg.contains(&group), "missing {group}");
+
+        // Upward and leftward runs flip so the text stays upright.
+        let upward = edge_label_rotation(&[(0.0, 100.0), (0.0, 0.0)], (0.0, 50.0)).unwrap();
+        assert!((upward - 90.0).abs() < 0.01);
+        let diagonal = edge_label_rotation(&[(100.0, 100.0), (0.0, 0.0)], (50.0, 50.0)).unwrap();
+        assert!((diagonal - 45.0).abs() < 0.01);
+        assert_eq!(
+            edge_label_rotation(&[(100.0, 0.0), (0.0, 0.0)], (50.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn render_min_and_max_size() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let node = &layout.nodes["A"];
+        let render_config = RenderConfig {
+            min_size: Some((400.0, 300.0)),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let vb = viewbox(&svg);
+        assert!(svg.contains("width=\"400\" height=\"300\""));
+        assert_eq!((vb[2], vb[3]), (400.0, 300.0));
+        let node_cx = node.x + node.width / 2.0 - vb[0];
+        let node_cy = node.y + node.height / 2.0 - vb[1];
+        assert!((node_cx - 200.0).abs() < 1.0);
+        assert!((node_cy - 150.0).abs() < 1.0);
+
+        let render_config = RenderConfig {
+            max_size: Some((layout.width / 2.0, layout.height)),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        assert!(svg.contains(&format!("width=\"{}\"", layout.width / 2.0)));
+        assert_eq!(viewbox(&svg)[2], layout.width);
+    }
+
+    #[test]
+    fn render_sequence_async_message_uses_open_arrowhead() {
+        let source = "sequenceDiagram\nA->>B: sync\nA-)B: async";
+        let parsed = crate::parser::parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(svg.contains("<marker id=\"arrow-seq-open-"));
+        let open = svg.matches("marker-end=\"url(#arrow-seq-open-").count();
+        let filled = svg.matches("marker-end=\"url(#arrow-seq-").count() - open;
+        assert_eq!(open, 1);
+        assert_eq!(filled, 1);
+    }
+
+    #[test]
+    fn render_state_history_as_circled_h() {
+        let source = "stateDiagram-v2\nstate Active {\n[*] --> Idle\nIdle --> Busy\nBusy --> [H]\n[H] --> Idle\n}";
+        let parsed = crate::parser::parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let node = &layout.nodes["__history_Active__"];
+        assert_eq!(node.width, node.height);
+        assert!(node.width < layout.nodes["Idle"].height * 2.0);
+        let svg = render_svg(&layout, &theme, &config);
+        let cx = node.x + node.width / 2.0;
+        let circle = format!(
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\"",
+            cx,
+            node.y + node.height / 2.0
+        );
+        let at = svg.find(&circle).expect("history circle");
+        let label = &svg[at..];
+        assert!(label.contains(&format!("<tspan x=\"{cx:.2}\" dy=\"0.00\">H</tspan>")));
+        assert_eq!(
+            layout
+                .edges
+                .iter()
+                .filter(|edge| edge.from == node.id || edge.to == node.id)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn render_curve_tension_moves_control_points() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart TD\nsubgraph S\nB\nend\nA --> B\nA --> C\nC --> B",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let idx = layout
+            .edges
+            .iter()
+            .position(|edge| dedupe_points(&edge.points).len() >= 3)
+            .expect("a bent edge");
+        let path_for = |config: &LayoutConfig| {
+            let svg = render_svg(&layout, &theme, config);
+            let marker = format!("id=\"{}\"", edge_dom_id(idx));
+            let at = svg.find(&marker).unwrap();
+            let d = &svg[at..];
+            let d = &d[d.find(" d=\"").unwrap() + 4..];
+            d[..d.find('"').unwrap()].to_string()
+        };
+        let straight = path_for(&config);
+        assert!(!straight.contains(" C "));
+        config.flowchart.routing.curve_tension = 0.0;
+        let round = path_for(&config);
+        config.flowchart.routing.curve_tension = 0.5;
+        let tighter = path_for(&config);
+        assert!(round.contains(" C ") && tighter.contains(" C "));
+        assert_ne!(round, tighter);
+    }
+
+    #[test]
+    fn render_requirement_fields_in_separate_compartments() {
+        let parsed = crate::parser::parse_mermaid(
+            "requirementDiagram\nrequirement r {\nid: 1\nrisk: high\n}",
+        )
+        .unwrap();
+        let node = &parsed.graph.nodes["r"];
+        assert_eq!(node.fields, vec!["ID: 1", "Risk: High"]);
+        assert!(!node.label.contains("---"));
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let svg = render_svg(
+            &compute_layout(&parsed.graph, &theme, &config),
+            &theme,
+            &config,
+        );
+        let parse_attr = |tag: &str, name: &str| -> Option<f32> {
+            let marker = format!(" {name}=\"");
+            let rest = &tag[tag.find(&marker)? + marker.len()..];
+            rest[..rest.find('"')?].parse().ok()
+        };
+        let text_y = |text: &str| {
+            let end = svg.find(&format!(">{text}</text>")).unwrap();
+            let start = svg[..end].rfind("<text").unwrap();
+            parse_attr(&svg[start..end], "y").unwrap()
+        };
+        let at = svg.find("class=\"requirement-compartment\"").unwrap();
+        let divider_y = parse_attr(&svg[at..], "y1").unwrap();
+        assert_eq!(svg.matches("requirement-compartment").count(), 1);
+        assert!(text_y("ID: 1") < divider_y);
+        assert!(divider_y < text_y("Risk: High"));
+        assert!(!svg.contains(">---<"));
+
+        config.requirement.field_compartments = false;
+        let plain = render_svg(
+            &compute_layout(&parsed.graph, &theme, &config),
+            &theme,
+            &config,
+        );
+        assert!(!plain.contains("requirement-compartment"));
+        assert!(plain.contains(">Risk: High</text>"));
+    }
+
+    #[test]
+    fn render_basis_curve_draws_cubic_edges() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart TD\nsubgraph S\nB\nend\nA --> B\nA --> C\nC --> B",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        assert!(!render_svg(&layout, &theme, &config).contains(" C "));
+        config.flowchart.edge_curve = crate::config::EdgeCurve::Basis;
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(svg.contains(" C "));
+
+        let points = [(0.0, 0.0), (0.0, 50.0), (40.0, 50.0), (40.0, 100.0)];
+        let (d, start, end) = basis_path_with_angles(&points);
+        assert_eq!(d.matches(" C ").count(), 3);
+        assert!((start - 90.0).abs() < 1e-3);
+        assert!((end - 90.0).abs() < 1e-3);
+        let (_, _, bent_end) = basis_path_with_angles(&[(0.0, 0.0), (40.0, 0.0), (40.0, 40.0)]);
+        assert!((bent_end - 90.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn render_mirror_reflects_root_group() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart LR\naccDescr: Left to right\nA[Left] --> B[Right]",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let plain = render_svg(&layout, &theme, &config);
+        let [x, y, w, h] = viewbox(&plain)[..] else {
+            panic!("expected four viewBox fields");
+        };
+        let mut render_config = RenderConfig {
+            mirror: Some(crate::config::Axis::Horizontal),
+            ..Default::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let open_end = svg.find('>').unwrap() + 1;
+        let expected = format!(
+            "<g class=\"mirror\" transform=\"translate({:.2} 0) scale(-1 1)\">",
+            2.0 * x + w
+        );
+        // Title and description stay direct children of the root.
+        let id_prefix = document_id_prefix(&layout, &render_config);
+        let preamble =
+            format!("<title>Flowchart</title><desc id=\"{id_prefix}-desc\">Left to right</desc>");
+        assert!(svg[open_end..].starts_with(&format!("{preamble}{expected}")));
+        assert!(svg.ends_with("</g></svg>"));
+        assert_eq!(viewbox(&svg), vec![x, y, w, h]);
+
+        render_config.mirror = Some(crate::config::Axis::Vertical);
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        assert!(svg.contains(&format!(
+            "transform=\"translate(0 {:.2}) scale(1 -1)\"",
+            2.0 * y + h
+        )));
+    }
+
+    #[test]
+    fn render_sequence_notes_stack_above_activations() {
+        let source =
+            "sequenceDiagram\nAlice->>+Bob: Hi\nNote over Bob: thinking hard\nBob-->>-Alice: Done";
+        let parsed = crate::parser::parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let note_fill = format!("fill=\"{}\"", theme.sequence_note_fill);
+        let order = |config: &LayoutConfig| {
+            let svg = render_svg(&layout, &theme, config);
+            let activation = svg
+                .find("<rect class=\"activation\"")
+                .expect("activation bar");
+            let note = svg.find(&note_fill).expect("note");
+            let lifeline = svg.find("stroke-width=\"0.5\"").expect("lifeline");
+            assert!(lifeline < activation);
+            (activation, note)
+        };
+        let (activation, note) = order(&config);
+        assert!(
+            note > activation,
+            "note should paint over the activation bar"
+        );
+        config.sequence.activation_z = crate::config::ActivationZ::AboveNotes;
+        let (activation, note) = order(&config);
+        assert!(activation > note);
+    }
+
+    #[test]
+    fn render_text_rendering_hint_on_labels() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA[Start] -->|go| B[End]").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        assert!(!render_svg(&layout, &theme, &config).contains("text-rendering"));
+        let render_config = RenderConfig {
+            text_rendering: Some(crate::config::TextRendering::GeometricPrecision),
+            ..Default::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let open_tag = &svg[..svg.find('>').unwrap()];
+        assert!(open_tag.contains(" text-rendering=\"geometricPrecision\""));
+        assert_eq!(svg.matches("text-rendering=").count(), 1);
+        let mut out = Vec::new();
+        render_svg_to_writer(&layout, &theme, &config, &render_config, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), svg);
+    }
+
+    #[test]
+    fn render_title_scale_enlarges_titles_over_labels() {
+        let parsed =
+            crate::parser::parse_mermaid("journey\ntitle My Day\nsection Work\nCode: 5: Me")
+                .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.labels.title_scale = 1.5;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let font_size_of = |text: &str| -> f32 {
+            let end = svg.find(&format!("\">{text}</tspan>")).unwrap();
+            let start = svg[..end].rfind("<text ").unwrap();
+            let tag = &svg[start..end];
+            let at = tag.find("font-size=\"").unwrap() + "font-size=\"".len();
+            let len = tag[at..].find('"').unwrap();
+            tag[at..at + len].parse().unwrap()
+        };
+        assert_eq!(font_size_of("My Day"), font_size_of("Code") * 1.5);
+
+        let DiagramData::Journey(scaled) = &layout.diagram else {
+            panic!("expected journey layout");
+        };
+        let base = compute_layout(&parsed.graph, &theme, &LayoutConfig::default());
+        let DiagramData::Journey(base) = &base.diagram else {
+            panic!("expected journey layout");
+        };
+        let (scaled, base) = (scaled.title.as_ref().unwrap(), base.title.as_ref().unwrap());
+        assert!(scaled.width > base.width && scaled.height > base.height);
+    }
+
+    #[test]
+    fn render_pill_edge_labels() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA -->|yes| B").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.flowchart.edge_label_shape = crate::config::LabelShape::Pill;
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let start = svg.find("class=\"edgeLabelPill\"").expect("pill badge");
+        let pill = &svg[start..start + svg[start..].find("/>").unwrap()];
+        let attr = |name: &str| -> f32 {
+            let at = pill.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+            pill[at..at + pill[at..].find('"').unwrap()]
+                .parse()
+                .unwrap()
+        };
+        assert!((attr("rx") - attr("height") / 2.0).abs() < 0.01);
+        let label = layout.edges[0].label.as_ref().unwrap();
+        assert!(attr("width") > label.width);
+        assert!(pill.contains(&format!("fill=\"{}\"", theme.primary_border_color)));
+        assert!(start < svg.find(">yes</tspan>").unwrap());
+
+        config.flowchart.edge_label_shape = crate::config::LabelShape::Text;
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(!svg.contains("edgeLabelPill"));
+        assert!(!svg.contains("data-label-kind=\"center\" x="));
+    }
+
+    #[test]
+    fn render_accessible_title_and_description() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let render = |input: &str| {
+            let parsed = crate::parser::parse_mermaid(input).unwrap();
+            render_svg(
+                &compute_layout(&parsed.graph, &theme, &config),
+                &theme,
+                &config,
+            )
+        };
+
+        let described_by = |svg: &str| {
+            let open_tag = &svg[..svg.find('>').unwrap()];
+            let (_, rest) = open_tag.split_once("aria-describedby=\"").unwrap();
+            rest[..rest.find('"').unwrap()].to_string()
+        };
+
+        let svg = render("flowchart LR\naccTitle: My Flow\naccDescr: Two <steps>\nA-->B");
+        let open_tag = &svg[..svg.find('>').unwrap()];
+        assert!(open_tag.contains("role=\"img\""));
+        let flow_desc = described_by(&svg);
+        assert!(flow_desc.starts_with("mmd-") && flow_desc.ends_with("-desc"));
+        assert!(svg.contains("<title>My Flow</title>"));
+        assert!(svg.contains(&format!(
+            "<desc id=\"{flow_desc}\">Two &lt;steps&gt;</desc>"
+        )));
+
+        let svg = render("sequenceDiagram\naccDescr {\n  Alice greets\n  Bob\n}\nAlice->>Bob: hi");
+        let seq_desc = described_by(&svg);
+        assert_ne!(seq_desc, flow_desc);
+        assert!(svg.contains("<title>Sequence diagram</title>"));
+        assert!(svg.contains(&format!("<desc id=\"{seq_desc}\">Alice greets\nBob</desc>")));
+        assert!(!svg.contains(">accDescr"));
+
+        let svg = render("pie\n\"Dogs\" : 3\n\"Cats\" : 2");
+        assert!(svg.contains("<title>Pie chart</title>"));
+        assert!(!svg.contains("aria-describedby"));
+    }
+
+    #[test]
+    fn render_css_variables_expose_theme_colors() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA-->B").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let plain = render_svg(&layout, &theme, &config);
+        assert!(!plain.contains("--mmd-"));
+
+        let render_config = RenderConfig {
+            css_variables: true,
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let style = &svg[svg.find("<style>").unwrap()..svg.find("</style>").unwrap()];
+        let id_prefix = document_id_prefix(&layout, &render_config);
+        assert!(svg.starts_with(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" id=\"{id_prefix}\""
+        )));
+        assert!(style.starts_with(&format!("<style>:where(#{id_prefix}) {{")));
+        assert!(style.contains(&format!("--mmd-node-fill: {};", theme.primary_color)));
+        assert!(style.contains(&format!("--mmd-line-color: {};", theme.line_color)));
+        let node_fill = format!("fill=\"var(--mmd-node-fill, {})\"", theme.primary_color);
+        assert_eq!(svg.matches(&node_fill).count(), 2);
+        assert!(svg.contains(&format!(
+            "stroke=\"var(--mmd-line-color, {})\"",
+            theme.line_color
+        )));
+
+        // Composite state headers and bodies keep their tints of the raw fill.
+        let parsed =
+            crate::parser::parse_mermaid("stateDiagram-v2\nstate Outer {\n  A --> B\n}").unwrap();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let header = adjust_color(&theme.primary_color, 0.0, 0.0, -4.0);
+        let body = adjust_color(&theme.primary_color, 0.0, -12.0, 10.0);
+        assert_ne!(header, body);
+        assert!(svg.contains(&format!("fill=\"{header}\"")));
+        assert!(svg.contains(&format!("fill=\"{body}\"")));
+    }
+
+    #[test]
+    fn render_integer_dimensions_rounds_width_and_height() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nA[Alpha]-->B[Beta gamma]").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let render_config = RenderConfig {
+            integer_dimensions: true,
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let attr = |name: &str| -> f32 {
+            let start = svg.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+            let end = start + svg[start..].find('"').unwrap();
+            svg[start..end].parse().unwrap()
+        };
+        let (width, height) = (attr("width"), attr("height"));
+        assert_eq!(width.fract(), 0.0);
+        assert_eq!(height.fract(), 0.0);
+        assert!(width >= layout.width && height >= layout.height);
+    }
+
+    #[test]
+    fn render_background_controls_canvas_fill() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA-->B").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let background_rect = format!("fill=\"{}\"/>", theme.background);
+        assert!(render_svg(&layout, &theme, &config).contains(&background_rect));
+
+        let transparent = RenderConfig {
+            background: Some(BackgroundMode::Transparent),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &transparent);
+        assert!(!svg.contains(&background_rect));
+
+        let solid = RenderConfig {
+            background: Some(BackgroundMode::Solid("#123456".to_string())),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &solid);
+        assert!(svg.contains("fill=\"#123456\"/>"));
+        assert!(!svg.contains(&background_rect));
+
+        // Theme and solid colors are escaped alike.
+        let mut odd_theme = theme.clone();
+        odd_theme.background = "a&b".to_string();
+        let svg = render_svg(&layout, &odd_theme, &config);
+        assert!(svg.contains("fill=\"a&amp;b\"/>"));
+
+        // Pie slices are stroked with the theme background; a solid canvas
+        // color must only replace the canvas rect.
+        let parsed = crate::parser::parse_mermaid("pie\n\"a\" : 1\n\"b\" : 2").unwrap();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let themed = render_svg(&layout, &theme, &config);
+        assert!(themed.matches(&theme.background).count() > 1);
+        let solid_svg = render_svg_with_config(&layout, &theme, &config, &solid);
+        assert_eq!(
+            solid_svg,
+            themed.replacen(&background_rect, "fill=\"#123456\"/>", 1)
+        );
+    }
+
+    #[test]
+    fn render_frame_draws_border_title_bar_and_insets_content() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA-->B").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let viewbox = |svg: &str| -> Vec<f32> {
+            let start = svg.find("viewBox=\"").unwrap() + 9;
+            let end = start + svg[start..].find('"').unwrap();
+            svg[start..end]
+                .split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect()
+        };
+        let plain = viewbox(&render_svg(&layout, &theme, &config));
+
+        let spec = FrameSpec {
+            title: Some("Checkout & pay".to_string()),
+            ..FrameSpec::default()
+        };
+        let render_config = RenderConfig {
+            frame: Some(spec.clone()),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &render_config);
+        assert!(svg.contains("<rect class=\"frame-border\""));
+        assert!(svg.contains(&format!("rx=\"{:.2}\"", spec.corner_radius)));
+        assert!(svg.contains("<path class=\"frame-title-bar\""));
+        assert!(svg.contains(">Checkout &amp; pay</text>"));
+        let framed = viewbox(&svg);
+        let inset = spec.padding + spec.stroke_width;
+        let title_height = theme.font_size * 2.0;
+        assert!((plain[0] - framed[0] - inset).abs() < 0.01);
+        assert!((plain[1] - framed[1] - inset - title_height).abs() < 0.01);
+        assert!((framed[2] - plain[2] - inset * 2.0).abs() < 0.01);
+        assert!((framed[3] - plain[3] - inset * 2.0 - title_height).abs() < 0.01);
+
+        let untitled = RenderConfig {
+            frame: Some(FrameSpec::default()),
+            ..RenderConfig::default()
+        };
+        let svg = render_svg_with_config(&layout, &theme, &config, &untitled);
+        assert!(svg.contains("frame-border"));
+        assert!(!svg.contains("frame-title"));
+    }
+
+    #[test]
+    fn render_flowchart_edge_legend_draws_samples_and_descriptions() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA-->B\nB-.->C").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let plain = render_svg(&layout, &theme, &config);
+        assert!(!plain.contains("edge-legend"));
+
+        config.flowchart.edge_legend = Some(vec![
+            (crate::ir::EdgeStyle::Solid, "Synchronous call".to_string()),
+            (crate::ir::EdgeStyle::Dotted, "Async event".to_string()),
+        ]);
+        let svg = render_svg(&layout, &theme, &config);
+        let legend = &svg[svg.find("<g class=\"edge-legend\">").unwrap()..];
+        assert_eq!(legend.matches("<line ").count(), 2);
+        assert_eq!(legend.matches("stroke-dasharray=\"2\"").count(), 1);
+        assert!(legend.contains(">Synchronous call</text>"));
+        assert!(legend.contains(">Async event</text>"));
+        assert!(viewbox(&svg)[3] > viewbox(&plain)[3]);
+
+        let render_config = RenderConfig {
+            max_size: Some((layout.width, layout.height)),
+            ..RenderConfig::default()
+        };
+        let limited = render_svg_with_config(&layout, &theme, &config, &render_config);
+        let open_tag = &limited[..limited.find('>').unwrap()];
+        let attr = |name: &str| -> f32 {
+            let (_, rest) = open_tag.split_once(&format!(" {name}=\"")).unwrap();
+            rest[..rest.find('"').unwrap()].parse().unwrap()
+        };
+        assert!(attr("width") <= layout.width + 0.01);
+        assert!(attr("height") <= layout.height + 0.01);
+        assert_eq!(viewbox(&limited), viewbox(&svg));
+    }
+
+    #[test]
+    fn render_markdown_label_emits_bold_and_italic_runs() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart LR\nA[\"**bold** and *italic*\"] --> B[plain * star]",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(svg.contains(
+            "<tspan font-weight=\"bold\">bold</tspan> and <tspan font-style=\"italic\">italic</tspan>"
+        ));
+        assert!(svg.contains(">plain * star</tspan>"));
+        assert!(!svg.contains("**bold**"));
+    }
+
+    #[test]
+    fn render_markdown_emphasis_is_limited_to_flowcharts() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        for input in [
+            "sequenceDiagram\nA->>B: compute a*b*c",
+            "erDiagram\nCUSTOMER ||--o{ ORDER : \"places *many*\"",
+        ] {
+            let parsed = crate::parser::parse_mermaid(input).unwrap();
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let svg = render_svg(&layout, &theme, &config);
+            assert!(!svg.contains("font-style=\"italic\""), "{input}");
+        }
+    }
+
+    #[test]
+    fn render_node_status_uses_theme_status_color() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart LR\nA[DB] --> B[API]\nA@{ status: error }\nstyle B fill:#123456",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let error_fill = format!("fill=\"{}\"", theme.status_colors.error);
+        assert_eq!(svg.matches(&error_fill).count(), 1);
+        assert!(svg.contains("fill=\"#123456\""));
+    }
+
+    #[test]
+    fn render_label_align_moves_text_but_not_nodes() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nA[\"short<br>a much longer line\"] --> B")
+                .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let centered = render_svg(&layout, &theme, &config);
+        assert!(!centered.contains("text-anchor=\"start\""));
+
+        config.label_align = Some(crate::config::LabelAlign::Left);
+        let aligned_layout = compute_layout(&parsed.graph, &theme, &config);
+        let node = &aligned_layout.nodes["A"];
+        assert_eq!(node.width, layout.nodes["A"].width);
+        assert_eq!(node.x, layout.nodes["A"].x);
+        let left = render_svg(&aligned_layout, &theme, &config);
+        let left_x = node.x + node.width / 2.0 - node.label.width / 2.0;
+        assert!(left.contains(&format!(
+            "<tspan x=\"{left_x:.2}\" dy=\"0.00\">short</tspan>"
+        )));
+
+        config.label_align = Some(crate::config::LabelAlign::Right);
+        let right = render_svg(&aligned_layout, &theme, &config);
+        assert!(right.contains("text-anchor=\"end\""));
+    }
+
+    #[test]
+    fn render_click_wraps_node_in_link_with_tooltip() {
+        let parsed = crate::parser::parse_mermaid(
+            "flowchart LR\nA-->B\nclick A \"https://example.com?a=1&b=2\" \"Go there\"",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let url = "https://example.com?a=1&amp;b=2";
+        assert!(svg.contains(&format!(
+            "<a href=\"{url}\" xlink:href=\"{url}\"><title>Go there</title>"
+        )));
+        assert_eq!(svg.matches("<a ").count(), 1);
+        assert_eq!(svg.matches("</a>").count(), 1);
+    }
+
+    #[test]
+    fn render_max_label_lines_truncates_with_tooltip() {
+        let text = "one two three four five six seven eight nine ten eleven twelve thirteen fourteen fifteen sixteen seventeen eighteen";
+        let parsed =
+            crate::parser::parse_mermaid(&format!("flowchart LR\nA[\"{text}\"] --> B[Short]"))
+                .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let unclamped = compute_layout(&parsed.graph, &theme, &config);
+        assert!(unclamped.nodes["A"].label.lines.len() > 2);
+
+        config.flowchart.max_label_lines = Some(2);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let node = &layout.nodes["A"];
+        assert_eq!(node.label.lines.len(), 2);
+        assert!(node.label.lines[1].ends_with('…'));
+        assert!(node.height < unclamped.nodes["A"].height);
+        assert!(layout.nodes["B"].tooltip.is_none());
+        let svg = render_svg(&layout, &theme, &config);
+        assert!(svg.contains(&format!("<title>{text}</title>")));
+        assert_eq!(svg.matches("<g><title>").count(), 1);
+    }
+
+    #[test]
+    fn render_v11_shapes_draw_distinct_paths() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let mut paths = Vec::new();
+        for shape in ["doc", "lin-doc", "brace", "braces", "bolt"] {
+            let parsed =
+                crate::parser::parse_mermaid(&format!("flowchart LR\nA@{{ shape: {shape} }}"))
+                    .unwrap();
+            let layout = compute_layout(&parsed.graph, &theme, &config);
+            let node = &layout.nodes["A"];
+            let svg = shape_svg(node, &theme, &config);
+            let start = svg.find("<path d=\"").expect("shape renders a path") + 9;
+            let end = start + svg[start..].find('"').unwrap();
+            paths.push((svg[start..end].to_string(), svg.contains("<line ")));
+        }
+        for (idx, path) in paths.iter().enumerate() {
+            assert!(paths[idx + 1..].iter().all(|other| other != path));
+        }
+        assert!(paths[1].1, "lined document draws its inner rule");
+        assert!(!paths[0].1);
+    }
+
+    #[test]
+    fn render_xychart_axis_titles_draw_fitted_lines() {
+        let parsed = crate::parser::parse_mermaid(
+            "xychart-beta\nx-axis \"Quarterly revenue by region and product line\" [a, b]\ny-axis \"Revenue in thousands of dollars\" 0 --> 100\nbar [10, 50]",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.labels.legend_max_width = Some(120.0);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::XYChart(ref chart) = layout.diagram else {
+            panic!("expected xychart layout");
+        };
+        let x_lines = chart.x_axis_label.as_ref().unwrap().lines.clone();
+        let y_lines = chart.y_axis_label.as_ref().unwrap().lines.clone();
+        assert!(x_lines.len() > 1 && y_lines.len() > 1);
+        let svg = render_svg(&layout, &theme, &config);
+        for line in x_lines.iter().chain(&y_lines) {
+            assert!(
+                svg.contains(&format!(">{}</tspan>", escape_xml(line))),
+                "{line}"
+            );
+        }
+        assert!(svg.contains("<g transform=\"rotate(-90,"));
+        let axis_size = theme.font_size * config.labels.axis_label_scale;
+        assert!(svg.contains(&format!("font-size=\"{axis_size}\"")));
+    }
+
+    #[test]
+    fn render_quadrant_y_axis_labels_draw_fitted_lines() {
+        let parsed = crate::parser::parse_mermaid(
+            "quadrantChart\nx-axis Low --> High\ny-axis Passive audience --> Active engagement level indicator\nquadrant-1 Grow",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.labels.legend_max_width = Some(80.0);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Quadrant(ref quadrant) = layout.diagram else {
+            panic!("expected quadrant layout");
+        };
+        let top_lines = quadrant.y_axis_top.as_ref().unwrap().lines.clone();
+        assert!(top_lines.len() > 1);
+        let svg = render_svg(&layout, &theme, &config);
+        for line in &top_lines {
+            assert!(
+                svg.contains(&format!(">{}</tspan>", escape_xml(line))),
+                "{line}"
+            );
+        }
+        assert!(svg.contains("indicator"));
+    }
+
+    #[test]
+    fn render_xychart_tick_count_and_gridlines() {
+        let parsed = crate::parser::parse_mermaid(
+            "xychart-beta\nx-axis [a, b, c, d, e, f]\ny-axis 0 --> 100\nbar [10, 50, 90, 20, 40, 60]",
+        )
+        .unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        config.xychart.y_tick_count = 5;
+        config.xychart.x_tick_count = Some(3);
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        assert_eq!(svg.matches("class=\"tick\"").count(), 5);
+        assert_eq!(svg.matches("class=\"gridline\"").count(), 5);
+        assert!(svg.find("class=\"gridline\"") < svg.find("fill=\"#4e79a7\""));
+        assert!(svg.contains(">c</text>") && !svg.contains(">b</text>"));
+
+        config.xychart.show_gridlines = false;
+        let svg = render_svg(&layout, &theme, &config);
+        assert_eq!(svg.matches("class=\"gridline\"").count(), 0);
+        assert_eq!(svg.matches("class=\"tick\"").count(), 5);
+    }
+
+    #[test]
+    fn render_title_and_small_subtitle_stack_in_one_node() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout_for = |label: &str| {
+            let source = format!("flowchart LR\nA[\"{label}\"]");
+            let parsed = crate::parser::parse_mermaid(&source).unwrap();
+            compute_layout(&parsed.graph, &theme, &config)
+        };
+        let plain = layout_for("**Title**<br/>subtitle");
+        let stacked = layout_for("**Title**<br/><small>subtitle</small>");
+        let line_height = theme.font_size * config.label_line_height;
+        let label = &stacked.nodes["A"].label;
+        assert!((label.height - line_height * 1.8).abs() < 0.01);
+        assert!(stacked.nodes["A"].height < plain.nodes["A"].height);
+
+        let svg = render_svg(&stacked, &theme, &config);
+        assert!(svg.contains("<tspan font-weight=\"bold\">Title</tspan>"));
+        let small_size = theme.font_size * crate::layout::SMALL_TEXT_SCALE;
+        assert!(svg.contains(&format!(
+            "dy=\"{:.2}\"><tspan font-size=\"{small_size:.1}\">subtitle</tspan>",
+            line_height * crate::layout::SMALL_TEXT_SCALE
+        )));
+    }
+
+    #[test]
+    fn render_emoji_label_keeps_glyph_and_widens_node() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout_for = |label: &str| {
+            let source = format!("flowchart LR\nA[\"{label} Launch\"]");
+            let parsed = crate::parser::parse_mermaid(&source).unwrap();
+            compute_layout(&parsed.graph, &theme, &config)
+        };
+        let plain = layout_for("x");
+        let emoji = layout_for("\u{1f680}");
+        assert!(emoji.nodes["A"].width > plain.nodes["A"].width);
+        let svg = render_svg(&emoji, &theme, &config);
+        assert!(svg.contains("\u{1f680} Launch"));
+    }
+
+    #[test]
+    fn render_thick_edge_references_larger_marker() {
+        fn marker_width(svg: &str, edge_id: &str) -> f32 {
+            let path = &svg[svg.find(&format!("<path id=\"{edge_id}\"")).unwrap()..];
+            let url = &path[path.find("marker-end=\"url(#").unwrap() + 17..];
+            let marker_id = &url[..url.find(')').unwrap()];
+            let marker = &svg[svg.find(&format!("<marker id=\"{marker_id}\"")).unwrap()..];
+            let width = &marker[marker.find("markerWidth=\"").unwrap() + 13..];
+            width[..width.find('"').unwrap()].parse().unwrap()
+        }
+
+        let parsed = crate::parser::parse_mermaid("stateDiagram-v2\nA --> B\nB --> C").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let mut layout = compute_layout(&parsed.graph, &theme, &config);
+        layout.edges[1].override_style.stroke_width = Some(4.0);
+        let svg = render_svg(&layout, &theme, &config);
+        let thin = marker_width(&svg, &edge_dom_id(0));
+        let thick = marker_width(&svg, &edge_dom_id(1));
+        assert!(thick > thin, "thick {thick} vs thin {thin}");
+    }
+
+    #[test]
+    fn render_er_cardinality_draws_crows_foot_facing_each_entity() {
+        let parsed =
+            crate::parser::parse_mermaid("erDiagram\nCUSTOMER ||--o{ ORDER : places").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let svg = render_svg(&layout, &theme, &config);
+        let edge = &layout.edges[0];
+        let end_angle = edge_endpoint_angle(&edge.points, false);
+
+        let rotation = |glyph: &str| -> f32 {
+            let idx = svg.find(glyph).unwrap();
+            let open = svg[..idx].rfind("rotate(").unwrap() + 7;
+            let close = open + svg[open..].find(')').unwrap();
+            svg[open..close].parse().unwrap()
+        };
+        let one = rotation("M 6 -6 L 6 6 M 11 -6 L 11 6");
+        let many = rotation("M 0 -6 L 12 0 L 0 6");
+        assert!(svg.contains("<circle cx=\"17\" cy=\"0\" r=\"4\""));
+        assert!((one - edge_endpoint_angle(&edge.points, true)).abs() < 0.01);
+        assert!((many - (end_angle + 180.0)).abs() < 0.01);
+    }
+
+    #[test]
+    fn render_minimap_draws_one_rect_per_node() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nA[Alpha] --> B[Beta]\nB --> C[Gamma]")
+                .unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let minimap = render_minimap(&layout, 0.1);
+        assert_eq!(minimap.matches("<rect ").count(), layout.nodes.len());
+        assert!(!minimap.contains("<text"));
+        assert!(!minimap.contains("Alpha"));
+        let [_, _, width, height] = viewbox(&minimap)[..] else {
+            panic!("minimap viewBox");
+        };
+        assert!((width - layout.width * 0.1).abs() < 0.01);
+        assert!((height - layout.height * 0.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn render_edge_linejoin_and_linecap() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA --> B").unwrap();
+        let theme = Theme::modern();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        config.flowchart.edge_linejoin = crate::config::LineJoin::Bevel;
+        config.flowchart.edge_linecap = crate::config::LineCap::Square;
+        let svg = render_svg(&layout, &theme, &config);
+        let at = svg.find("class=\"edgePath\"").unwrap();
+        let path = &svg[at..at + svg[at..].find("/>").unwrap()];
+        assert!(path.contains("stroke-linejoin=\"bevel\""));
+        assert!(path.contains("stroke-linecap=\"square\""));
+    }
+
+    #[test]
+    fn render_journey_actor_legend() {
+        let source =
+            "journey\ntitle My day\nsection Work\nMake tea: 5: Me\nGo upstairs: 3: Me, Cat";
+        let parsed = crate::parser::parse_mermaid(source).unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let DiagramData::Journey(journey) = &layout.diagram else {
+            panic!("expected journey layout");
+        };
+        assert_eq!(journey.actors.len(), 2);
+        let svg = render_svg(&layout, &theme, &config);
+        for actor in &journey.actors {
+            let swatch = format!("fill=\"{}\"", actor.color);
+            let at = svg.find(&swatch).expect("actor swatch");
+            let after = &svg[at..];
+            let text = &after[after.find("<text").unwrap()..];
+            let text = &text[..text.find("</text>").unwrap()];
+            assert!(text.ends_with(&format!(">{}", actor.name)));
+        }
+
+        let mut hidden = LayoutConfig::default();
+        hidden.journey.show_legend = false;
+        let hidden_layout = compute_layout(&parsed.graph, &theme, &hidden);
+        let svg = render_svg(&hidden_layout, &theme, &hidden);
+        assert!(!svg.contains(">Cat</text>"));
+        assert!(hidden_layout.height < layout.height);
+    }
+
+    #[test]
+    fn render_edge_midpoint_marker() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nA --> B\nlinkStyle 0 marker-mid:square")
+                .unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let edge = &layout.edges[0];
+        assert_eq!(
+            edge.override_style.midpoint_marker.as_deref(),
+            Some("square")
+        );
+        let (x, y) = edge_label_anchor_from_points(&edge.points).unwrap();
+        let svg = render_svg(&layout, &theme, &config);
+        let expected =
+            format!("<g class=\"edgeMidpointMarker\" transform=\"translate({x:.2} {y:.2})\"><rect");
+        assert!(svg.contains(&expected), "missing {expected}");
+    }
+
+    #[test]
+    fn render_decodes_html_entities_in_labels() {
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout_for = |source: &str| {
+            let parsed = crate::parser::parse_mermaid(source).unwrap();
+            compute_layout(&parsed.graph, &theme, &config)
+        };
+        let encoded = layout_for("flowchart LR\nX[\"A &amp; B\"]");
+        let plain = layout_for("flowchart LR\nX[\"A & B\"]");
+        assert_eq!(encoded.nodes["X"].label.lines, vec!["A & B"]);
+        assert_eq!(encoded.nodes["X"].label.width, plain.nodes["X"].label.width);
+        let svg = render_svg(&encoded, &theme, &config);
+        assert!(svg.contains(">A &amp; B<"));
+        assert!(!svg.contains("&amp;amp;"));
+    }
+
+    #[test]
+    fn render_text_baseline_offset_shifts_node_labels() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA[Hello]").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let label_y = |svg: &str| -> f32 {
+            let end = svg.find(">Hello<").expect("label rendered");
+            let start = svg[..end].rfind("<text").unwrap();
+            let attr = &svg[start..end];
+            let y = attr.find(" y=\"").unwrap() + 4;
+            attr[y..y + attr[y..].find('"').unwrap()].parse().unwrap()
+        };
+        let base = label_y(&render_svg(&layout, &theme, &config));
+        let render_config = RenderConfig {
+            text_baseline_offset: 3.5,
+            ..RenderConfig::default()
+        };
+        let shifted = label_y(&render_svg_with_config(
+            &layout,
+            &theme,
+            &config,
+            &render_config,
+        ));
+        assert!((shifted - base - 3.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn render_tight_viewbox_touches_content() {
+        let parsed = crate::parser::parse_mermaid("flowchart LR\nA --> B --> C").unwrap();
+        let theme = Theme::modern();
+        let config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        let padded = viewbox(&render_svg(&layout, &theme, &config));
+        let render_config = RenderConfig {
+            tight: true,
+            ..RenderConfig::default()
+        };
+        let tight = viewbox(&render_svg_with_config(
+            &layout,
+            &theme,
+            &config,
+            &render_config,
+        ));
+        assert!(tight[2] < padded[2]);
+        assert!(tight[3] < padded[3]);
+
+        let min_x = layout.nodes.values().map(|n| n.x).fold(f32::MAX, f32::min);
+        let min_y = layout.nodes.values().map(|n| n.y).fold(f32::MAX, f32::min);
+        let max_x = layout
+            .nodes
+            .values()
+            .map(|n| n.x + n.width)
+            .fold(f32::MIN, f32::max);
+        let max_y = layout
+            .nodes
+            .values()
+            .map(|n| n.y + n.height)
+            .fold(f32::MIN, f32::max);
+        assert!((tight[0] - min_x).abs() < 0.01);
+        assert!((tight[1] - min_y).abs() < 0.01);
+        assert!((tight[0] + tight[2] - max_x).abs() < 0.01);
+        assert!((tight[1] + tight[3] - max_y).abs() < 0.01);
+
+        // Radar data is not in the node list, so `tight` leaves it alone.
+        let parsed =
+            crate::parser::parse_mermaid("radar-beta\naxis a, b, c\ncurve x{1, 2, 3}\nmax 3")
+                .unwrap();
+        let layout = compute_layout(&parsed.graph, &theme, &config);
+        assert_eq!(
+            viewbox(&render_svg_with_config(
+                &layout,
+                &theme,
+                &config,
+                &render_config
+            )),
+            viewbox(&render_svg(&layout, &theme, &config))
+        );
+    }
+
+    #[test]
+    fn render_debug_node_ids() {
+        let parsed =
+            crate::parser::parse_mermaid("flowchart LR\nstart_node[Begin] --> end_node[Finish]")
+                .unwrap();
+        let mut config = LayoutConfig::default();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &config);
+        let plain = render_svg(&layout, &Theme::modern(), &config);
+        assert!(!plain.contains("node-id-debug"));
+
+        config.debug.show_node_ids = true;
+        let svg = render_svg(&layout, &Theme::modern(), &config);
+        assert!(svg.contains("Begin"));
+        assert!(svg.contains(">start_node</text>"));
+        assert!(svg.contains(">end_node</text>"));
+        let node = &layout.nodes["start_node"];
+        let expected = format!("class=\"node-id-debug\" x=\"{:.2}\"", node.x + 2.0);
+        assert!(svg.contains(&expected));
+    }
+
+    #[test]
+    fn render_dashed_edges_keep_end_markers() {
+        let parsed = crate::parser::parse_mermaid("classDiagram\nShape <|.. Circle").unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let svg = render_svg(&layout, &Theme::modern(), &LayoutConfig::default());
+        let edge_start = svg.find("<path id=\"edge-0\"").unwrap();
+        let edge_end = svg[edge_start..].find("/>").unwrap() + edge_start;
+        let edge = &svg[edge_start..edge_end];
+        assert!(edge.contains("stroke-dasharray=\"2\""));
+        assert!(edge.contains("url(#arrow-class-open-"));
+        // The hollow triangle marker keeps a solid outline of its own.
+        let marker_start = svg.find("<marker id=\"arrow-class-open-start-0\"").unwrap();
+        let marker_end = svg[marker_start..].find("</marker>").unwrap() + marker_start;
+        assert!(svg[marker_start..marker_end].contains("stroke-dasharray=\"1,0\""));
+
+        let parsed =
+            crate::parser::parse_mermaid("erDiagram\nCUSTOMER }o..o{ ORDER : places").unwrap();
+        let layout = compute_layout(&parsed.graph, &Theme::modern(), &LayoutConfig::default());
+        let svg = render_svg(&layout, &Theme::modern(), &LayoutConfig::default());
+        assert!(svg.contains("stroke-dasharray=\"2\""));
+        assert_eq!(svg.matches("M 0 -6 L 12 0 L 0 6").count(), 2);
+    }
+
     #[test]
     fn center_label_background_visibility_matches_diagram_kind() {
         let points = vec![(0.0, 0.0), (120.0, 0.0)];
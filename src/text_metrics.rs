@@ -9,6 +9,40 @@ use ttf_parser::{Face, GlyphId};
 
 static TEXT_MEASURER: Lazy<Mutex<TextMeasurer>> = Lazy::new(|| Mutex::new(TextMeasurer::new()));
 
+/// Emoji draw about twice as wide as an average text glyph, and usually come
+/// from a fallback color font rather than the measured face.
+const EMOJI_WIDTH_FACTOR: f32 = 1.12;
+
+/// Width factor for emoji code points, or `None` for ordinary text.
+/// Joiners, variation selectors and skin-tone modifiers fold into the
+/// preceding emoji and take no space; each regional indicator is half a flag.
+pub(crate) fn emoji_width_factor(ch: char) -> Option<f32> {
+    match ch as u32 {
+        0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F => Some(0.0),
+        0x1F1E6..=0x1F1FF => Some(EMOJI_WIDTH_FACTOR / 2.0),
+        0x2600..=0x27BF
+        | 0x2B00..=0x2BFF
+        | 0x1F000..=0x1F0FF
+        | 0x1F200..=0x1F2FF
+        | 0x1F300..=0x1F5FF
+        | 0x1F600..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F900..=0x1FAFF => Some(EMOJI_WIDTH_FACTOR),
+        _ => None,
+    }
+}
+
+/// Yields the characters of `text` that take space of their own, skipping
+/// those a zero-width joiner fuses into the preceding emoji.
+pub(crate) fn spaced_chars(text: &str) -> impl Iterator<Item = char> + '_ {
+    let mut after_joiner = false;
+    text.chars().filter(move |&ch| {
+        let keep = !after_joiner;
+        after_joiner = ch == '\u{200D}';
+        keep
+    })
+}
+
 pub fn measure_text_width(text: &str, font_size: f32, font_family: &str) -> Option<f32> {
     if text.is_empty() || font_size <= 0.0 {
         return Some(0.0);
@@ -219,10 +253,14 @@ impl FontFace {
         let scale = font_size / self.units_per_em as f32;
         let mut width = 0.0f32;
 
-        for ch in text.chars() {
+        for ch in spaced_chars(text) {
             if ch == '\n' {
                 continue;
             }
+            if let Some(factor) = emoji_width_factor(ch) {
+                width += factor * font_size;
+                continue;
+            }
             let glyph = if let Some(cached) = self.glyph_cache.get(&ch) {
                 *cached
             } else {
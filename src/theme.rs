@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ir::NodeStatus;
+
 const MERMAID_GIT_COLORS: [&str; 8] = [
     "hsl(240, 100%, 46.2745098039%)",
     "hsl(60, 100%, 43.5294117647%)",
@@ -33,7 +35,58 @@ const MERMAID_GIT_TAG_LABEL_BG: &str = "#ECECFF";
 const MERMAID_GIT_TAG_LABEL_BORDER: &str = "hsl(240, 60%, 86.2745098039%)";
 const MERMAID_TEXT_COLOR: &str = "#333";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+const SANKEY_COLORS: [&str; 10] = [
+    "#4e79a7", "#f28e2c", "#e15759", "#76b7b2", "#59a14f", "#edc949", "#af7aa1", "#ff9da7",
+    "#9c755f", "#bab0ab",
+];
+
+const DARK_GIT_COLORS: [&str; 8] = [
+    "#60A5FA", "#FBBF24", "#A3E635", "#38BDF8", "#2DD4BF", "#4ADE80", "#E879F9", "#F87171",
+];
+
+const DARK_GIT_INV_COLORS: [&str; 8] = [
+    "#1E3A8A", "#78350F", "#365314", "#0C4A6E", "#134E4A", "#14532D", "#701A75", "#7F1D1D",
+];
+
+const DARK_PIE_COLORS: [&str; 12] = [
+    "#3B82F6", "#F59E0B", "#10B981", "#8B5CF6", "#EF4444", "#06B6D4", "#EC4899", "#84CC16",
+    "#6366F1", "#F97316", "#14B8A6", "#A855F7",
+];
+
+const DARK_SANKEY_COLORS: [&str; 10] = [
+    "#7AA6D6", "#F6A95A", "#F07F81", "#8FD1CB", "#7BC470", "#F2D65E", "#C99BBE", "#FFB3BC",
+    "#C09A84", "#D4CCC7",
+];
+
+const DARK_TEXT_COLOR: &str = "#E2E8F0";
+
+/// Fills for nodes tagged with a [`crate::NodeStatus`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusColors {
+    pub error: String,
+    pub warn: String,
+    pub ok: String,
+}
+
+impl StatusColors {
+    fn new(error: &str, warn: &str, ok: &str) -> Self {
+        Self {
+            error: error.to_string(),
+            warn: warn.to_string(),
+            ok: ok.to_string(),
+        }
+    }
+
+    pub fn get(&self, status: NodeStatus) -> &str {
+        match status {
+            NodeStatus::Error => &self.error,
+            NodeStatus::Warn => &self.warn,
+            NodeStatus::Ok => &self.ok,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Theme {
     pub font_family: String,
     pub font_size: f32,
@@ -75,9 +128,22 @@ pub struct Theme {
     pub pie_outer_stroke_width: f32,
     pub pie_outer_stroke_color: String,
     pub pie_opacity: f32,
+    pub sankey_colors: [String; 10],
+    pub status_colors: StatusColors,
 }
 
 impl Theme {
+    /// Look up a built-in theme by the name used in config files and
+    /// `%%{init}%%` blocks.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "modern" => Some(Self::modern()),
+            "dark" => Some(Self::dark()),
+            "base" | "default" | "mermaid" => Some(Self::mermaid_default()),
+            _ => None,
+        }
+    }
+
     pub fn mermaid_default() -> Self {
         let primary_color = "#ECECFF".to_string();
         let secondary_color = "#FFFFDE".to_string();
@@ -124,6 +190,8 @@ impl Theme {
             pie_outer_stroke_width: 2.0,
             pie_outer_stroke_color: "#000000".to_string(),
             pie_opacity: 0.7,
+            sankey_colors: SANKEY_COLORS.map(|value| value.to_string()),
+            status_colors: StatusColors::new("#F87171", "#FBBF24", "#4ADE80"),
         }
     }
 
@@ -174,6 +242,58 @@ impl Theme {
             pie_outer_stroke_width: 1.6,
             pie_outer_stroke_color: "#CBD5E1".to_string(),
             pie_opacity: 0.85,
+            sankey_colors: SANKEY_COLORS.map(|value| value.to_string()),
+            status_colors: StatusColors::new("#F87171", "#FBBF24", "#4ADE80"),
+        }
+    }
+
+    /// Light text on a dark slate background, for sites with a dark color
+    /// scheme.
+    pub fn dark() -> Self {
+        Self {
+            font_family: "Inter, ui-sans-serif, system-ui, -apple-system, \"Segoe UI\", sans-serif"
+                .to_string(),
+            font_size: 14.0,
+            primary_color: "#1E293B".to_string(),
+            primary_text_color: DARK_TEXT_COLOR.to_string(),
+            primary_border_color: "#64748B".to_string(),
+            line_color: "#94A3B8".to_string(),
+            secondary_color: "#334155".to_string(),
+            tertiary_color: "#0F172A".to_string(),
+            edge_label_background: "#0F172A".to_string(),
+            cluster_background: "#172033".to_string(),
+            cluster_border: "#475569".to_string(),
+            background: "#0B1120".to_string(),
+            sequence_actor_fill: "#1E293B".to_string(),
+            sequence_actor_border: "#64748B".to_string(),
+            sequence_actor_line: "#475569".to_string(),
+            sequence_note_fill: "#422006".to_string(),
+            sequence_note_border: "#B45309".to_string(),
+            sequence_activation_fill: "#334155".to_string(),
+            sequence_activation_border: "#64748B".to_string(),
+            text_color: DARK_TEXT_COLOR.to_string(),
+            git_colors: DARK_GIT_COLORS.map(|value| value.to_string()),
+            git_inv_colors: DARK_GIT_INV_COLORS.map(|value| value.to_string()),
+            git_branch_label_colors: ["#0F172A"; 8].map(|value| value.to_string()),
+            git_commit_label_color: DARK_TEXT_COLOR.to_string(),
+            git_commit_label_background: "#1E293B".to_string(),
+            git_tag_label_color: DARK_TEXT_COLOR.to_string(),
+            git_tag_label_background: "#334155".to_string(),
+            git_tag_label_border: "#64748B".to_string(),
+            pie_colors: DARK_PIE_COLORS.map(|value| value.to_string()),
+            pie_title_text_size: 25.0,
+            pie_title_text_color: DARK_TEXT_COLOR.to_string(),
+            pie_section_text_size: 17.0,
+            pie_section_text_color: "#F8FAFC".to_string(),
+            pie_legend_text_size: 17.0,
+            pie_legend_text_color: DARK_TEXT_COLOR.to_string(),
+            pie_stroke_color: "#0B1120".to_string(),
+            pie_stroke_width: 1.6,
+            pie_outer_stroke_width: 1.6,
+            pie_outer_stroke_color: "#475569".to_string(),
+            pie_opacity: 0.9,
+            sankey_colors: DARK_SANKEY_COLORS.map(|value| value.to_string()),
+            status_colors: StatusColors::new("#B91C1C", "#B45309", "#15803D"),
         }
     }
 }
@@ -211,7 +331,7 @@ pub(crate) fn adjust_color(color: &str, delta_h: f32, delta_s: f32, delta_l: f32
 }
 
 pub(crate) fn parse_color_to_hsl(color: &str) -> Option<(f32, f32, f32)> {
-    let color = color.trim();
+    let color = css_var_fallback(color.trim());
     if let Some(hsl) = parse_hsl(color) {
         return Some(hsl);
     }
@@ -219,6 +339,16 @@ pub(crate) fn parse_color_to_hsl(color: &str) -> Option<(f32, f32, f32)> {
     Some(rgb_to_hsl(rgb.0, rgb.1, rgb.2))
 }
 
+/// The fallback of a `var(--name, fallback)` color, so color math works on
+/// the raw color behind a CSS custom property; other values pass through.
+fn css_var_fallback(color: &str) -> &str {
+    color
+        .strip_prefix("var(")
+        .and_then(|inner| inner.strip_suffix(')'))
+        .and_then(|inner| inner.split_once(','))
+        .map_or(color, |(_, fallback)| fallback.trim())
+}
+
 fn parse_hsl(value: &str) -> Option<(f32, f32, f32)> {
     let value = value.trim();
     let open = value.find('(')?;
@@ -1,4 +1,4 @@
-use mermaid_rs_renderer::{RenderOptions, render_with_options};
+use mermaid_rs_renderer::{BackgroundMode, RenderOptions, render_with_options};
 use serde::Deserialize;
 use wasm_bindgen::prelude::*;
 
@@ -8,15 +8,20 @@ struct MermaidRenderOptions {
     theme: Option<String>,
     font_family: Option<String>,
     font_size: Option<f32>,
+    background: Option<String>,
+    /// Use approximate text widths instead of font metrics, trading label
+    /// sizing accuracy for speed.
     fast_text: Option<bool>,
+    /// Accepted for compatibility. This build only produces SVG, so the value
+    /// has no effect.
     svg_only: Option<bool>,
 }
 
 fn build_render_options(options: MermaidRenderOptions) -> RenderOptions {
-    let mut render_options = if options.theme.as_deref() == Some("default") {
-        RenderOptions::mermaid_default()
-    } else {
-        RenderOptions::modern()
+    let mut render_options = match options.theme.as_deref() {
+        Some("default") => RenderOptions::mermaid_default(),
+        Some("dark") => RenderOptions::dark(),
+        _ => RenderOptions::modern(),
     };
 
     if let Some(font_family) = options.font_family {
@@ -25,8 +30,17 @@ fn build_render_options(options: MermaidRenderOptions) -> RenderOptions {
     if let Some(font_size) = options.font_size {
         render_options.theme.font_size = font_size;
     }
+    match options.background.as_deref() {
+        Some("transparent") => render_options = render_options.with_transparent_background(),
+        Some(color) => {
+            render_options.render.background = Some(BackgroundMode::Solid(color.to_string()));
+        }
+        None => {}
+    }
 
-    let _ = options.fast_text;
+    if let Some(fast_text) = options.fast_text {
+        render_options.layout.fast_text_metrics = fast_text;
+    }
     let _ = options.svg_only;
 
     render_options
@@ -47,7 +61,7 @@ pub fn render_mermaid_svg(code: &str, options_json: Option<String>) -> Result<St
 
 #[cfg(test)]
 mod tests {
-    use mermaid_rs_renderer::render_with_options;
+    use mermaid_rs_renderer::{Theme, render_with_options};
 
     use crate::{MermaidRenderOptions, build_render_options};
 
@@ -76,4 +90,44 @@ mod tests {
         assert!(svg.contains("yes"));
         assert!(svg.contains("no"));
     }
+
+    #[test]
+    fn dark_theme_option_selects_dark_palette() {
+        let options = MermaidRenderOptions {
+            theme: Some("dark".to_string()),
+            ..Default::default()
+        };
+        let render_options = build_render_options(options);
+        let svg = render_with_options("flowchart LR\n  A --> B", render_options)
+            .expect("dark flowchart should render");
+
+        assert!(svg.contains(&Theme::dark().background));
+    }
+
+    #[test]
+    fn transparent_background_option_skips_background_rect() {
+        let options = MermaidRenderOptions {
+            background: Some("transparent".to_string()),
+            ..Default::default()
+        };
+        let svg = render_with_options("flowchart LR\n  A --> B", build_render_options(options))
+            .expect("transparent flowchart should render");
+
+        assert!(!svg.contains(&format!("fill=\"{}\"/>", Theme::modern().background)));
+    }
+
+    #[test]
+    fn fast_text_option_enables_fast_text_metrics() {
+        let options = MermaidRenderOptions {
+            fast_text: Some(true),
+            ..Default::default()
+        };
+        let render_options = build_render_options(options);
+        assert!(render_options.layout.fast_text_metrics);
+        assert!(
+            !build_render_options(MermaidRenderOptions::default())
+                .layout
+                .fast_text_metrics
+        );
+    }
 }